@@ -0,0 +1,196 @@
+use crate::config::CompletionPolicy;
+use crate::date_parser::parse_date;
+use crate::error::{Result, TodoError};
+use crate::filter::{self, Filter, sort_tasks};
+use crate::output::{Summary, list_json};
+use crate::storage::{Completed, TaskStorage};
+use crate::task::{Task, TaskState, valid_priority, valid_tags};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Which tasks to list: the same options as `todo list`.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ListQuery {
+    /// A filter expression.
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Only tasks with this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Leave out tasks with this tag.
+    #[serde(default)]
+    pub exclude_tag: Option<String>,
+    /// Include completed tasks.
+    #[serde(default)]
+    pub completed: bool,
+}
+
+/// A task to add.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewTask {
+    description: String,
+    #[serde(default)]
+    priority: Option<u8>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+/// Changes to a task. Fields that are left out stay the same; `null` clears
+/// `priority`, `due` and `notes`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskUpdate {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    priority: Option<Option<u8>>,
+    #[serde(default, deserialize_with = "nullable")]
+    due: Option<Option<String>>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    #[serde(default)]
+    state: Option<TaskState>,
+    #[serde(default, deserialize_with = "nullable")]
+    notes: Option<Option<String>>,
+}
+
+/// Options for completing a task.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Completion {
+    /// Note recorded with the completion.
+    #[serde(default)]
+    pub(crate) note: Option<String>,
+}
+
+/// Tells a field set to `null` (`Some(None)`) apart from a missing one (`None`).
+fn nullable<'de, D, T>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// The same document as `todo list --output json`.
+pub fn list(storage: &TaskStorage, query: &ListQuery) -> Result<Value> {
+    let filter = query.filter.as_deref().map(Filter::parse).transpose()?;
+    let mut tasks = storage.get_filtered_tasks(
+        query.tag.as_deref(),
+        query.exclude_tag.as_deref(),
+        query.completed,
+    );
+    if let Some(filter) = &filter {
+        tasks.retain(|task| filter.matches(task));
+    }
+    sort_tasks(&mut tasks);
+    let summary = Summary {
+        shown: tasks.len(),
+        total: storage.tasks.len(),
+        completed: storage.tasks.iter().filter(|t| t.completed).count(),
+        overdue: storage.tasks.iter().filter(|t| t.is_overdue()).count(),
+    };
+    Ok(list_json(&tasks, summary))
+}
+
+/// Add a task and return its ID.
+pub fn create(storage: &mut TaskStorage, new: NewTask) -> Result<u64> {
+    if new.description.trim().is_empty() {
+        return Err(bad_request("description must not be empty"));
+    }
+    let mut task = Task::new(0, new.description);
+    task.priority = valid_priority(new.priority)?;
+    task.due_date = new.due.as_deref().map(parse_due).transpose()?;
+    task.tags = valid_tags(new.tags)?;
+    task.notes = new.notes;
+    Ok(storage.add_task(task))
+}
+
+/// Change the given fields of a task.
+pub fn apply_update(storage: &mut TaskStorage, id: u64, update: TaskUpdate) -> Result<()> {
+    let priority = update.priority.map(valid_priority).transpose()?;
+    let due_date = update
+        .due
+        .map(|due| due.as_deref().map(parse_due).transpose())
+        .transpose()?;
+    let tags = update.tags.map(valid_tags).transpose()?;
+    if update
+        .description
+        .as_ref()
+        .is_some_and(|description| description.trim().is_empty())
+    {
+        return Err(bad_request("description must not be empty"));
+    }
+
+    let task = storage
+        .get_task_mut(id)
+        .ok_or(TodoError::TaskNotFound(id))?;
+    if let Some(description) = update.description {
+        task.description = description;
+    }
+    if let Some(priority) = priority {
+        task.priority = priority;
+    }
+    if let Some(due_date) = due_date {
+        task.due_date = due_date;
+    }
+    if let Some(tags) = tags {
+        task.tags = tags;
+    }
+    if let Some(state) = update.state {
+        task.state = state;
+    }
+    if let Some(notes) = update.notes {
+        task.notes = notes;
+    }
+    Ok(())
+}
+
+/// Complete a task, handling its open subtasks as `policy` says. A note
+/// given for a task that is already completed replaces its note.
+pub fn complete(
+    storage: &mut TaskStorage,
+    id: u64,
+    completion: Completion,
+    policy: CompletionPolicy,
+) -> Result<Completed> {
+    storage.complete_tasks(&[id], completion.note.as_deref(), policy)
+}
+
+/// Reopen a task. Returns whether it was already open.
+pub fn reopen(storage: &mut TaskStorage, id: u64) -> Result<bool> {
+    Ok(!storage.reopen_tasks(&[id])?.is_empty())
+}
+
+/// Look up a task by ID or UUID prefix.
+pub fn task<'a>(storage: &'a TaskStorage, reference: &str) -> Result<&'a Task> {
+    let tasks: Vec<&Task> = storage.tasks.iter().collect();
+    filter::resolve_task(&tasks, reference)
+}
+
+/// Look up a task in the trash by ID or UUID prefix.
+pub fn trashed(storage: &TaskStorage, reference: &str) -> Result<Uuid> {
+    let tasks: Vec<&Task> = storage.trash.iter().map(|trashed| &trashed.task).collect();
+    filter::resolve_task(&tasks, reference).map(|task| task.uuid)
+}
+
+/// An error for a request that is malformed rather than one that failed.
+pub fn bad_request(message: &str) -> TodoError {
+    TodoError::BadRequest(message.to_string())
+}
+
+/// Due dates are RFC 3339 timestamps or anything `--due` accepts.
+fn parse_due(due: &str) -> Result<DateTime<Local>> {
+    match DateTime::parse_from_rfc3339(due) {
+        Ok(date) => Ok(date.with_timezone(&Local)),
+        Err(_) => parse_date(due),
+    }
+}
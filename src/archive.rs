@@ -0,0 +1,110 @@
+use crate::storage::TaskStorage;
+use crate::task::Task;
+use chrono::{DateTime, Duration, Local};
+use std::path::{Path, PathBuf};
+
+/// How long ago tasks must have been completed to be archived or purged
+/// when no `--before` date is given.
+pub const DEFAULT_AGE_DAYS: i64 = 30;
+
+/// Archive file kept next to the data file: `tasks.json` archives to
+/// `tasks.archive.json`.
+pub fn archive_path(data_path: &Path) -> PathBuf {
+    let stem = data_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "tasks".to_string());
+    data_path.with_file_name(format!("{}.archive.json", stem))
+}
+
+/// Cut-off used when no `--before` date is given.
+pub fn default_cutoff(now: DateTime<Local>) -> DateTime<Local> {
+    now - Duration::days(DEFAULT_AGE_DAYS)
+}
+
+/// IDs of tasks completed before `before`, in storage order.
+pub fn completed_before(tasks: &[Task], before: DateTime<Local>) -> Vec<u64> {
+    tasks
+        .iter()
+        .filter(|task| task.completed && task.completed_at.is_some_and(|at| at < before))
+        .map(|task| task.id)
+        .collect()
+}
+
+/// Move the tasks with the given IDs from `storage` to the end of `archive`.
+///
+/// Tasks keep their IDs; `storage` never hands out an ID again, so archived
+/// and active tasks can't collide.
+pub fn move_tasks(storage: &mut TaskStorage, archive: &mut TaskStorage, ids: &[u64]) {
+    let (moved, kept): (Vec<Task>, Vec<Task>) = std::mem::take(&mut storage.tasks)
+        .into_iter()
+        .partition(|task| ids.contains(&task.id));
+    storage.tasks = kept;
+    archive.tasks.extend(moved);
+    archive.next_id = archive.next_id.max(storage.next_id);
+}
+
+/// Remove the tasks with the given IDs for good.
+pub fn purge_tasks(storage: &mut TaskStorage, ids: &[u64]) -> Vec<Task> {
+    let (purged, kept): (Vec<Task>, Vec<Task>) = std::mem::take(&mut storage.tasks)
+        .into_iter()
+        .partition(|task| ids.contains(&task.id));
+    storage.tasks = kept;
+    purged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 9, day, 12, 0, 0).unwrap()
+    }
+
+    fn sample() -> TaskStorage {
+        let mut storage = TaskStorage::default();
+        for (name, completed) in [("Old", Some(1)), ("Open", None), ("Recent", Some(20))] {
+            let mut task = Task::new(0, name.to_string());
+            if let Some(day) = completed {
+                task.completed = true;
+                task.completed_at = Some(at(day));
+            }
+            storage.add_task(task);
+        }
+        storage
+    }
+
+    #[test]
+    fn test_archive_path() {
+        assert_eq!(
+            archive_path(Path::new("/home/me/.config/todo/tasks.json")),
+            PathBuf::from("/home/me/.config/todo/tasks.archive.json")
+        );
+        assert_eq!(
+            archive_path(Path::new("work.json")),
+            PathBuf::from("work.archive.json")
+        );
+    }
+
+    #[test]
+    fn test_move_and_purge() {
+        let mut storage = sample();
+        let ids = completed_before(&storage.tasks, at(10));
+        assert_eq!(ids, [1]);
+
+        let mut archive = TaskStorage::default();
+        move_tasks(&mut storage, &mut archive, &ids);
+        let ids_of = |storage: &TaskStorage| -> Vec<u64> {
+            storage.tasks.iter().map(|task| task.id).collect()
+        };
+        assert_eq!(ids_of(&storage), [2, 3]);
+        assert_eq!(ids_of(&archive), [1]);
+        assert_eq!(storage.next_id, 4);
+
+        let ids = completed_before(&storage.tasks, at(30));
+        let purged = purge_tasks(&mut storage, &ids);
+        assert_eq!(purged.len(), 1);
+        assert_eq!(ids_of(&storage), [2]);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::config::BoardConfig;
+use crate::renderer::task_style;
+use crate::table::{pad, truncate, wrap};
+use crate::task::{Task, TaskState};
+use crate::theme::{Style, theme};
+use chrono::{DateTime, Duration, Local};
+
+/// Completed tasks stay in the "done" column for this many days.
+pub const DONE_DAYS: i64 = 7;
+/// Narrowest a column is squeezed to; wider boards overflow the terminal.
+const MIN_COLUMN_WIDTH: usize = 12;
+/// Spaces between two columns.
+const COLUMN_GAP: usize = 2;
+
+/// One column of the board.
+#[derive(Debug)]
+pub struct BoardColumn<'a> {
+    /// Column name: a state (`started`) or a tag.
+    pub name: String,
+    /// Tasks in the column, in display order.
+    pub tasks: Vec<&'a Task>,
+    /// Work-in-progress limit from the config, if any.
+    pub limit: Option<usize>,
+}
+
+impl BoardColumn<'_> {
+    /// Whether the column holds more tasks than its WIP limit allows.
+    pub fn over_limit(&self) -> bool {
+        self.limit.is_some_and(|limit| self.tasks.len() > limit)
+    }
+}
+
+/// Columns for pending, started and waiting tasks, plus tasks completed in
+/// the last [`DONE_DAYS`] days.
+pub fn state_columns<'a>(
+    tasks: &[&'a Task],
+    config: &BoardConfig,
+    now: DateTime<Local>,
+) -> Vec<BoardColumn<'a>> {
+    let mut columns: Vec<BoardColumn> =
+        [TaskState::Pending, TaskState::Started, TaskState::Waiting]
+            .into_iter()
+            .map(|state| {
+                let mut column_tasks: Vec<&Task> = tasks
+                    .iter()
+                    .filter(|task| !task.completed && task.state == state)
+                    .copied()
+                    .collect();
+                column_tasks.sort();
+                column(state.as_str(), column_tasks, config)
+            })
+            .collect();
+
+    let since = now - Duration::days(DONE_DAYS);
+    let mut done: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.completed && task.completed_at.is_none_or(|at| at >= since))
+        .copied()
+        .collect();
+    done.sort_by_key(|task| std::cmp::Reverse(task.completed_at));
+    columns.push(column("done", done, config));
+
+    columns
+}
+
+/// One column per tag with the open tasks carrying it.
+///
+/// A task with several of the tags shows up in each of their columns.
+pub fn tag_columns<'a>(
+    tasks: &[&'a Task],
+    tags: &[String],
+    config: &BoardConfig,
+) -> Vec<BoardColumn<'a>> {
+    tags.iter()
+        .map(|tag| {
+            let mut column_tasks: Vec<&Task> = tasks
+                .iter()
+                .filter(|task| !task.completed && task.tags.contains(tag))
+                .copied()
+                .collect();
+            column_tasks.sort();
+            column(tag, column_tasks, config)
+        })
+        .collect()
+}
+
+fn column<'a>(name: &str, tasks: Vec<&'a Task>, config: &BoardConfig) -> BoardColumn<'a> {
+    BoardColumn {
+        name: name.to_string(),
+        tasks,
+        limit: config.wip_limits.get(name).copied(),
+    }
+}
+
+/// Render the board.
+pub fn render_board(columns: &[BoardColumn], width: usize) {
+    for line in format_board(columns, width) {
+        println!("{}", line);
+    }
+}
+
+/// Build the lines of the board without printing them.
+///
+/// Columns share the width equally. Each header shows the task count, and
+/// the limit as `count/limit` when one is configured; headers of columns
+/// over their limit end with `!` and use the overdue color.
+pub fn format_board(columns: &[BoardColumn], width: usize) -> Vec<String> {
+    if columns.is_empty() {
+        return Vec::new();
+    }
+
+    let theme = theme();
+    let gaps = COLUMN_GAP * (columns.len() - 1);
+    let column_width = (width.saturating_sub(gaps) / columns.len()).max(MIN_COLUMN_WIDTH);
+
+    let header = columns.iter().map(|column| {
+        let count = match column.limit {
+            Some(limit) => format!("{}/{}", column.tasks.len(), limit),
+            None => column.tasks.len().to_string(),
+        };
+        let marker = if column.over_limit() { " !" } else { "" };
+        let text = truncate(
+            &format!("{} {}{}", column.name.to_uppercase(), count, marker),
+            column_width,
+        );
+        let style = if column.over_limit() {
+            &theme.overdue
+        } else {
+            &theme.header
+        };
+        style.apply(&pad(&text, column_width)).to_string()
+    });
+    let rule = columns
+        .iter()
+        .map(|_| theme.muted.apply(&"─".repeat(column_width)).to_string());
+
+    let mut lines = vec![join_cells(header), join_cells(rule)];
+
+    let cells: Vec<Vec<(String, Style)>> = columns
+        .iter()
+        .map(|column| {
+            column
+                .tasks
+                .iter()
+                .flat_map(|task| {
+                    let style = task_style(task);
+                    wrap(&format!("{} {}", task.id, task.description), column_width)
+                        .into_iter()
+                        .map(move |line| (line, style.clone()))
+                })
+                .collect()
+        })
+        .collect();
+    let height = cells.iter().map(Vec::len).max().unwrap_or(0);
+
+    for line_index in 0..height {
+        lines.push(join_cells(cells.iter().map(
+            |cell| match cell.get(line_index) {
+                Some((text, style)) => style.apply(&pad(text, column_width)).to_string(),
+                None => " ".repeat(column_width),
+            },
+        )));
+    }
+
+    lines
+}
+
+fn join_cells(cells: impl Iterator<Item = String>) -> String {
+    cells
+        .collect::<Vec<_>>()
+        .join(&" ".repeat(COLUMN_GAP))
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: u64, description: &str, state: TaskState) -> Task {
+        let mut task = Task::new(id, description.to_string());
+        task.state = state;
+        task
+    }
+
+    #[test]
+    fn test_state_columns_and_limits() {
+        let now = Local::now();
+        let mut old = task(4, "Old", TaskState::Pending);
+        old.complete();
+        old.completed_at = Some(now - Duration::days(DONE_DAYS + 1));
+        let mut recent = task(5, "Recent", TaskState::Started);
+        recent.complete();
+        let tasks = [
+            task(1, "Plan", TaskState::Pending),
+            task(2, "Build", TaskState::Started),
+            task(3, "Review", TaskState::Started),
+            old,
+            recent,
+        ];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let config = BoardConfig {
+            wip_limits: [("started".to_string(), 1)].into(),
+        };
+
+        let columns = state_columns(&refs, &config, now);
+        let ids: Vec<Vec<u64>> = columns
+            .iter()
+            .map(|column| column.tasks.iter().map(|task| task.id).collect())
+            .collect();
+        assert_eq!(ids, vec![vec![1], vec![2, 3], vec![], vec![5]]);
+        assert!(columns[1].over_limit());
+        assert!(!columns[0].over_limit());
+    }
+
+    #[test]
+    fn test_format_board() {
+        colored::control::set_override(false);
+        let mut work = task(1, "Write the quarterly report", TaskState::Pending);
+        work.tags = vec!["work".to_string()];
+        let mut home = task(2, "Fix sink", TaskState::Pending);
+        home.tags = vec!["home".to_string(), "work".to_string()];
+        let tasks = [work, home];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let config = BoardConfig {
+            wip_limits: [("work".to_string(), 1)].into(),
+        };
+
+        let columns = tag_columns(&refs, &["work".to_string(), "home".to_string()], &config);
+        let lines = format_board(&columns, 34);
+        assert_eq!(
+            lines,
+            vec![
+                "WORK 2/1 !        HOME 1",
+                "────────────────  ────────────────",
+                "1 Write the       2 Fix sink",
+                "quarterly report",
+                "2 Fix sink",
+            ]
+        );
+    }
+}
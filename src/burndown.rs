@@ -0,0 +1,311 @@
+use crate::table::pad;
+use crate::task::Task;
+use crate::theme::theme;
+use chrono::{Days, NaiveDate};
+
+/// Widest a single day's column gets in the chart.
+const MAX_DAY_WIDTH: usize = 4;
+
+/// Which series the chart draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    /// Open tasks going down towards zero.
+    Burndown,
+    /// Completed tasks going up towards the total scope.
+    Burnup,
+}
+
+/// State of the tracked tasks at the end of one day.
+///
+/// Counts are `None` for days after today, which have no history yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayPoint {
+    /// The day.
+    pub date: NaiveDate,
+    /// Tasks created by the end of the day and not completed yet.
+    pub open: Option<usize>,
+    /// Tasks completed by the end of the day.
+    pub completed: Option<usize>,
+    /// Tasks created by the end of the day (the scope).
+    pub total: Option<usize>,
+    /// Where the chart's ideal line is on this day.
+    pub ideal: f64,
+}
+
+/// Reconstruct daily counts between `from` and `to` from task timestamps.
+///
+/// The ideal line goes straight from the first day's value to zero open
+/// tasks (burndown) or to the latest known scope (burnup) on the last day.
+/// A range that starts after today has no values yet, so its ideal line
+/// starts from today's.
+pub fn compute(
+    tasks: &[&Task],
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+    kind: ChartKind,
+) -> Vec<DayPoint> {
+    // Tasks created and completed by the end of a day.
+    let counts = |date: NaiveDate| {
+        let total = tasks
+            .iter()
+            .filter(|task| task.created_at.date_naive() <= date)
+            .count();
+        let completed = tasks
+            .iter()
+            .filter(|task| {
+                task.completed && task.completed_at.is_some_and(|at| at.date_naive() <= date)
+            })
+            .count();
+        (total, completed)
+    };
+    let mut points: Vec<DayPoint> = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| {
+            let known = date <= today;
+            let (total, completed) = counts(date);
+            DayPoint {
+                date,
+                open: known.then(|| total.saturating_sub(completed)),
+                completed: known.then_some(completed),
+                total: known.then_some(total),
+                ideal: 0.0,
+            }
+        })
+        .collect();
+
+    if points.is_empty() {
+        return points;
+    }
+    // The last known counts on or before a day.
+    let known = |date: NaiveDate| counts(date.min(today));
+    let (start, end) = match kind {
+        ChartKind::Burndown => {
+            let (total, completed) = known(from);
+            (total.saturating_sub(completed) as f64, 0.0)
+        }
+        ChartKind::Burnup => (known(from).1 as f64, known(to).0 as f64),
+    };
+    let steps = points.len().saturating_sub(1).max(1) as f64;
+    for (index, point) in points.iter_mut().enumerate() {
+        point.ideal = start + (end - start) * index as f64 / steps;
+    }
+    points
+}
+
+/// Render the chart.
+pub fn render_chart(points: &[DayPoint], kind: ChartKind, height: usize, width: usize) {
+    for line in format_chart(points, kind, height, width) {
+        println!("{}", line);
+    }
+}
+
+/// Build the lines of the chart without printing them.
+///
+/// Actual values are drawn as bars of `█` and the ideal line as `·`, or as
+/// `░` where it crosses a bar. Each day gets between 1 and 4 columns
+/// depending on `width`.
+pub fn format_chart(
+    points: &[DayPoint],
+    kind: ChartKind,
+    height: usize,
+    width: usize,
+) -> Vec<String> {
+    let theme = theme();
+    let height = height.max(2);
+    let values: Vec<Option<usize>> = points
+        .iter()
+        .map(|point| match kind {
+            ChartKind::Burndown => point.open,
+            ChartKind::Burnup => point.completed,
+        })
+        .collect();
+    let max = values
+        .iter()
+        .flatten()
+        .map(|&value| value as f64)
+        .chain(points.iter().map(|point| point.ideal))
+        .fold(1.0, f64::max)
+        .ceil();
+
+    let axis_width = format!("{}", max).len();
+    let day_width =
+        (width.saturating_sub(axis_width + 2) / points.len().max(1)).clamp(1, MAX_DAY_WIDTH);
+    let bar_width = day_width.saturating_sub(1).max(1);
+    let level = |value: f64| (value / max * height as f64).round() as usize;
+
+    let title = match kind {
+        ChartKind::Burndown => "Open tasks",
+        ChartKind::Burnup => "Completed tasks",
+    };
+    let mut lines = vec![theme.header.apply(title).to_string()];
+
+    for row in (1..=height).rev() {
+        let label = if row == height {
+            format!("{:>w$}", max, w = axis_width)
+        } else {
+            " ".repeat(axis_width)
+        };
+        let mut line = format!("{} {}", theme.muted.apply(&label), theme.muted.apply("│"));
+        for (point, value) in points.iter().zip(&values) {
+            let on_ideal = level(point.ideal) == row;
+            let cell = match value {
+                Some(value) if level(*value as f64) >= row => {
+                    let block = if on_ideal { "░" } else { "█" };
+                    theme.success.apply(&block.repeat(bar_width)).to_string()
+                }
+                _ if on_ideal => theme.muted.apply(&pad("·", bar_width)).to_string(),
+                _ => " ".repeat(bar_width),
+            };
+            line.push_str(&cell);
+            line.push_str(&" ".repeat(day_width - bar_width));
+        }
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines.push(format!(
+        "{} {}{}",
+        theme.muted.apply(&format!("{:>w$}", 0, w = axis_width)),
+        theme.muted.apply("└"),
+        theme.muted.apply(&"─".repeat(points.len() * day_width))
+    ));
+
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        let first_label = first.date.format("%b %-d").to_string();
+        let last_label = last.date.format("%b %-d").to_string();
+        let span = points.len() * day_width;
+        let gap = span.saturating_sub(first_label.len() + last_label.len());
+        lines.push(format!(
+            "{}{}{}{}",
+            " ".repeat(axis_width + 2),
+            first_label,
+            " ".repeat(gap.max(1)),
+            if first.date == last.date {
+                ""
+            } else {
+                &last_label
+            }
+        ));
+    }
+
+    lines
+}
+
+/// Daily counts as CSV with a header row.
+///
+/// Days after today have empty counts; the ideal value has one decimal.
+pub fn to_csv(points: &[DayPoint]) -> String {
+    let count = |value: Option<usize>| value.map(|v| v.to_string()).unwrap_or_default();
+    let mut csv = String::from("date,open,completed,total,ideal\n");
+    for point in points {
+        csv.push_str(&format!(
+            "{},{},{},{},{:.1}\n",
+            point.date,
+            count(point.open),
+            count(point.completed),
+            count(point.total),
+            point.ideal
+        ));
+    }
+    csv
+}
+
+/// Default last day of the chart: today, or the week after `from` if that is later.
+pub fn default_to(from: NaiveDate, today: NaiveDate) -> NaiveDate {
+    today.max(from + Days::new(6))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 7, d).unwrap()
+    }
+
+    fn task(id: u64, created: u32, completed: Option<u32>) -> Task {
+        let mut task = Task::new(id, format!("Task {}", id));
+        task.created_at = Local.with_ymd_and_hms(2025, 7, created, 9, 0, 0).unwrap();
+        if let Some(day) = completed {
+            task.completed = true;
+            task.completed_at = Some(Local.with_ymd_and_hms(2025, 7, day, 17, 0, 0).unwrap());
+        }
+        task
+    }
+
+    fn sample() -> Vec<Task> {
+        vec![
+            task(1, 7, Some(8)),
+            task(2, 7, Some(10)),
+            task(3, 7, None),
+            task(4, 9, None),
+        ]
+    }
+
+    #[test]
+    fn test_reconstructs_daily_counts() {
+        let tasks = sample();
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let points = compute(&refs, date(7), date(11), date(10), ChartKind::Burndown);
+
+        let open: Vec<Option<usize>> = points.iter().map(|p| p.open).collect();
+        assert_eq!(open, vec![Some(3), Some(2), Some(3), Some(2), None]);
+        let ideal: Vec<f64> = points.iter().map(|p| p.ideal).collect();
+        assert_eq!(ideal, vec![3.0, 2.25, 1.5, 0.75, 0.0]);
+
+        let points = compute(&refs, date(7), date(11), date(10), ChartKind::Burnup);
+        assert_eq!(points[0].ideal, 0.0);
+        assert_eq!(points[4].ideal, 4.0);
+    }
+
+    #[test]
+    fn test_range_after_today_starts_from_today() {
+        let tasks = sample();
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let points = compute(&refs, date(12), date(14), date(10), ChartKind::Burndown);
+
+        assert!(points.iter().all(|p| p.open.is_none()));
+        let ideal: Vec<f64> = points.iter().map(|p| p.ideal).collect();
+        assert_eq!(ideal, vec![2.0, 1.0, 0.0]);
+
+        let points = compute(&refs, date(12), date(14), date(10), ChartKind::Burnup);
+        let ideal: Vec<f64> = points.iter().map(|p| p.ideal).collect();
+        assert_eq!(ideal, vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_csv() {
+        let tasks = sample();
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let points = compute(&refs, date(9), date(11), date(10), ChartKind::Burndown);
+        assert_eq!(
+            to_csv(&points),
+            "date,open,completed,total,ideal\n\
+             2025-07-09,3,1,4,3.0\n\
+             2025-07-10,2,2,4,1.5\n\
+             2025-07-11,,,,0.0\n"
+        );
+    }
+
+    #[test]
+    fn test_chart() {
+        colored::control::set_override(false);
+        let tasks = sample();
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let points = compute(&refs, date(7), date(10), date(10), ChartKind::Burndown);
+        let lines = format_chart(&points, ChartKind::Burndown, 3, 20);
+        assert_eq!(
+            lines,
+            vec![
+                "Open tasks",
+                "3 │░░░     ███",
+                "  │███ ░░░ ███ ███",
+                "  │███ ███ ░░░ ███",
+                "0 └────────────────",
+                "   Jul 7     Jul 10",
+            ]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::cli::CalendarView;
+use crate::renderer::task_style;
+use crate::table::{pad, truncate};
+use crate::task::Task;
+use crate::theme::{Style, theme};
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use std::collections::BTreeMap;
+
+/// Titles listed per day in the month view before "+N more".
+const MONTH_TITLES: usize = 3;
+/// Narrowest a day column gets, enough for "[31]".
+const MIN_CELL_WIDTH: usize = 4;
+/// Widest a day column gets on very wide terminals.
+const MAX_CELL_WIDTH: usize = 24;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Days covered by a calendar, from a Monday to a Sunday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalendarRange {
+    /// First day shown.
+    pub start: NaiveDate,
+    /// Last day shown (inclusive).
+    pub end: NaiveDate,
+    /// First day of the month being shown; `None` for the week view.
+    month: Option<NaiveDate>,
+}
+
+impl CalendarRange {
+    /// The month or week containing `anchor`. Weeks start on Monday.
+    pub fn new(view: CalendarView, anchor: NaiveDate) -> Self {
+        match view {
+            CalendarView::Month => {
+                let first = anchor.with_day(1).expect("every month has a first day");
+                let last = first
+                    .checked_add_months(Months::new(1))
+                    .and_then(|next| next.pred_opt())
+                    .unwrap_or(first);
+                Self {
+                    start: first.week(Weekday::Mon).first_day(),
+                    end: last.week(Weekday::Mon).last_day(),
+                    month: Some(first),
+                }
+            }
+            CalendarView::Week => {
+                let week = anchor.week(Weekday::Mon);
+                Self {
+                    start: week.first_day(),
+                    end: week.last_day(),
+                    month: None,
+                }
+            }
+        }
+    }
+
+    /// Heading such as "October 2026" or "Week 42: Oct 12 – Oct 18, 2026".
+    pub fn title(&self) -> String {
+        match self.month {
+            Some(first) => first.format("%B %Y").to_string(),
+            None => format!(
+                "Week {}: {} – {}",
+                self.start.iso_week().week(),
+                self.start.format("%b %-d"),
+                self.end.format("%b %-d, %Y")
+            ),
+        }
+    }
+
+    /// Whether `date` belongs to the shown month (always true for weeks).
+    fn in_focus(&self, date: NaiveDate) -> bool {
+        self.month
+            .is_none_or(|first| (date.year(), date.month()) == (first.year(), first.month()))
+    }
+}
+
+/// Group tasks due within `range` by their due day, highest priority first.
+pub fn tasks_by_day<'a>(
+    tasks: &[&'a Task],
+    range: &CalendarRange,
+) -> BTreeMap<NaiveDate, Vec<&'a Task>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        if let Some(due_date) = task.due_date {
+            let day = due_date.date_naive();
+            if (range.start..=range.end).contains(&day) {
+                days.entry(day).or_default().push(task);
+            }
+        }
+    }
+    for day_tasks in days.values_mut() {
+        day_tasks.sort_by_key(|task| (task.priority.unwrap_or(u8::MAX), task.id));
+    }
+    days
+}
+
+/// Render a calendar grid.
+pub fn render_calendar(
+    days: &BTreeMap<NaiveDate, Vec<&Task>>,
+    range: &CalendarRange,
+    today: NaiveDate,
+    width: usize,
+    titles: bool,
+) {
+    for line in format_calendar(days, range, today, width, titles) {
+        println!("{}", line);
+    }
+}
+
+/// Build the lines of a calendar grid without printing them.
+///
+/// Each day shows its number followed by either a task count or the task
+/// titles. Today is shown as `[18]`; past days with open tasks are marked
+/// `18!` and colored as overdue. The week view always lists every title;
+/// the month view lists titles only when `titles` is set.
+pub fn format_calendar(
+    days: &BTreeMap<NaiveDate, Vec<&Task>>,
+    range: &CalendarRange,
+    today: NaiveDate,
+    width: usize,
+    titles: bool,
+) -> Vec<String> {
+    let theme = theme();
+    let cell_width = (width.saturating_sub(WEEKDAYS.len() - 1) / WEEKDAYS.len())
+        .clamp(MIN_CELL_WIDTH, MAX_CELL_WIDTH);
+    let (titles, max_titles) = match range.month {
+        Some(_) => (titles, MONTH_TITLES),
+        None => (true, usize::MAX),
+    };
+
+    let mut lines = vec![theme.header.apply(&range.title()).to_string()];
+    lines.push(join_cells(WEEKDAYS.iter().map(|name| {
+        theme
+            .header
+            .apply(&pad(&truncate(name, cell_width), cell_width))
+            .to_string()
+    })));
+
+    let mut week_start = range.start;
+    while week_start <= range.end {
+        let cells: Vec<Vec<(String, Style)>> = (0..WEEKDAYS.len() as u64)
+            .map(|offset| {
+                let date = week_start + Days::new(offset);
+                let tasks = days.get(&date).map(Vec::as_slice).unwrap_or(&[]);
+                day_cell(date, tasks, range, today, cell_width, titles, max_titles)
+            })
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+        if week_start != range.start && range.month.is_some() {
+            lines.push(String::new());
+        }
+        for line_index in 0..height {
+            lines.push(join_cells(cells.iter().map(
+                |cell| match cell.get(line_index) {
+                    Some((text, style)) => style.apply(&pad(text, cell_width)).to_string(),
+                    None => " ".repeat(cell_width),
+                },
+            )));
+        }
+
+        week_start = week_start + Days::new(WEEKDAYS.len() as u64);
+    }
+
+    lines
+}
+
+/// Plain text and style of each line in one day's cell.
+fn day_cell(
+    date: NaiveDate,
+    tasks: &[&Task],
+    range: &CalendarRange,
+    today: NaiveDate,
+    width: usize,
+    titles: bool,
+    max_titles: usize,
+) -> Vec<(String, Style)> {
+    let theme = theme();
+    let overdue = date < today && tasks.iter().any(|task| !task.completed);
+
+    let label = if date == today {
+        (format!("[{}]", date.day()), theme.due_today.clone())
+    } else if overdue {
+        (format!("{}!", date.day()), theme.overdue.clone())
+    } else if range.in_focus(date) {
+        (date.day().to_string(), Style::default())
+    } else {
+        (date.day().to_string(), theme.muted.clone())
+    };
+
+    let mut lines = vec![label];
+    if tasks.is_empty() {
+        return lines;
+    }
+
+    if !titles {
+        let count = match tasks.len() {
+            1 => "1 task".to_string(),
+            n => format!("{} tasks", n),
+        };
+        let count = if count.len() <= width {
+            count
+        } else {
+            tasks.len().to_string()
+        };
+        let style = if overdue {
+            theme.overdue.clone()
+        } else {
+            theme.info.clone()
+        };
+        lines.push((count, style));
+        return lines;
+    }
+
+    let shown = if tasks.len() > max_titles {
+        max_titles - 1
+    } else {
+        tasks.len()
+    };
+    for task in &tasks[..shown] {
+        lines.push((truncate(&task.description, width), task_style(task)));
+    }
+    if shown < tasks.len() {
+        lines.push((
+            truncate(&format!("+{} more", tasks.len() - shown), width),
+            theme.muted.clone(),
+        ));
+    }
+    lines
+}
+
+fn join_cells(cells: impl Iterator<Item = String>) -> String {
+    cells.collect::<Vec<_>>().join(" ").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn task_due(id: u64, description: &str, due: NaiveDate) -> Task {
+        let mut task = Task::new(id, description.to_string());
+        task.due_date = Local
+            .from_local_datetime(&due.and_hms_opt(23, 59, 59).unwrap())
+            .single();
+        task
+    }
+
+    #[test]
+    fn test_ranges() {
+        let range = CalendarRange::new(CalendarView::Month, date(2026, 10, 18));
+        assert_eq!(range.start, date(2026, 9, 28));
+        assert_eq!(range.end, date(2026, 11, 1));
+        assert_eq!(range.title(), "October 2026");
+
+        let range = CalendarRange::new(CalendarView::Week, date(2026, 10, 18));
+        assert_eq!(range.start, date(2026, 10, 12));
+        assert_eq!(range.end, date(2026, 10, 18));
+        assert_eq!(range.title(), "Week 42: Oct 12 – Oct 18, 2026");
+    }
+
+    #[test]
+    fn test_month_counts_and_markers() {
+        colored::control::set_override(false);
+        let tasks = [
+            task_due(1, "Pay rent", date(2026, 10, 1)),
+            task_due(2, "Call mom", date(2026, 10, 18)),
+            task_due(3, "Dentist", date(2026, 10, 18)),
+            task_due(4, "Next year", date(2027, 10, 18)),
+        ];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let range = CalendarRange::new(CalendarView::Month, date(2026, 10, 18));
+        let days = tasks_by_day(&refs, &range);
+        assert_eq!(days.values().map(Vec::len).sum::<usize>(), 3);
+
+        let lines = format_calendar(&days, &range, date(2026, 10, 18), 70, false);
+        assert_eq!(lines[0], "October 2026");
+        assert_eq!(
+            lines[1],
+            "Mon       Tue       Wed       Thu       Fri       Sat       Sun"
+        );
+        assert_eq!(
+            lines[2],
+            "28        29        30        1!        2         3         4"
+        );
+        assert_eq!(lines[3], "                              1 task");
+        assert!(lines.contains(
+            &"12        13        14        15        16        17        [18]".to_string()
+        ));
+        assert!(lines.contains(&format!("{}2 tasks", " ".repeat(60))));
+    }
+
+    #[test]
+    fn test_week_lists_titles() {
+        colored::control::set_override(false);
+        let tasks = [
+            task_due(1, "Write the quarterly report", date(2026, 10, 13)),
+            task_due(2, "Gym", date(2026, 10, 13)),
+        ];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let range = CalendarRange::new(CalendarView::Week, date(2026, 10, 13));
+        let days = tasks_by_day(&refs, &range);
+
+        let lines = format_calendar(&days, &range, date(2026, 10, 12), 70, false);
+        assert_eq!(
+            lines[2],
+            "[12]      13        14        15        16        17        18"
+        );
+        assert_eq!(lines[3], "          Write th…");
+        assert_eq!(lines[4], "          Gym");
+        assert_eq!(lines.len(), 5);
+    }
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use crate::task::TaskState;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
 /// Command-line interface for the todo CLI application.
@@ -17,6 +18,18 @@ pub struct Cli {
     /// Path to the data file (overrides default location).
     #[arg(long, global = true)]
     pub data_file: Option<String>,
+
+    /// Path to the config file (overrides default location).
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// When to use colors (auto respects NO_COLOR and pipes).
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Output format for command results and errors.
+    #[arg(long = "output", global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
 }
 
 /// All supported subcommands for the todo CLI.
@@ -38,6 +51,10 @@ pub enum Commands {
         /// Tags for the task.
         #[arg(short, long)]
         tags: Vec<String>,
+
+        /// Make the task a subtask of this one (ID or UUID prefix).
+        #[arg(long)]
+        parent: Option<String>,
     },
 
     /// List tasks with optional filters.
@@ -53,40 +70,326 @@ pub enum Commands {
         /// Show completed tasks.
         #[arg(short, long)]
         completed: bool,
+
+        /// Only show tasks matching a filter expression, e.g. 'tag:work and due:before:friday'.
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// List archived tasks instead (see `todo archive`).
+        #[arg(long)]
+        archived: bool,
+
+        /// Show tasks as an aligned table.
+        #[arg(long)]
+        table: bool,
+
+        /// Table columns to show, comma separated (implies --table).
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<TableColumn>,
+
+        /// Table width in columns (defaults to the terminal width).
+        #[arg(long)]
+        width: Option<usize>,
+
+        /// Print each task with a template, e.g. '{id} {priority:P} {description}',
+        /// or the name of a template from the config file.
+        #[arg(long, conflicts_with_all = ["table", "columns"])]
+        format: Option<String>,
+    },
+
+    /// Show all details of a single task.
+    Show {
+        /// Task ID or UUID prefix.
+        task: String,
     },
 
-    /// Mark a task as complete.
+    /// Mark tasks as complete.
     Complete {
-        /// Task ID to complete.
-        id: u64,
+        /// Task IDs (3), ranges (8-12) or filter terms (tag:inbox).
+        #[arg(required_unless_present = "filter")]
+        targets: Vec<String>,
+
+        /// Filter expression selecting the tasks, e.g. 'tag:inbox and not started'.
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Note to keep with the completed tasks, e.g. how they were resolved.
+        #[arg(short, long)]
+        note: Option<String>,
+
+        /// Don't ask for confirmation when many tasks are affected.
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Mark completed tasks as open again.
+    Reopen {
+        /// Task IDs (3), ranges (8-12) or filter terms (tag:inbox).
+        #[arg(required_unless_present = "filter")]
+        targets: Vec<String>,
+
+        /// Filter expression selecting the tasks, e.g. 'completed and tag:release'.
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Don't ask for confirmation when many tasks are affected.
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// Edit an existing task.
     Edit {
-        /// Task ID to edit.
-        id: u64,
+        /// Task IDs (3), ranges (8-12) or filter terms (tag:inbox).
+        #[arg(required_unless_present = "filter")]
+        targets: Vec<String>,
+
+        /// Fields to change.
+        #[command(flatten)]
+        fields: EditFields,
+
+        /// Edit the task and its notes as a document in $VISUAL or $EDITOR.
+        #[arg(short, long, conflicts_with_all = ["fields", "filter", "yes"])]
+        interactive: bool,
+
+        /// Filter expression selecting the tasks, e.g. 'tag:inbox and not started'.
+        #[arg(long)]
+        filter: Option<String>,
 
-        /// New description.
+        /// Don't ask for confirmation when many tasks are affected.
         #[arg(short, long)]
-        description: Option<String>,
+        yes: bool,
+    },
+
+    /// Delete tasks.
+    Delete {
+        /// Task IDs (3), ranges (8-12) or filter terms (tag:inbox).
+        #[arg(required_unless_present = "filter")]
+        targets: Vec<String>,
 
-        /// New priority (1-5, 1 = highest).
+        /// Filter expression selecting the tasks, e.g. 'completed and completed:before:"30 days ago"'.
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Don't ask for confirmation when many tasks are affected.
         #[arg(short, long)]
-        priority: Option<u8>,
+        yes: bool,
+    },
+
+    /// Show or empty the trash of deleted tasks.
+    Trash {
+        /// What to do with the trash; lists it when omitted.
+        #[command(subcommand)]
+        action: Option<TrashAction>,
+    },
+
+    /// Move deleted tasks from the trash back into the task list.
+    Restore {
+        /// IDs or UUID prefixes of the deleted tasks.
+        #[arg(required = true)]
+        tasks: Vec<String>,
+    },
 
-        /// New due date.
+    /// Commit the data file to the sync repository, merge it with the remote and push.
+    Sync {
+        /// Commit and merge, but don't push.
         #[arg(long)]
-        due: Option<String>,
+        no_push: bool,
+    },
+
+    /// Serve the tasks over an HTTP/JSON API on localhost.
+    Serve {
+        /// Port to listen on (0 picks a free one).
+        #[arg(short, long, default_value_t = crate::server::DEFAULT_PORT)]
+        port: u16,
+    },
+
+    /// Answer JSON-RPC 2.0 requests on stdin, one per line, for editor plugins.
+    Rpc,
+
+    /// Merge two copies of the data file that diverged from a common ancestor.
+    Merge {
+        /// The common ancestor (an empty file means no tasks), our copy and
+        /// their copy. The result is written to OURS unless --into is given.
+        #[arg(required = true, num_args = 2..=3, value_names = ["BASE", "OURS", "THEIRS"])]
+        files: Vec<String>,
+        /// Write the result to this file instead of OURS.
+        #[arg(long, value_name = "FILE")]
+        into: Option<String>,
+        /// Ask which side to keep for each conflict.
+        #[arg(short, long, conflicts_with = "prefer")]
+        interactive: bool,
+        /// Resolve every conflict for this side.
+        #[arg(long, value_enum)]
+        prefer: Option<Prefer>,
+        /// Only report the changes and conflicts; write nothing.
+        #[arg(long)]
+        dry_run: bool,
+        /// Merge without a common ancestor: give only OURS and THEIRS. Every
+        /// difference is a conflict and no task is deleted.
+        #[arg(long)]
+        no_base: bool,
+    },
+
+    /// Renumber short IDs so open tasks are 1, 2, 3, ...; UUIDs stay the same.
+    Compact {
+        /// Only show which IDs would change.
+        #[arg(long)]
+        dry_run: bool,
+    },
 
-        /// Add tags (existing tags will be kept).
+    /// Move old completed tasks out of the task list into the archive file.
+    Archive {
+        /// Archive tasks completed before this date (default: 30 days ago).
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Only show which tasks would be archived.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Permanently delete old completed tasks from the task list and the archive.
+    Purge {
+        /// Purge tasks completed before this date (default: 30 days ago).
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Only show which tasks would be deleted.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Don't ask for confirmation.
         #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show tasks by due date in a month or week calendar.
+    Calendar {
+        /// Calendar view.
+        #[arg(value_enum, default_value_t = CalendarView::Month)]
+        view: CalendarView,
+
+        /// Day to show the calendar for (e.g. 2025-12-01, "in 1 month", "next friday").
+        #[arg(short, long)]
+        date: Option<String>,
+
+        /// Show task titles instead of counts in the month view.
+        #[arg(long)]
+        titles: bool,
+
+        /// Filter by tag.
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Include completed tasks.
+        #[arg(short, long)]
+        completed: bool,
+
+        /// Calendar width in columns (defaults to the terminal width).
+        #[arg(long)]
+        width: Option<usize>,
+    },
+
+    /// Show open tasks as a kanban board, one column per state or tag.
+    Board {
+        /// Use these tags as columns instead of pending/started/waiting/done.
+        #[arg(long, value_delimiter = ',')]
         tags: Vec<String>,
+
+        /// Only include tasks with this tag.
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Board width in columns (defaults to the terminal width).
+        #[arg(long)]
+        width: Option<usize>,
     },
 
-    /// Delete a task.
-    Delete {
-        /// Task ID to delete.
-        id: u64,
+    /// Show productivity statistics over a date range.
+    Stats {
+        /// First day of the range (defaults to 30 days or 12 weeks ago).
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day of the range (defaults to today).
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Group activity per day or per week.
+        #[arg(long, value_enum, default_value_t = StatsPeriod::Day)]
+        by: StatsPeriod,
+
+        /// Only include tasks with this tag.
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Chart width in columns (defaults to the terminal width).
+        #[arg(long)]
+        width: Option<usize>,
+    },
+
+    /// Chart open (or completed) tasks per day for a tag or the whole list.
+    Burndown {
+        /// Only include tasks with this tag, e.g. a sprint or project.
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// First day of the chart (defaults to when the first task was created).
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day of the chart (defaults to today, or a week after --from).
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Draw a burnup chart of completed tasks instead.
+        #[arg(long)]
+        burnup: bool,
+
+        /// Print the daily counts as CSV instead of a chart.
+        #[arg(long)]
+        csv: bool,
+
+        /// Chart height in lines.
+        #[arg(long, default_value_t = 12)]
+        height: usize,
+
+        /// Chart width in columns (defaults to the terminal width).
+        #[arg(long)]
+        width: Option<usize>,
+    },
+
+    /// List tags with task counts, or rename and merge them across all tasks.
+    Tags {
+        /// What to do with the tags; lists them when omitted.
+        #[command(subcommand)]
+        action: Option<TagsAction>,
+    },
+
+    /// Open the full-screen interactive task list.
+    Tui,
+
+    /// Start an interactive shell that keeps tasks loaded between commands.
+    Shell,
+
+    /// Export tasks to a file or stdout.
+    Export {
+        /// File to write to (defaults to stdout).
+        file: Option<String>,
+
+        /// Export format.
+        #[arg(short, long, value_enum, default_value_t = DataFormat::Json)]
+        format: DataFormat,
+    },
+
+    /// Import tasks from a file ("-" reads stdin).
+    Import {
+        /// File to read tasks from.
+        file: String,
+
+        /// Input format.
+        #[arg(short, long, value_enum, default_value_t = DataFormat::Json)]
+        format: DataFormat,
     },
 
     /// Generate shell completions for supported shells.
@@ -96,3 +399,292 @@ pub enum Commands {
         shell: Shell,
     },
 }
+
+/// How command results are printed.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored, human-readable text.
+    #[default]
+    Text,
+    /// A single pretty-printed JSON document.
+    Json,
+    /// Compact JSON, one object per line (one task per line for `list`).
+    Jsonl,
+}
+
+/// Whether to use colors in text output.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always color, even when piped or `NO_COLOR` is set.
+    Always,
+    /// Never color.
+    Never,
+}
+
+/// How `merge --prefer` resolves conflicts.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prefer {
+    /// Keep our version.
+    Ours,
+    /// Keep their version.
+    Theirs,
+    /// Keep the version that was changed last.
+    Latest,
+}
+
+/// File formats supported by `export` and `import`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    /// JSON array of tasks.
+    Json,
+    /// Emacs org-mode headlines.
+    Org,
+}
+
+/// Time span shown by `calendar`.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CalendarView {
+    /// The whole month, one row per week.
+    #[default]
+    Month,
+    /// A single week, with every task title.
+    Week,
+}
+
+/// Field changes accepted by `todo edit`.
+#[derive(Args, Debug)]
+#[group(id = "fields", multiple = true)]
+pub struct EditFields {
+    /// New description.
+    #[arg(short, long)]
+    pub description: Option<String>,
+
+    /// New priority (1-5, 1 = highest), or 'none' to clear it.
+    #[arg(short, long, value_parser = parse_priority_update)]
+    pub priority: Option<Update<u8>>,
+
+    /// Clear the priority.
+    #[arg(long, conflicts_with = "priority")]
+    pub no_priority: bool,
+
+    /// New due date, or 'none' to clear it.
+    #[arg(long, value_parser = parse_update)]
+    pub due: Option<Update<String>>,
+
+    /// Clear the due date.
+    #[arg(long, conflicts_with = "due")]
+    pub no_due: bool,
+
+    /// Add tags (existing tags will be kept).
+    #[arg(short, long, visible_alias = "add-tag")]
+    pub tags: Vec<String>,
+
+    /// Remove tags.
+    #[arg(long)]
+    pub remove_tag: Vec<String>,
+
+    /// Replace all tags with a comma-separated list ("" removes them all).
+    #[arg(long, conflicts_with_all = ["tags", "remove_tag"])]
+    pub set_tags: Option<String>,
+
+    /// Remove all tags.
+    #[arg(long, conflicts_with = "set_tags")]
+    pub no_tags: bool,
+
+    /// Remove the notes.
+    #[arg(long)]
+    pub no_notes: bool,
+
+    /// Make the tasks subtasks of this one (ID or UUID prefix), or 'none' to
+    /// make them top-level tasks.
+    #[arg(long, value_parser = parse_update)]
+    pub parent: Option<Update<String>>,
+
+    /// Make the tasks top-level tasks.
+    #[arg(long, conflicts_with = "parent")]
+    pub no_parent: bool,
+
+    /// New progress state of an open task.
+    #[arg(short, long, value_enum)]
+    pub state: Option<TaskState>,
+}
+
+/// New value for an optional task field, given as `none` to clear it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Update<T> {
+    /// Set the field to this value.
+    Set(T),
+    /// Clear the field.
+    Clear,
+}
+
+impl<T> Update<T> {
+    /// The field's new value: `None` when it is cleared.
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            Update::Set(value) => Some(value),
+            Update::Clear => None,
+        }
+    }
+}
+
+fn parse_update(value: &str) -> Result<Update<String>, String> {
+    Ok(match value.trim().to_lowercase().as_str() {
+        "none" => Update::Clear,
+        _ => Update::Set(value.to_string()),
+    })
+}
+
+fn parse_priority_update(value: &str) -> Result<Update<u8>, String> {
+    match parse_update(value)? {
+        Update::Set(value) => value
+            .trim()
+            .parse()
+            .map(Update::Set)
+            .map_err(|_| format!("expected a number from 1 to 5 or 'none', got '{}'", value)),
+        Update::Clear => Ok(Update::Clear),
+    }
+}
+
+/// What `todo trash` does.
+#[derive(Subcommand, Debug)]
+pub enum TrashAction {
+    /// List deleted tasks, most recently deleted first (the default).
+    List,
+    /// Permanently delete everything in the trash.
+    Empty {
+        /// Don't ask for confirmation.
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+/// Changes `todo tags` can make across all tasks.
+#[derive(Subcommand, Debug)]
+pub enum TagsAction {
+    /// Rename a tag on every task that has it.
+    Rename {
+        /// Current tag name.
+        old: String,
+        /// New tag name; must not be in use yet.
+        new: String,
+    },
+    /// Fold one tag into another that may already be in use.
+    Merge {
+        /// Tag to remove.
+        from: String,
+        /// Tag the tasks get instead.
+        into: String,
+    },
+}
+
+/// Period used to group activity in `stats`.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatsPeriod {
+    /// One entry per day.
+    #[default]
+    Day,
+    /// One entry per week, starting on Monday.
+    Week,
+}
+
+/// Columns available in the table layout of `list`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableColumn {
+    /// Task ID.
+    Id,
+    /// Priority (P1-P5).
+    Priority,
+    /// done, overdue, or the state of an open task.
+    Status,
+    /// Task description, wrapped to fit.
+    Description,
+    /// Tags with a # prefix.
+    Tags,
+    /// Due date relative to today.
+    Due,
+    /// Time since the task was created.
+    Age,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit_fields(args: &[&str]) -> EditFields {
+        let cli = Cli::try_parse_from(["todo", "edit", "1"].iter().chain(args)).unwrap();
+        match cli.command {
+            Commands::Edit { fields, .. } => fields,
+            _ => panic!("expected the edit command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_update() {
+        assert_eq!(
+            parse_update("tomorrow"),
+            Ok(Update::Set("tomorrow".to_string()))
+        );
+        for value in ["none", "NONE", " None "] {
+            assert_eq!(parse_update(value), Ok(Update::Clear));
+        }
+    }
+
+    #[test]
+    fn test_parse_priority_update() {
+        assert_eq!(parse_priority_update("3"), Ok(Update::Set(3)));
+        assert_eq!(parse_priority_update("none"), Ok(Update::Clear));
+        assert_eq!(parse_priority_update("NONE"), Ok(Update::Clear));
+        assert_eq!(
+            parse_priority_update("high"),
+            Err("expected a number from 1 to 5 or 'none', got 'high'".to_string())
+        );
+        // The range is checked when the edit is applied.
+        assert_eq!(parse_priority_update("9"), Ok(Update::Set(9)));
+    }
+
+    #[test]
+    fn test_edit_clear_arguments() {
+        let fields = edit_fields(&["--due", "none", "--priority", "None"]);
+        assert_eq!(fields.due, Some(Update::Clear));
+        assert_eq!(fields.priority, Some(Update::Clear));
+
+        let fields = edit_fields(&["--no-due", "--no-priority", "--no-tags", "--no-notes"]);
+        assert!(fields.no_due && fields.no_priority && fields.no_tags && fields.no_notes);
+        assert_eq!(fields.due, None);
+
+        for args in [
+            &["--due", "friday", "--no-due"][..],
+            &["--priority", "2", "--no-priority"],
+            &["--set-tags", "a", "--no-tags"],
+            &["--priority", "high"],
+        ] {
+            let args = ["todo", "edit", "1"].iter().chain(args);
+            assert!(Cli::try_parse_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_merge_files() {
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(["todo", "merge"].iter().chain(args)).map(|cli| match cli.command {
+                Commands::Merge { files, no_base, .. } => (files, no_base),
+                _ => panic!("expected the merge command"),
+            })
+        };
+
+        let (files, no_base) = parse(&["base.json", "a.json", "b.json"]).unwrap();
+        assert_eq!(files, ["base.json", "a.json", "b.json"]);
+        assert!(!no_base);
+
+        let (files, no_base) = parse(&["--no-base", "a.json", "b.json"]).unwrap();
+        assert_eq!(files, ["a.json", "b.json"]);
+        assert!(no_base);
+
+        assert!(parse(&["a.json"]).is_err());
+        assert!(parse(&["base.json", "a.json", "b.json", "c.json"]).is_err());
+    }
+}
@@ -0,0 +1,1500 @@
+use crate::burndown::{self, ChartKind};
+use crate::calendar::{self, CalendarRange};
+use crate::cli::{
+    Cli, Commands, DataFormat, EditFields, OutputFormat, Prefer, TableColumn, TagsAction,
+    TrashAction, Update,
+};
+use crate::config::{Config, HooksConfig};
+use crate::date_parser::{parse_date, parse_date_from_words};
+use crate::error::{Result, TodoError, join_ids};
+use crate::filter::{self, Filter, Selection, sort_tasks};
+use crate::hooks::run_hooks;
+use crate::merge::{self, Merge, Side};
+use crate::output::{self, Summary, action_json, print_json, print_task_list};
+use crate::renderer::{
+    render_conflict, render_info, render_success, render_tag_counts, render_task_details,
+    render_task_list, render_trash, render_warning,
+};
+use crate::rpc::Rpc;
+use crate::server::Server;
+use crate::stats::{self, Stats};
+use crate::storage::{Completed, TaskStorage};
+use crate::sync::{self, Pull};
+use crate::task::{Task, TaskState, valid_priority, valid_tags};
+use crate::template::Template;
+use crate::{archive, board, editor, org, table, tui};
+use chrono::{DateTime, Local};
+use clap::CommandFactory;
+use clap_complete::generate;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Everything a command works on: the loaded tasks, where they are saved and
+/// the user's settings.
+pub struct Session {
+    /// Tasks loaded from the data file.
+    pub storage: TaskStorage,
+    /// Data file the tasks are saved to after each change.
+    pub data_path: PathBuf,
+    /// Settings from the config file.
+    pub config: Config,
+}
+
+/// Run a single command against the session, printing its result.
+///
+/// Commands that change tasks save the storage before returning.
+pub fn execute(session: &mut Session, command: Commands, format: OutputFormat) -> Result<()> {
+    let Session {
+        storage,
+        data_path,
+        config,
+    } = session;
+
+    match command {
+        Commands::Add {
+            description,
+            priority,
+            due,
+            tags,
+            parent,
+        } => {
+            // Parse tags from description words starting with '@' and parse date-like phrase
+            let mut desc_words = Vec::new();
+            let mut parsed_tags = tags;
+            let mut parsed_due = due;
+            for word in &description {
+                if word.starts_with('@') && word.len() > 1 {
+                    let tag = word[1..].to_string();
+                    if !parsed_tags.contains(&tag) {
+                        parsed_tags.push(tag);
+                    }
+                } else {
+                    desc_words.push(word.clone());
+                }
+            }
+            // Try to extract a date phrase from the remaining words if no due date was given
+            if parsed_due.is_none() {
+                let word_refs: Vec<&str> = desc_words.iter().map(|s| s.as_str()).collect();
+                if let Some(dt) = parse_date_from_words(&word_refs) {
+                    parsed_due = Some(dt.to_rfc3339());
+                    // Remove the date phrase from the description
+                    // (optional: not implemented here for simplicity)
+                }
+            }
+            let description = desc_words.join(" ").trim().to_string();
+            let parent = parent
+                .map(|reference| task_uuid(storage, &reference))
+                .transpose()?;
+            let mut updated = storage.clone();
+            let task_id = add_task(
+                &mut updated,
+                description,
+                priority,
+                parsed_due,
+                parsed_tags,
+                parent,
+            )?;
+            save_changes(storage, updated, data_path, &config.hooks)?;
+            if format == OutputFormat::Text {
+                render_info(&format!("Created task with ID: {}", task_id));
+                render_success("Task added successfully");
+            } else {
+                let task = storage
+                    .get_task(task_id)
+                    .ok_or(TodoError::TaskNotFound(task_id))?;
+                print_json(&action_json("add", task), format);
+            }
+        }
+
+        Commands::List {
+            tag,
+            exclude_tag,
+            completed,
+            filter,
+            archived,
+            table,
+            columns,
+            width,
+            format: template,
+        } => {
+            let filter = filter.as_deref().map(Filter::parse).transpose()?;
+            let layout = if let Some(template) = template {
+                let source = config.templates.get(&template).unwrap_or(&template);
+                Layout::Template(Template::parse(source)?)
+            } else if table || !columns.is_empty() {
+                let columns = if columns.is_empty() {
+                    table::DEFAULT_COLUMNS.to_vec()
+                } else {
+                    columns
+                };
+                Layout::Table {
+                    columns,
+                    width: width.unwrap_or_else(table::terminal_width),
+                }
+            } else {
+                Layout::Lines
+            };
+            let archive;
+            let source = if archived {
+                archive = TaskStorage::load_from_file(&archive::archive_path(data_path))?;
+                &archive
+            } else {
+                &*storage
+            };
+            list_tasks(
+                source,
+                tag.as_deref(),
+                exclude_tag.as_deref(),
+                completed || archived,
+                filter.as_ref(),
+                format,
+                &layout,
+            );
+        }
+
+        Commands::Show { task } => {
+            let tasks: Vec<&Task> = storage.tasks.iter().collect();
+            let task = filter::resolve_task(&tasks, &task)?;
+            match format {
+                OutputFormat::Text => render_task_details(task),
+                _ => print_json(&output::task_json(task), format),
+            }
+        }
+
+        Commands::Complete {
+            targets,
+            filter,
+            note,
+            yes,
+        } => {
+            let selection = Selection::parse(&targets, filter.as_deref())?;
+            let Some(ids) =
+                confirm_selection(storage, &selection, "Complete", yes, config, format)?
+            else {
+                return Ok(());
+            };
+
+            let mut updated = storage.clone();
+            let completed = updated.complete_tasks(
+                &ids,
+                note.as_deref(),
+                config.complete_with_open_subtasks,
+            )?;
+            save_changes(storage, updated, data_path, &config.hooks)?;
+            let already_completed = &completed.already_completed;
+
+            match (selection.single_id(&ids), format) {
+                (Some(id), OutputFormat::Text) => {
+                    if already_completed.is_empty() {
+                        render_success(&format!("Task {} marked as complete", id));
+                    } else if note.is_some() {
+                        render_info(&format!(
+                            "Task {} is already completed; updated its completion note",
+                            id
+                        ));
+                    } else {
+                        render_info(&format!("Task {} is already completed", id));
+                    }
+                }
+                (Some(id), _) => {
+                    let task = storage.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+                    print_json(&output::complete_json(task, &completed), format);
+                }
+                (None, OutputFormat::Text) => {
+                    render_success(&format!(
+                        "Completed {} tasks",
+                        ids.len() - already_completed.len()
+                    ));
+                    if !already_completed.is_empty() {
+                        let updated = if note.is_some() {
+                            "; updated their completion notes"
+                        } else {
+                            ""
+                        };
+                        render_info(&format!(
+                            "{} tasks were already completed{}",
+                            already_completed.len(),
+                            updated
+                        ));
+                    }
+                }
+                (None, _) => {
+                    let tasks = tasks_by_id(storage, &ids);
+                    print_json(&output::bulk_complete_json(&tasks, &completed), format);
+                }
+            }
+            if format == OutputFormat::Text {
+                render_subtasks(&completed);
+            }
+        }
+
+        Commands::Reopen {
+            targets,
+            filter,
+            yes,
+        } => {
+            let selection = Selection::parse(&targets, filter.as_deref())?;
+            let Some(ids) = confirm_selection(storage, &selection, "Reopen", yes, config, format)?
+            else {
+                return Ok(());
+            };
+
+            let mut updated = storage.clone();
+            let already_open = updated.reopen_tasks(&ids)?;
+            save_changes(storage, updated, data_path, &config.hooks)?;
+
+            match (selection.single_id(&ids), format) {
+                (Some(id), OutputFormat::Text) => {
+                    if already_open.is_empty() {
+                        render_success(&format!("Task {} reopened", id));
+                    } else {
+                        render_info(&format!("Task {} is not completed", id));
+                    }
+                }
+                (Some(id), _) => {
+                    let task = storage.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+                    let mut value = action_json("reopen", task);
+                    value["already_open"] = (!already_open.is_empty()).into();
+                    print_json(&value, format);
+                }
+                (None, OutputFormat::Text) => {
+                    render_success(&format!(
+                        "Reopened {} tasks",
+                        ids.len() - already_open.len()
+                    ));
+                    if !already_open.is_empty() {
+                        render_info(&format!("{} tasks were not completed", already_open.len()));
+                    }
+                }
+                (None, _) => {
+                    let mut value = output::bulk_action_json("reopen", &tasks_by_id(storage, &ids));
+                    value["already_open"] = already_open.into();
+                    print_json(&value, format);
+                }
+            }
+        }
+
+        Commands::Edit {
+            targets,
+            fields,
+            interactive,
+            filter,
+            yes,
+        } => {
+            if interactive {
+                return edit_interactively(storage, data_path, config, &targets, format);
+            }
+
+            let changes = TaskChanges::parse(fields, storage)?;
+            let selection = Selection::parse(&targets, filter.as_deref())?;
+            let Some(ids) = confirm_selection(storage, &selection, "Edit", yes, config, format)?
+            else {
+                return Ok(());
+            };
+
+            let mut updated = storage.clone();
+            for &id in &ids {
+                edit_task(&mut updated, id, &changes)?;
+            }
+            save_changes(storage, updated, data_path, &config.hooks)?;
+
+            match (selection.single_id(&ids), format) {
+                (Some(id), OutputFormat::Text) => {
+                    render_success(&format!("Task {} updated successfully", id));
+                }
+                (Some(id), _) => {
+                    let task = storage.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+                    print_json(&action_json("edit", task), format);
+                }
+                (None, OutputFormat::Text) => {
+                    render_success(&format!("Updated {} tasks", ids.len()));
+                }
+                (None, _) => {
+                    print_json(
+                        &output::bulk_action_json("edit", &tasks_by_id(storage, &ids)),
+                        format,
+                    );
+                }
+            }
+        }
+
+        Commands::Delete {
+            targets,
+            filter,
+            yes,
+        } => {
+            let selection = Selection::parse(&targets, filter.as_deref())?;
+            let Some(ids) = confirm_selection(storage, &selection, "Delete", yes, config, format)?
+            else {
+                return Ok(());
+            };
+
+            let mut updated = storage.clone();
+            let deleted = ids
+                .iter()
+                .map(|&id| updated.delete_task(id))
+                .collect::<Result<Vec<Task>>>()?;
+            save_changes(storage, updated, data_path, &config.hooks)?;
+
+            match (selection.single_id(&ids), format) {
+                (Some(id), OutputFormat::Text) => {
+                    render_success(&format!(
+                        "Task {} moved to the trash (undo with 'todo restore {}')",
+                        id, id
+                    ));
+                }
+                (Some(_), _) => print_json(&action_json("delete", &deleted[0]), format),
+                (None, OutputFormat::Text) => {
+                    render_success(&format!("Moved {} tasks to the trash", deleted.len()));
+                }
+                (None, _) => {
+                    let deleted: Vec<&Task> = deleted.iter().collect();
+                    print_json(&output::bulk_action_json("delete", &deleted), format);
+                }
+            }
+        }
+
+        Commands::Trash { action } => match action.unwrap_or(TrashAction::List) {
+            TrashAction::List => match format {
+                OutputFormat::Text => render_trash(&storage.trash),
+                _ => print_json(&output::trash_json(&storage.trash), format),
+            },
+            TrashAction::Empty { yes } => {
+                let tasks: Vec<&Task> = storage.trash.iter().map(|trashed| &trashed.task).collect();
+                let ids: Vec<u64> = tasks.iter().map(|task| task.id).collect();
+                if !ids.is_empty() && !confirm_tasks(&tasks, "Permanently delete", yes, 0, format)?
+                {
+                    return Ok(());
+                }
+
+                let mut updated = storage.clone();
+                updated.trash.clear();
+                save_all(storage, updated, data_path)?;
+                match format {
+                    OutputFormat::Text => {
+                        render_success(&format!("Deleted {} tasks for good", ids.len()))
+                    }
+                    _ => print_json(
+                        &output::cleanup_json("empty_trash", &ids, false, None),
+                        format,
+                    ),
+                }
+            }
+        },
+
+        Commands::Restore { tasks } => {
+            let trashed: Vec<&Task> = storage.trash.iter().map(|trashed| &trashed.task).collect();
+            let uuids = tasks
+                .iter()
+                .map(|reference| filter::resolve_task(&trashed, reference).map(|task| task.uuid))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut updated = storage.clone();
+            let mut ids = Vec::new();
+            for uuid in uuids {
+                ids.push(updated.restore_task(uuid)?.id);
+            }
+            save_changes(storage, updated, data_path, &config.hooks)?;
+
+            match (ids.as_slice(), format) {
+                ([id], OutputFormat::Text) => render_success(&format!("Task {} restored", id)),
+                ([id], _) => {
+                    let task = storage.get_task(*id).ok_or(TodoError::TaskNotFound(*id))?;
+                    print_json(&action_json("restore", task), format);
+                }
+                (_, OutputFormat::Text) => {
+                    render_success(&format!("Restored {} tasks", ids.len()));
+                }
+                (_, _) => print_json(
+                    &output::bulk_action_json("restore", &tasks_by_id(storage, &ids)),
+                    format,
+                ),
+            }
+        }
+
+        Commands::Sync { no_push } => {
+            let (synced, report) = sync::sync(storage, &config.sync, !no_push)?;
+            save_all(storage, synced, data_path)?;
+
+            match format {
+                OutputFormat::Text => print_sync_report(&report),
+                _ => print_json(&output::sync_json(&report), format),
+            }
+        }
+
+        Commands::Serve { port } => {
+            let token = config.server.token.clone().ok_or_else(|| {
+                TodoError::Config("set server.token in the config file to use 'todo serve'".into())
+            })?;
+            let server = Server::bind(
+                port,
+                data_path.clone(),
+                token,
+                config.hooks.clone(),
+                config.complete_with_open_subtasks,
+            )?;
+            let url = format!("http://127.0.0.1:{}", server.port());
+            match format {
+                OutputFormat::Text => render_info(&format!("Serving tasks on {}", url)),
+                _ => print_json(
+                    &serde_json::json!({ "action": "serve", "url": url }),
+                    format,
+                ),
+            }
+            io::stdout().flush()?;
+            server.run();
+        }
+
+        Commands::Rpc => {
+            Rpc::new(
+                data_path.clone(),
+                config.hooks.clone(),
+                config.complete_with_open_subtasks,
+                Box::new(io::stdout()),
+            )
+            .run(io::stdin().lock())?;
+        }
+
+        Commands::Merge {
+            files,
+            into,
+            interactive,
+            prefer,
+            dry_run,
+            no_base,
+        } => {
+            let (base, ours, theirs) = match (no_base, files.as_slice()) {
+                (false, [base, ours, theirs]) => (Some(base), ours, theirs),
+                (true, [ours, theirs]) => (None, ours, theirs),
+                (false, _) => {
+                    return Err(TodoError::Usage(
+                        "expected BASE, OURS and THEIRS; use --no-base to merge without a common ancestor"
+                            .to_string(),
+                    ));
+                }
+                (true, _) => {
+                    return Err(TodoError::Usage(
+                        "--no-base takes only OURS and THEIRS".to_string(),
+                    ));
+                }
+            };
+            let base = match base.map(Path::new) {
+                Some(base) if !base.exists() => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!(
+                            "merge base {} not found; use --no-base to merge without a common ancestor",
+                            base.display()
+                        ),
+                    )
+                    .into());
+                }
+                Some(base) => Some(TaskStorage::read_from_file(base)?),
+                None => None,
+            };
+            let ours = Path::new(ours);
+            let our_tasks = TaskStorage::read_from_file(ours)?;
+            let their_tasks = TaskStorage::read_from_file(Path::new(theirs))?;
+            let mut merged = merge::merge(base.as_ref(), &our_tasks, &their_tasks);
+
+            let side = match prefer {
+                Some(Prefer::Ours) => Some(Side::Ours),
+                Some(Prefer::Theirs) => Some(Side::Theirs),
+                Some(Prefer::Latest) | None => None,
+            };
+            for index in 0..merged.conflicts.len() {
+                if interactive {
+                    let side = ask_conflict_side(&merged.conflicts[index])?;
+                    merged.resolve(index, side);
+                } else if let Some(side) = side {
+                    merged.resolve(index, side);
+                }
+            }
+
+            let resolved = interactive || prefer.is_some() || merged.conflicts.is_empty();
+            let target = into.map_or_else(|| ours.to_path_buf(), PathBuf::from);
+            let written = !dry_run && resolved;
+            if written {
+                merged.storage.save_to_file(&target)?;
+                if same_file(&target, data_path) {
+                    *storage = TaskStorage::load_from_file(data_path)?;
+                }
+            }
+
+            let summary = merged.summary(&our_tasks);
+            let file = written.then_some(target.as_path());
+            match format {
+                OutputFormat::Text => print_merge_report(&merged, &summary, file, interactive),
+                _ => print_json(
+                    &output::merge_json(&summary, &merged.conflicts, file),
+                    format,
+                ),
+            }
+            if !dry_run && !resolved {
+                return Err(TodoError::MergeConflicts(merged.conflicts.len()));
+            }
+        }
+
+        Commands::Compact { dry_run } => {
+            let mut updated = storage.clone();
+            let archive = TaskStorage::load_from_file(&archive::archive_path(data_path))?;
+            let archived: Vec<u64> = archive.tasks.iter().map(|task| task.id).collect();
+            let changes = updated.compact_ids(&archived);
+            let uuids: Vec<String> = changes
+                .iter()
+                .filter_map(|&(old, _)| storage.get_task(old))
+                .map(|task| task.uuid.to_string())
+                .collect();
+            if !dry_run && !changes.is_empty() {
+                save_all(storage, updated, data_path)?;
+            }
+
+            match format {
+                OutputFormat::Text => {
+                    for &(old, new) in &changes {
+                        println!("{:>4} → {}", old, new);
+                    }
+                    let verb = if dry_run {
+                        "Would renumber"
+                    } else {
+                        "Renumbered"
+                    };
+                    render_info(&format!("{} {} tasks", verb, changes.len()));
+                }
+                _ => print_json(&output::compact_json(&changes, &uuids, dry_run), format),
+            }
+        }
+
+        Commands::Archive { before, dry_run } => {
+            let before = match before {
+                Some(date) => parse_date(&date)?,
+                None => archive::default_cutoff(Local::now()),
+            };
+            let ids = archive::completed_before(&storage.tasks, before);
+            let archive_path = archive::archive_path(data_path);
+
+            if !dry_run && !ids.is_empty() {
+                let mut archive = TaskStorage::load_from_file(&archive_path)?;
+                let mut updated = storage.clone();
+                archive::move_tasks(&mut updated, &mut archive, &ids);
+                // Write the archive first: if saving the task list then fails,
+                // the tasks are in both files rather than in neither.
+                archive.save_to_file(&archive_path)?;
+                save_all(storage, updated, data_path)?;
+            }
+
+            match format {
+                OutputFormat::Text if dry_run => {
+                    render_task_list(&tasks_by_id(storage, &ids));
+                    render_info(&format!("Would archive {} tasks", ids.len()));
+                }
+                OutputFormat::Text => render_success(&format!(
+                    "Archived {} tasks to {}",
+                    ids.len(),
+                    archive_path.display()
+                )),
+                _ => print_json(
+                    &output::cleanup_json("archive", &ids, dry_run, Some(&archive_path)),
+                    format,
+                ),
+            }
+        }
+
+        Commands::Purge {
+            before,
+            dry_run,
+            yes,
+        } => {
+            let before = match before {
+                Some(date) => parse_date(&date)?,
+                None => archive::default_cutoff(Local::now()),
+            };
+            let archive_path = archive::archive_path(data_path);
+            let mut archive = TaskStorage::load_from_file(&archive_path)?;
+            let active_ids = archive::completed_before(&storage.tasks, before);
+            let archived_ids = archive::completed_before(&archive.tasks, before);
+            let tasks: Vec<&Task> = tasks_by_id(storage, &active_ids)
+                .into_iter()
+                .chain(tasks_by_id(&archive, &archived_ids))
+                .collect();
+            let ids: Vec<u64> = tasks.iter().map(|task| task.id).collect();
+
+            if dry_run {
+                match format {
+                    OutputFormat::Text => {
+                        render_task_list(&tasks);
+                        render_info(&format!("Would delete {} tasks", ids.len()));
+                    }
+                    _ => print_json(&output::cleanup_json("purge", &ids, true, None), format),
+                }
+                return Ok(());
+            }
+            if ids.is_empty() {
+                match format {
+                    OutputFormat::Text => render_info("No tasks to purge"),
+                    _ => print_json(&output::cleanup_json("purge", &ids, false, None), format),
+                }
+                return Ok(());
+            }
+            if !confirm_tasks(&tasks, "Permanently delete", yes, 0, format)? {
+                return Ok(());
+            }
+
+            if !archived_ids.is_empty() {
+                archive::purge_tasks(&mut archive, &archived_ids);
+                archive.save_to_file(&archive_path)?;
+            }
+            if !active_ids.is_empty() {
+                let mut updated = storage.clone();
+                archive::purge_tasks(&mut updated, &active_ids);
+                save_all(storage, updated, data_path)?;
+            }
+
+            match format {
+                OutputFormat::Text => render_success(&format!("Deleted {} tasks", ids.len())),
+                _ => print_json(&output::cleanup_json("purge", &ids, false, None), format),
+            }
+        }
+
+        Commands::Calendar {
+            view,
+            date,
+            titles,
+            tag,
+            completed,
+            width,
+        } => {
+            let today = Local::now().date_naive();
+            let anchor = match date {
+                Some(phrase) => parse_date(&phrase)?.date_naive(),
+                None => today,
+            };
+            let range = CalendarRange::new(view, anchor);
+            let tasks = storage.get_filtered_tasks(tag.as_deref(), None, completed);
+            let days = calendar::tasks_by_day(&tasks, &range);
+
+            if format == OutputFormat::Text {
+                let width = width.unwrap_or_else(table::terminal_width);
+                calendar::render_calendar(&days, &range, today, width, titles);
+                let due: Vec<&Task> = days.values().flatten().copied().collect();
+                let overdue = due.iter().filter(|task| task.is_overdue()).count();
+                println!();
+                render_info(&format!("{} tasks due, {} overdue", due.len(), overdue));
+            } else {
+                print_json(
+                    &output::calendar_json(range.start, range.end, &days),
+                    format,
+                );
+            }
+        }
+
+        Commands::Board { tags, tag, width } => {
+            let tasks = storage.get_filtered_tasks(tag.as_deref(), None, true);
+            let columns = if tags.is_empty() {
+                board::state_columns(&tasks, &config.board, Local::now())
+            } else {
+                board::tag_columns(&tasks, &tags, &config.board)
+            };
+
+            if format == OutputFormat::Text {
+                board::render_board(&columns, width.unwrap_or_else(table::terminal_width));
+                for column in columns.iter().filter(|column| column.over_limit()) {
+                    render_warning(&format!(
+                        "'{}' has {} tasks, over its WIP limit of {}",
+                        column.name,
+                        column.tasks.len(),
+                        column.limit.unwrap_or_default()
+                    ));
+                }
+            } else {
+                print_json(&output::board_json(&columns), format);
+            }
+        }
+
+        Commands::Stats {
+            from,
+            to,
+            by,
+            tag,
+            width,
+        } => {
+            let to = match to {
+                Some(phrase) => parse_date(&phrase)?.date_naive(),
+                None => Local::now().date_naive(),
+            };
+            let from = match from {
+                Some(phrase) => parse_date(&phrase)?.date_naive(),
+                None => stats::default_from(to, by),
+            };
+            if from > to {
+                return Err(TodoError::DateParse(format!(
+                    "--from ({}) is after --to ({})",
+                    from, to
+                )));
+            }
+
+            let tasks = storage.get_filtered_tasks(tag.as_deref(), None, true);
+            let stats = Stats::compute(&tasks, from, to, by);
+            match format {
+                OutputFormat::Text => {
+                    stats::render_stats(&stats, width.unwrap_or_else(table::terminal_width))
+                }
+                _ => print_json(&output::stats_json(&stats), format),
+            }
+        }
+
+        Commands::Burndown {
+            tag,
+            from,
+            to,
+            burnup,
+            csv,
+            height,
+            width,
+        } => {
+            let today = Local::now().date_naive();
+            let tasks = storage.get_filtered_tasks(tag.as_deref(), None, true);
+            let from = match from {
+                Some(phrase) => parse_date(&phrase)?.date_naive(),
+                None => tasks
+                    .iter()
+                    .map(|task| task.created_at.date_naive())
+                    .min()
+                    .unwrap_or(today),
+            };
+            let to = match to {
+                Some(phrase) => parse_date(&phrase)?.date_naive(),
+                None => burndown::default_to(from, today),
+            };
+            if from > to {
+                return Err(TodoError::DateParse(format!(
+                    "--from ({}) is after --to ({})",
+                    from, to
+                )));
+            }
+
+            let kind = if burnup {
+                ChartKind::Burnup
+            } else {
+                ChartKind::Burndown
+            };
+            let points = burndown::compute(&tasks, from, to, today, kind);
+            if csv {
+                print!("{}", burndown::to_csv(&points));
+            } else if format == OutputFormat::Text {
+                let width = width.unwrap_or_else(table::terminal_width);
+                burndown::render_chart(&points, kind, height, width);
+            } else {
+                print_json(&output::burndown_json(&points), format);
+            }
+        }
+
+        Commands::Tags { action } => match action {
+            None => {
+                let counts = storage.tag_counts();
+                match format {
+                    OutputFormat::Text => render_tag_counts(&counts),
+                    _ => print_json(&output::tags_json(&counts), format),
+                }
+            }
+            Some(TagsAction::Rename { old, new }) => {
+                let mut updated = storage.clone();
+                let ids = updated.rename_tag(&old, &new)?;
+                save_changes(storage, updated, data_path, &config.hooks)?;
+                print_tag_change("rename_tag", &old, new.trim(), &ids, format);
+            }
+            Some(TagsAction::Merge { from, into }) => {
+                let mut updated = storage.clone();
+                let ids = updated.merge_tag(&from, &into)?;
+                save_changes(storage, updated, data_path, &config.hooks)?;
+                print_tag_change("merge_tag", &from, into.trim(), &ids, format);
+            }
+        },
+
+        Commands::Tui => {
+            *storage = tui::run_tui(
+                std::mem::take(storage),
+                data_path,
+                &config.hooks,
+                config.complete_with_open_subtasks,
+            )?;
+        }
+
+        Commands::Shell => {
+            crate::shell::run_shell(session, format)?;
+        }
+
+        Commands::Export {
+            file,
+            format: data_format,
+        } => {
+            export_tasks(storage, data_format, file.as_deref())?;
+        }
+
+        Commands::Import {
+            file,
+            format: data_format,
+        } => {
+            let mut updated = storage.clone();
+            let ids = import_tasks(&mut updated, &file, data_format)?;
+            save_changes(storage, updated, data_path, &config.hooks)?;
+            if format == OutputFormat::Text {
+                render_success(&format!("Imported {} tasks", ids.len()));
+            } else {
+                print_json(
+                    &serde_json::json!({ "action": "import", "ids": ids }),
+                    format,
+                );
+            }
+        }
+
+        Commands::Completions { shell } => {
+            generate_completions(shell);
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a new task to the storage and returns its ID.
+fn add_task(
+    storage: &mut TaskStorage,
+    description: String,
+    priority: Option<u8>,
+    due: Option<String>,
+    tags: Vec<String>,
+    parent: Option<Uuid>,
+) -> Result<u64> {
+    let mut task = Task::new(0, description); // ID will be set by storage
+    task.priority = valid_priority(priority)?;
+    task.tags = valid_tags(tags)?;
+    task.parent = parent;
+
+    // Parse due date if provided
+    if let Some(due_str) = due {
+        // Try RFC3339 first (for parse_date_from_words result)
+        if let Ok(dt) = DateTime::parse_from_rfc3339(&due_str) {
+            task.due_date = Some(dt.with_timezone(&Local));
+        } else {
+            task.due_date = Some(parse_date(&due_str)?);
+        }
+    }
+
+    Ok(storage.add_task(task))
+}
+
+/// How `list` lays out tasks in text output.
+enum Layout {
+    /// One line per task.
+    Lines,
+    /// Aligned table with the given columns, fitted to `width`.
+    Table {
+        columns: Vec<TableColumn>,
+        width: usize,
+    },
+    /// One line per task rendered from a template, without the summary.
+    Template(Template),
+}
+
+/// Lists tasks based on the provided filters.
+fn list_tasks(
+    storage: &TaskStorage,
+    include_tag: Option<&str>,
+    exclude_tag: Option<&str>,
+    show_completed: bool,
+    filter: Option<&Filter>,
+    format: OutputFormat,
+    layout: &Layout,
+) {
+    let mut tasks = storage.get_filtered_tasks(include_tag, exclude_tag, show_completed);
+    if let Some(filter) = filter {
+        tasks.retain(|task| filter.matches(task));
+    }
+    sort_tasks(&mut tasks);
+
+    let summary = Summary {
+        shown: tasks.len(),
+        total: storage.tasks.len(),
+        completed: storage.tasks.iter().filter(|t| t.completed).count(),
+        overdue: storage.tasks.iter().filter(|t| t.is_overdue()).count(),
+    };
+
+    if format != OutputFormat::Text {
+        print_task_list(&tasks, summary, format);
+        return;
+    }
+
+    if let Layout::Template(template) = layout {
+        for task in &tasks {
+            println!("{}", template.render(task));
+        }
+        return;
+    }
+
+    if tasks.is_empty() {
+        render_info("No tasks found matching the criteria");
+        return;
+    }
+
+    match layout {
+        Layout::Lines => render_task_list(&tasks),
+        Layout::Table { columns, width } => table::render_task_table(&tasks, columns, *width),
+        Layout::Template(_) => unreachable!("templates are rendered above"),
+    }
+
+    println!();
+    render_info(&format!(
+        "Showing {} tasks. Total: {}, Completed: {}, Overdue: {}",
+        summary.shown, summary.total, summary.completed, summary.overdue
+    ));
+}
+
+/// Field changes requested by `todo edit`, validated once for all selected tasks.
+///
+/// `priority`, `due_date`, `notes` and `parent` are `Some(None)` when the field
+/// is cleared.
+struct TaskChanges {
+    description: Option<String>,
+    priority: Option<Option<u8>>,
+    due_date: Option<Option<DateTime<Local>>>,
+    tags: Vec<String>,
+    remove_tags: Vec<String>,
+    set_tags: Option<Vec<String>>,
+    notes: Option<Option<String>>,
+    state: Option<TaskState>,
+    parent: Option<Option<Uuid>>,
+}
+
+impl TaskChanges {
+    fn parse(fields: EditFields, storage: &TaskStorage) -> Result<Self> {
+        let priority = if fields.no_priority {
+            Some(Update::Clear)
+        } else {
+            fields.priority
+        };
+        let due = if fields.no_due {
+            Some(Update::Clear)
+        } else {
+            fields.due
+        };
+        let parent = if fields.no_parent {
+            Some(Update::Clear)
+        } else {
+            fields.parent
+        };
+        let set_tags = if fields.no_tags {
+            Some(String::new())
+        } else {
+            fields.set_tags
+        };
+
+        let due_date = match due {
+            Some(Update::Set(due)) => Some(Some(parse_date(&due)?)),
+            Some(Update::Clear) => Some(None),
+            None => None,
+        };
+
+        Ok(Self {
+            description: fields.description,
+            priority: priority
+                .map(|update| valid_priority(update.into_option()))
+                .transpose()?,
+            due_date,
+            tags: valid_tags(&fields.tags)?,
+            remove_tags: fields
+                .remove_tag
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .collect(),
+            set_tags: set_tags
+                .map(|list| valid_tags(list.split(',').filter(|tag| !tag.trim().is_empty())))
+                .transpose()?,
+            notes: fields.no_notes.then_some(None),
+            state: fields.state,
+            parent: parent
+                .map(|parent| {
+                    parent
+                        .into_option()
+                        .map(|reference| task_uuid(storage, &reference))
+                        .transpose()
+                })
+                .transpose()?,
+        })
+    }
+}
+
+/// Edits an existing task in the storage.
+fn edit_task(storage: &mut TaskStorage, id: u64, changes: &TaskChanges) -> Result<()> {
+    if let Some(parent) = changes.parent {
+        storage.set_parent(id, parent)?;
+    }
+
+    let task = storage
+        .get_task_mut(id)
+        .ok_or(TodoError::TaskNotFound(id))?;
+
+    // Update description
+    if let Some(desc) = &changes.description {
+        task.description = desc.clone();
+    }
+
+    // Update or clear priority
+    if let Some(p) = changes.priority {
+        task.priority = p;
+    }
+
+    // Update or clear due date
+    if let Some(due_date) = changes.due_date {
+        task.due_date = due_date;
+    }
+
+    // Clear notes
+    if let Some(notes) = &changes.notes {
+        task.notes = notes.clone();
+    }
+
+    // Update progress state
+    if let Some(state) = changes.state {
+        task.state = state;
+    }
+
+    // Replace the tags, or add and remove individual ones
+    if let Some(tags) = &changes.set_tags {
+        task.tags.clear();
+        for tag in tags {
+            if !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+    }
+    for tag in &changes.tags {
+        if !task.tags.contains(tag) {
+            task.tags.push(tag.clone());
+        }
+    }
+    task.tags.retain(|tag| !changes.remove_tags.contains(tag));
+
+    Ok(())
+}
+
+/// Reports a tag rename or merge.
+fn print_tag_change(action: &str, from: &str, to: &str, ids: &[u64], format: OutputFormat) {
+    match format {
+        OutputFormat::Text => {
+            let change = if action == "rename_tag" {
+                format!("Renamed #{} to #{}", from, to)
+            } else {
+                format!("Merged #{} into #{}", from, to)
+            };
+            let tasks = match ids.len() {
+                1 => "1 task".to_string(),
+                n => format!("{} tasks", n),
+            };
+            render_success(&format!("{} on {}", change, tasks));
+        }
+        _ => print_json(&output::tag_change_json(action, from, to, ids), format),
+    }
+}
+
+/// Opens a single task in the user's editor and saves the result.
+///
+/// Completing the task there handles its open subtasks the way
+/// `todo complete` does.
+fn edit_interactively(
+    storage: &mut TaskStorage,
+    data_path: &Path,
+    config: &Config,
+    targets: &[String],
+    format: OutputFormat,
+) -> Result<()> {
+    let selection = Selection::parse(targets, None)?;
+    let id = selection
+        .single_id(&selection.select(&storage.tasks)?)
+        .ok_or_else(|| {
+            TodoError::Editor("--interactive edits one task ID at a time".to_string())
+        })?;
+    let task = storage.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+
+    let Some(document) = editor::edit_in_editor(task, &editor::editor_command())? else {
+        if format == OutputFormat::Text {
+            render_info(&format!("Task {} was not changed", id));
+        } else {
+            print_json(&action_json("edit", task), format);
+        }
+        return Ok(());
+    };
+
+    let mut updated = storage.clone();
+    let completed = document.apply(&mut updated, id, config.complete_with_open_subtasks)?;
+    save_changes(storage, updated, data_path, &config.hooks)?;
+
+    match format {
+        OutputFormat::Text => {
+            render_success(&format!("Task {} updated successfully", id));
+            render_subtasks(&completed);
+        }
+        _ => {
+            let task = storage.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+            print_json(&action_json("edit", task), format);
+        }
+    }
+    Ok(())
+}
+
+/// Resolves a selection to task IDs, asking for confirmation when more
+/// tasks would change than the config allows without asking.
+///
+/// Returns `None` if the user declined. Without a terminal to ask on (or
+/// with JSON output), too large a selection is an error unless `yes` is set.
+fn confirm_selection(
+    storage: &TaskStorage,
+    selection: &Selection,
+    verb: &str,
+    yes: bool,
+    config: &Config,
+    format: OutputFormat,
+) -> Result<Option<Vec<u64>>> {
+    let ids = selection.select(&storage.tasks)?;
+    let confirmed = confirm_tasks(
+        &tasks_by_id(storage, &ids),
+        verb,
+        yes,
+        config.confirm_threshold(),
+        format,
+    )?;
+    Ok(confirmed.then_some(ids))
+}
+
+/// Lists the tasks and asks whether to go ahead when there are more than
+/// `threshold` of them and `yes` isn't set.
+///
+/// Returns false if the user declined.
+fn confirm_tasks(
+    tasks: &[&Task],
+    verb: &str,
+    yes: bool,
+    threshold: usize,
+    format: OutputFormat,
+) -> Result<bool> {
+    if yes || tasks.len() <= threshold {
+        return Ok(true);
+    }
+    if format != OutputFormat::Text || !io::stdin().is_terminal() {
+        return Err(TodoError::ConfirmationRequired(tasks.len()));
+    }
+
+    render_task_list(tasks);
+    println!();
+    print!("{} these {} tasks? [y/N] ", verb, tasks.len());
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(true)
+    } else {
+        render_info("Cancelled, no tasks were changed");
+        Ok(false)
+    }
+}
+
+/// Ask which side to keep for a merge conflict.
+fn ask_conflict_side(conflict: &merge::Conflict) -> Result<Side> {
+    render_conflict(conflict);
+    loop {
+        print!("Keep [o]urs or [t]heirs? ");
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Err(TodoError::MergeConflicts(1));
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "o" | "ours" => return Ok(Side::Ours),
+            "t" | "theirs" => return Ok(Side::Theirs),
+            _ => {}
+        }
+    }
+}
+
+/// Describe what `todo merge` did, listing conflicts unless they were just
+/// answered interactively.
+fn print_merge_report(
+    merged: &Merge,
+    summary: &merge::MergeSummary,
+    file: Option<&Path>,
+    interactive: bool,
+) {
+    if !interactive {
+        for conflict in &merged.conflicts {
+            render_conflict(conflict);
+        }
+        if !merged.conflicts.is_empty() {
+            println!();
+        }
+    }
+    for (old, new) in &summary.renumbered {
+        render_warning(&format!("Their task {} is now task {}", old, new));
+    }
+
+    let changes = format!(
+        "{} added, {} updated, {} removed, {} conflicts",
+        summary.added,
+        summary.updated,
+        summary.removed,
+        merged.conflicts.len()
+    );
+    match file {
+        Some(path) => render_success(&format!("Merged into {}: {}", path.display(), changes)),
+        None => render_info(&format!("Nothing written: {}", changes)),
+    }
+}
+
+/// Whether two paths name the same file.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Describe what `todo sync` did.
+fn print_sync_report(report: &sync::SyncReport) {
+    if report.committed {
+        render_success("Committed local changes");
+    } else {
+        render_info("No local changes to commit");
+    }
+
+    let Some(remote) = &report.remote else {
+        render_info("No remote configured; set sync.remote or add an 'origin' remote");
+        return;
+    };
+    match &report.pull {
+        Some(Pull::Merged(summary)) => {
+            render_success(&format!(
+                "Merged changes from {}: {} added, {} updated, {} removed",
+                remote, summary.added, summary.updated, summary.removed
+            ));
+            for (old, new) in &summary.renumbered {
+                render_warning(&format!("Task {} from {} is now task {}", old, remote, new));
+            }
+        }
+        Some(Pull::FastForward) => render_success(&format!("Pulled changes from {}", remote)),
+        Some(Pull::UpToDate) => render_info(&format!("Already up to date with {}", remote)),
+        Some(Pull::Skipped) | None => {}
+    }
+    if report.pushed {
+        render_success(&format!("Pushed to {}", remote));
+    }
+}
+
+/// Saves a changed copy of the storage and, only if that succeeds, makes it
+/// the current one. Bulk commands change a copy so a failure part-way
+/// leaves both the file and the loaded tasks untouched.
+fn save_all(storage: &mut TaskStorage, mut updated: TaskStorage, data_path: &Path) -> Result<()> {
+    updated.save_to_file(data_path)?;
+    *storage = updated;
+    Ok(())
+}
+
+/// Runs the hooks for the tasks that changed, then saves like `save_all`.
+/// Nothing is saved if a hook rejects a change.
+fn save_changes(
+    storage: &mut TaskStorage,
+    mut updated: TaskStorage,
+    data_path: &Path,
+    hooks: &HooksConfig,
+) -> Result<()> {
+    run_hooks(hooks, &mut updated)?;
+    save_all(storage, updated, data_path)
+}
+
+/// Warns about subtasks a completion left open and lists the ones it
+/// completed along with their parents.
+fn render_subtasks(completed: &Completed) {
+    if !completed.open_subtasks.is_empty() {
+        render_warning(&format!(
+            "Left open subtasks: {}",
+            join_ids(&completed.open_subtasks)
+        ));
+    }
+    if !completed.cascaded.is_empty() {
+        render_info(&format!(
+            "Also completed subtasks: {}",
+            join_ids(&completed.cascaded)
+        ));
+    }
+}
+
+fn tasks_by_id<'a>(storage: &'a TaskStorage, ids: &[u64]) -> Vec<&'a Task> {
+    ids.iter().filter_map(|&id| storage.get_task(id)).collect()
+}
+
+/// UUID of the task an ID or UUID prefix refers to.
+fn task_uuid(storage: &TaskStorage, reference: &str) -> Result<Uuid> {
+    let tasks: Vec<&Task> = storage.tasks.iter().collect();
+    filter::resolve_task(&tasks, reference).map(|task| task.uuid)
+}
+
+/// Writes all tasks in the given format to a file, or stdout when no file is given.
+fn export_tasks(storage: &TaskStorage, format: DataFormat, output: Option<&str>) -> Result<()> {
+    let content = match format {
+        DataFormat::Json => serde_json::to_string_pretty(&storage.tasks)? + "\n",
+        DataFormat::Org => org::to_org(&storage.tasks),
+    };
+
+    match output {
+        Some(path) if path != "-" => {
+            fs::write(path, content)?;
+            render_success(&format!(
+                "Exported {} tasks to {}",
+                storage.tasks.len(),
+                path
+            ));
+        }
+        _ => io::stdout().write_all(content.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// Reads tasks from a file (or stdin for "-") and adds them with fresh IDs.
+///
+/// Returns the IDs of the imported tasks.
+fn import_tasks(storage: &mut TaskStorage, file: &str, format: DataFormat) -> Result<Vec<u64>> {
+    let content = if file == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        fs::read_to_string(file)?
+    };
+
+    let tasks: Vec<Task> = match format {
+        DataFormat::Json => serde_json::from_str(&content)?,
+        DataFormat::Org => org::from_org(&content)?,
+    };
+
+    Ok(tasks
+        .into_iter()
+        .map(|task| storage.add_task(task))
+        .collect())
+}
+
+/// Generates shell completions for the CLI.
+fn generate_completions(shell: clap_complete::Shell) {
+    let mut cmd = Cli::command();
+    let bin_name = "todo";
+
+    generate(shell, &mut cmd, bin_name, &mut io::stdout());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use chrono::TimeZone;
+    use clap::Parser;
+
+    /// Storage holding a subtask with every clearable field set.
+    fn sample_storage() -> TaskStorage {
+        let mut storage = TaskStorage::default();
+        let parent = storage.add_task(Task::new(0, "Plan the release".to_string()));
+        let mut task = Task::new(0, "Write report".to_string());
+        task.priority = Some(2);
+        task.due_date = Local.with_ymd_and_hms(2026, 10, 20, 17, 30, 0).single();
+        task.tags = vec!["work".to_string(), "q4".to_string()];
+        task.notes = Some("Ask Sam for the numbers.".to_string());
+        let id = storage.add_task(task);
+        let uuid = storage.get_task(parent).unwrap().uuid;
+        storage.set_parent(id, Some(uuid)).unwrap();
+        storage
+    }
+
+    fn parse_changes(storage: &TaskStorage, args: &[&str]) -> Result<TaskChanges> {
+        let cli = Cli::try_parse_from(["todo", "edit", "2"].iter().chain(args)).unwrap();
+        match cli.command {
+            Commands::Edit { fields, .. } => TaskChanges::parse(fields, storage),
+            _ => panic!("expected the edit command"),
+        }
+    }
+
+    fn edited(args: &[&str]) -> Task {
+        let mut storage = sample_storage();
+        let changes = parse_changes(&storage, args).unwrap();
+        edit_task(&mut storage, 2, &changes).unwrap();
+        storage.get_task(2).unwrap().clone()
+    }
+
+    #[test]
+    fn test_parse_changes() {
+        let storage = sample_storage();
+        let changes = parse_changes(&storage, &["--description", "Write memo"]).unwrap();
+        assert_eq!(changes.description.as_deref(), Some("Write memo"));
+        assert_eq!(changes.priority, None);
+        assert_eq!(changes.due_date, None);
+        assert_eq!(changes.set_tags, None);
+        assert_eq!(changes.notes, None);
+        assert_eq!(changes.parent, None);
+
+        for args in [&["--no-priority"][..], &["--priority", "none"]] {
+            let changes = parse_changes(&storage, args).unwrap();
+            assert_eq!(changes.priority, Some(None));
+        }
+        for args in [&["--no-due"][..], &["--due", "none"]] {
+            let changes = parse_changes(&storage, args).unwrap();
+            assert_eq!(changes.due_date, Some(None));
+        }
+        for args in [&["--no-parent"][..], &["--parent", "none"]] {
+            let changes = parse_changes(&storage, args).unwrap();
+            assert_eq!(changes.parent, Some(None));
+        }
+        let changes = parse_changes(&storage, &["--no-tags", "--no-notes"]).unwrap();
+        assert_eq!(changes.set_tags, Some(Vec::new()));
+        assert_eq!(changes.notes, Some(None));
+
+        assert!(matches!(
+            parse_changes(&storage, &["--priority", "9"]),
+            Err(TodoError::InvalidPriority(9))
+        ));
+        assert!(matches!(
+            parse_changes(&storage, &["--tags", " "]),
+            Err(TodoError::InvalidTag(_))
+        ));
+    }
+
+    #[test]
+    fn test_edit_clears_fields() {
+        for args in [&["--no-due"][..], &["--due", "none"]] {
+            let task = edited(args);
+            assert_eq!(task.due_date, None);
+            assert_eq!(task.priority, Some(2));
+        }
+        for args in [&["--no-priority"][..], &["--priority", "NONE"]] {
+            let task = edited(args);
+            assert_eq!(task.priority, None);
+            assert!(task.due_date.is_some());
+        }
+        for args in [&["--no-tags"][..], &["--set-tags", ""]] {
+            let task = edited(args);
+            assert!(task.tags.is_empty());
+            assert!(task.notes.is_some());
+        }
+
+        let task = edited(&["--no-notes"]);
+        assert_eq!(task.notes, None);
+        assert_eq!(task.tags, ["work", "q4"]);
+
+        let task = edited(&["--no-parent"]);
+        assert_eq!(task.parent, None);
+
+        let task = edited(&["--description", "Write memo"]);
+        assert_eq!(task.description, "Write memo");
+        assert_eq!(task.priority, Some(2));
+        assert!(task.due_date.is_some() && task.notes.is_some() && task.parent.is_some());
+    }
+}
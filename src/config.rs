@@ -0,0 +1,209 @@
+use crate::error::{Result, TodoError};
+use crate::theme::Theme;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default for `Config::confirm_threshold`.
+const DEFAULT_CONFIRM_THRESHOLD: usize = 5;
+/// Default for `Config::trash_retention_days`.
+const DEFAULT_TRASH_RETENTION_DAYS: u64 = 30;
+
+/// User settings read from the config file.
+///
+/// Every field is optional; a missing or empty file gives the defaults.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Name of the theme to use: a built-in (`default`, `plain`) or a key of `themes`.
+    pub theme: Option<String>,
+    /// User-defined themes.
+    pub themes: HashMap<String, Theme>,
+    /// Named output templates usable with `list --format <name>`.
+    pub templates: HashMap<String, String>,
+    /// Settings for `todo board`.
+    pub board: BoardConfig,
+    /// Bulk `complete`, `edit` and `delete` ask for confirmation when they
+    /// would change more tasks than this.
+    pub confirm_threshold: Option<usize>,
+    /// Deleted tasks are removed from the trash for good after this many days.
+    pub trash_retention_days: Option<u64>,
+    /// What completing a task with open subtasks does.
+    pub complete_with_open_subtasks: CompletionPolicy,
+    /// Settings for `todo sync`.
+    pub sync: SyncConfig,
+    /// Settings for `todo serve`.
+    pub server: ServerConfig,
+    /// Commands run when tasks change.
+    pub hooks: HooksConfig,
+}
+
+/// Commands run when tasks change, before the change is saved.
+///
+/// Each is a shell command, run with `sh -c` in order for each task the
+/// event applies to. See `hooks::run_hooks` for how they are called.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run for tasks that are added (or restored from the trash).
+    pub on_add: Vec<String>,
+    /// Run for tasks whose fields change, other than completing them.
+    pub on_modify: Vec<String>,
+    /// Run for tasks that are completed.
+    pub on_complete: Vec<String>,
+    /// Run for tasks that are moved to the trash.
+    pub on_delete: Vec<String>,
+    /// Seconds a hook may run before it is stopped and the change is
+    /// abandoned (default: 10).
+    pub timeout_secs: Option<u64>,
+}
+
+/// Settings for `todo serve`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Secret that API clients send as `Authorization: Bearer <token>`.
+    pub token: Option<String>,
+}
+
+/// What completing a task with open subtasks does.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionPolicy {
+    /// Refuse to complete the task.
+    Refuse,
+    /// Complete the task and warn about the subtasks left open.
+    #[default]
+    Warn,
+    /// Complete the open subtasks along with the task.
+    Cascade,
+}
+
+/// Settings for `todo sync`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Local git repository the data file is committed to.
+    pub repo: Option<PathBuf>,
+    /// Path of the tasks file inside the repository (default: `tasks.json`).
+    pub file: Option<PathBuf>,
+    /// Remote name or URL to pull from and push to (default: `origin`, if the
+    /// repository has it).
+    pub remote: Option<String>,
+    /// Branch to sync (default: the repository's current branch).
+    pub branch: Option<String>,
+}
+
+/// Settings for `todo board`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoardConfig {
+    /// Work-in-progress limits by column name (a state such as `started`, or a tag).
+    pub wip_limits: HashMap<String, usize>,
+}
+
+impl Config {
+    /// Load the config from a JSON file at the given path.
+    ///
+    /// Returns the default config if the file does not exist or is empty.
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        serde_json::from_str(&content)
+            .map_err(|e| TodoError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Number of tasks a bulk command may change without confirmation.
+    pub fn confirm_threshold(&self) -> usize {
+        self.confirm_threshold.unwrap_or(DEFAULT_CONFIRM_THRESHOLD)
+    }
+
+    /// Days a deleted task stays in the trash.
+    pub fn trash_retention_days(&self) -> u64 {
+        self.trash_retention_days
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+    }
+
+    /// Resolve the selected theme.
+    ///
+    /// User-defined themes take precedence over built-in ones with the same name.
+    pub fn resolve_theme(&self) -> Result<Theme> {
+        let Some(name) = self.theme.as_deref() else {
+            return Ok(Theme::default());
+        };
+
+        self.themes
+            .get(name)
+            .cloned()
+            .or_else(|| Theme::builtin(name))
+            .ok_or_else(|| TodoError::Config(format!("unknown theme '{}'", name)))
+    }
+}
+
+/// Get the config file path, prioritizing environment variable, then custom path, then default locations.
+pub fn get_config_file_path(custom_path: Option<&str>) -> PathBuf {
+    if let Ok(env_path) = std::env::var("TODO_CONFIG_FILE") {
+        return PathBuf::from(env_path);
+    }
+
+    if let Some(path) = custom_path {
+        return PathBuf::from(path);
+    }
+
+    if let Some(config_dir) = dirs::config_dir() {
+        return config_dir.join("todo").join("config.json");
+    }
+
+    if let Some(home_dir) = dirs::home_dir() {
+        return home_dir.join(".todo-config.json");
+    }
+
+    PathBuf::from("config.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_theme() {
+        let config: Config = serde_json::from_str(
+            r#"{"theme": "mine", "themes": {"mine": {"tag": "bold magenta", "emoji": false}}}"#,
+        )
+        .unwrap();
+        let theme = config.resolve_theme().unwrap();
+        assert!(!theme.emoji);
+        assert_eq!(theme.tag, "bold magenta".parse().unwrap());
+
+        let config: Config = serde_json::from_str(r#"{"theme": "plain"}"#).unwrap();
+        assert!(!config.resolve_theme().unwrap().emoji);
+
+        let config: Config = serde_json::from_str(r#"{"theme": "missing"}"#).unwrap();
+        assert!(matches!(config.resolve_theme(), Err(TodoError::Config(_))));
+    }
+
+    #[test]
+    fn test_completion_policy() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.complete_with_open_subtasks, CompletionPolicy::Warn);
+
+        let config: Config =
+            serde_json::from_str(r#"{"complete_with_open_subtasks": "cascade"}"#).unwrap();
+        assert_eq!(
+            config.complete_with_open_subtasks,
+            CompletionPolicy::Cascade
+        );
+
+        assert!(
+            serde_json::from_str::<Config>(r#"{"complete_with_open_subtasks": "ask"}"#).is_err()
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use crate::error::{Result, TodoError};
-use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone};
 
 /// Parse a date string into a `DateTime<Local>`.
 ///
 /// This function supports multiple formats:
-/// - ISO format: `YYYY-MM-DD`
+/// - ISO format: `YYYY-MM-DD`, optionally with a time: `YYYY-MM-DD HH:MM`
 /// - US/European formats: `MM/DD/YYYY`, `DD/MM/YYYY`, `YYYY/MM/DD`, `MM-DD-YYYY`, `DD-MM-YYYY`
 /// - Relative keywords: `today`, `tomorrow`
 /// - Natural language (via `chrono-english`): e.g. `next friday`, `in 2 days`
@@ -43,6 +43,12 @@ pub fn parse_date(date_str: &str) -> Result<DateTime<Local>> {
             .unwrap());
     }
 
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(&date_str, "%Y-%m-%d %H:%M")
+        && let Some(datetime) = Local.from_local_datetime(&datetime).earliest()
+    {
+        return Ok(datetime);
+    }
+
     let formats = ["%m/%d/%Y", "%d/%m/%Y", "%Y/%m/%d", "%m-%d-%Y", "%d-%m-%Y"];
     for format in &formats {
         if let Ok(date) = NaiveDate::parse_from_str(&date_str, format) {
@@ -68,13 +74,8 @@ pub fn parse_date(date_str: &str) -> Result<DateTime<Local>> {
                 // Clamp day to last day of month
                 let last_day = match m {
                     1 => 31,
-                    2 => {
-                        if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) {
-                            29
-                        } else {
-                            28
-                        }
-                    }
+                    2 if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) => 29,
+                    2 => 28,
                     3 => 31,
                     4 => 30,
                     5 => 31,
@@ -260,6 +261,15 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parse_iso_date_with_time() {
+        let result = parse_date("2025-07-15 09:30").unwrap();
+        assert_eq!(
+            result.format("%Y-%m-%d %H:%M").to_string(),
+            "2025-07-15 09:30"
+        );
+    }
+
     #[test]
     fn test_parse_invalid_date() {
         let result = parse_date("invalid");
@@ -0,0 +1,480 @@
+use crate::config::CompletionPolicy;
+use crate::date_parser::parse_date;
+use crate::error::{Result, TodoError};
+use crate::renderer::render_error;
+use crate::storage::{Completed, TaskStorage};
+use crate::task::{Task, TaskState, valid_priority, valid_tags};
+use chrono::{DateTime, Local, Timelike};
+use clap::ValueEnum;
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use uuid::Uuid;
+
+/// Line that separates the fields from the notes.
+const NOTES_SEPARATOR: &str = "---";
+/// Prefix of the comment lines that report why the last save was rejected.
+const ERROR_PREFIX: &str = "# Error:";
+
+/// A task's editable fields, as written in the editor document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskDocument {
+    /// Task description; can't be empty.
+    pub description: String,
+    /// Priority (1-5), or `None` when the field is blank.
+    pub priority: Option<u8>,
+    /// Due date, or `None` when the field is blank.
+    pub due_date: Option<DateTime<Local>>,
+    /// Comma-separated tags.
+    pub tags: Vec<String>,
+    /// Progress state.
+    pub state: TaskState,
+    /// Whether the task is completed.
+    pub completed: bool,
+    /// Everything below the `---` line, or `None` when that is blank.
+    pub notes: Option<String>,
+}
+
+impl TaskDocument {
+    /// The document describing a task as it is now.
+    pub fn from_task(task: &Task) -> Self {
+        Self {
+            description: task.description.clone(),
+            priority: task.priority,
+            due_date: task.due_date,
+            tags: task.tags.clone(),
+            state: task.state,
+            completed: task.completed,
+            notes: task.notes.clone(),
+        }
+    }
+
+    /// Text shown in the editor for the task with the given ID.
+    pub fn render(&self, id: u64) -> String {
+        let mut text = format!(
+            "# Editing task {}. Lines starting with '#' are ignored.\n\
+             # Leave priority or due blank to clear them; due accepts anything --due does.\n\
+             # State is pending, started or waiting. Save an empty file to cancel.\n",
+            id
+        );
+        text.push_str(&format!("description: {}\n", self.description));
+        text.push_str(&format!(
+            "priority: {}\n",
+            self.priority.map(|p| p.to_string()).unwrap_or_default()
+        ));
+        text.push_str(&format!(
+            "due: {}\n",
+            self.due_date.map(format_due).unwrap_or_default()
+        ));
+        text.push_str(&format!("tags: {}\n", self.tags.join(", ")));
+        text.push_str(&format!("state: {}\n", self.state));
+        text.push_str(&format!(
+            "completed: {}\n",
+            if self.completed { "yes" } else { "no" }
+        ));
+        text.push_str(&format!("{}\n", NOTES_SEPARATOR));
+        if let Some(notes) = &self.notes {
+            text.push_str(notes);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parse an edited document.
+    ///
+    /// `original` is the task being edited: a due date left as it was
+    /// rendered keeps its exact time instead of being parsed again.
+    ///
+    /// # Errors
+    /// Returns an error naming the line for unknown, repeated or missing
+    /// fields, and the usual priority, tag and date errors for bad values.
+    pub fn parse(text: &str, original: &Task) -> Result<Self> {
+        let mut document = Self::from_task(original);
+        let mut seen: Vec<&str> = Vec::new();
+        let mut lines = text.lines().enumerate();
+
+        for (index, line) in lines.by_ref() {
+            let line = line.trim();
+            if line == NOTES_SEPARATOR {
+                break;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line_number = index + 1;
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(line_error(line_number, "expected 'field: value'"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if seen.contains(&key) {
+                return Err(line_error(line_number, &format!("'{}' is repeated", key)));
+            }
+
+            match key {
+                "description" => {
+                    if value.is_empty() {
+                        return Err(line_error(line_number, "description can't be empty"));
+                    }
+                    document.description = value.to_string();
+                }
+                "priority" => {
+                    document.priority = match value {
+                        "" | "none" => None,
+                        _ => match value.parse::<u8>() {
+                            Ok(p) => valid_priority(Some(p))?,
+                            Err(_) => {
+                                return Err(line_error(
+                                    line_number,
+                                    &format!("priority must be 1-5, not '{}'", value),
+                                ));
+                            }
+                        },
+                    };
+                }
+                "due" => {
+                    document.due_date = match value {
+                        "" | "none" => None,
+                        _ if original.due_date.map(format_due).as_deref() == Some(value) => {
+                            original.due_date
+                        }
+                        _ => Some(parse_date(value)?),
+                    };
+                }
+                "tags" => {
+                    document.tags = if value.is_empty() {
+                        Vec::new()
+                    } else {
+                        valid_tags(value.split(','))?
+                    };
+                }
+                "state" => {
+                    document.state = TaskState::from_str(value, true).map_err(|_| {
+                        line_error(
+                            line_number,
+                            &format!("state must be pending, started or waiting, not '{}'", value),
+                        )
+                    })?;
+                }
+                "completed" => {
+                    document.completed = match value.to_lowercase().as_str() {
+                        "yes" | "true" => true,
+                        "no" | "false" => false,
+                        _ => {
+                            return Err(line_error(
+                                line_number,
+                                &format!("completed must be yes or no, not '{}'", value),
+                            ));
+                        }
+                    };
+                }
+                _ => {
+                    return Err(line_error(line_number, &format!("unknown field '{}'", key)));
+                }
+            }
+            seen.push(key);
+        }
+
+        if !seen.contains(&"description") {
+            return Err(TodoError::Editor(
+                "the description field is missing".to_string(),
+            ));
+        }
+
+        let notes: Vec<&str> = lines.map(|(_, line)| line).collect();
+        let notes = notes.join("\n");
+        let notes = notes.trim();
+        document.notes = (!notes.is_empty()).then(|| notes.to_string());
+
+        Ok(document)
+    }
+
+    /// Write the document's fields into the task with the given ID.
+    ///
+    /// Completing the task goes through `TaskStorage::complete_tasks`, so its
+    /// open subtasks are handled as `policy` says; reopening clears the
+    /// completion time and note.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TaskNotFound` for an unknown ID, and
+    /// `TodoError::OpenSubtasks` when `policy` refuses the completion.
+    pub fn apply(
+        self,
+        storage: &mut TaskStorage,
+        id: u64,
+        policy: CompletionPolicy,
+    ) -> Result<Completed> {
+        let task = storage
+            .get_task_mut(id)
+            .ok_or(TodoError::TaskNotFound(id))?;
+        task.description = self.description;
+        task.priority = self.priority;
+        task.due_date = self.due_date;
+        task.tags = self.tags;
+        task.state = self.state;
+        task.notes = self.notes;
+        if self.completed && !task.completed {
+            return storage.complete_tasks(&[id], None, policy);
+        }
+        if !self.completed && task.completed {
+            task.reopen();
+        }
+        Ok(Completed::default())
+    }
+}
+
+/// The editor to run: `$VISUAL`, then `$EDITOR`, then `vi`.
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Open the task in `editor` and return the edited document.
+///
+/// If the saved document is invalid the editor is opened again with the
+/// error at the top. Returns `None` if the user saved an empty file or
+/// changed nothing.
+///
+/// # Errors
+/// Returns an error if the editor can't be started or exits unsuccessfully.
+pub fn edit_in_editor(task: &Task, editor: &str) -> Result<Option<TaskDocument>> {
+    let dir = private_dir()?;
+    let path = dir.join(format!("todo-task-{}.txt", task.id));
+    let original = TaskDocument::from_task(task);
+    let mut text = original.render(task.id);
+
+    let result = loop {
+        fs::write(&path, &text)?;
+        if let Err(e) = run_editor(editor, &path) {
+            break Err(e);
+        }
+        let edited = fs::read_to_string(&path)?;
+
+        if is_blank(&edited) {
+            break Ok(None);
+        }
+        match TaskDocument::parse(&edited, task) {
+            Ok(document) if document == original => break Ok(None),
+            Ok(document) => break Ok(Some(document)),
+            Err(e) => {
+                render_error(&e.to_string());
+                text = with_error(&edited, &e.to_string());
+            }
+        }
+    };
+
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+/// Create a new temporary directory that only the current user can access,
+/// so nobody else can read the task or plant a symlink where it is written.
+fn private_dir() -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("todo-edit-{}", Uuid::new_v4()));
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| TodoError::Editor("no editor is set".to_string()))?;
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| TodoError::Editor(format!("couldn't start '{}': {}", editor, e)))?;
+    if !status.success() {
+        return Err(TodoError::Editor(format!(
+            "'{}' exited with {}",
+            editor, status
+        )));
+    }
+    Ok(())
+}
+
+/// Whether a document has nothing but comments and blank lines.
+fn is_blank(text: &str) -> bool {
+    text.lines()
+        .map(str::trim)
+        .all(|line| line.is_empty() || line.starts_with('#'))
+}
+
+/// The edited text with the previous error replaced by `error`.
+fn with_error(text: &str, error: &str) -> String {
+    let mut result = format!("{} {}\n", ERROR_PREFIX, error);
+    for line in text.lines().filter(|line| !line.starts_with(ERROR_PREFIX)) {
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+/// Due dates without a time of day are stored at 23:59:59.
+fn format_due(date: DateTime<Local>) -> String {
+    if (date.hour(), date.minute(), date.second()) == (23, 59, 59) {
+        date.format("%Y-%m-%d").to_string()
+    } else {
+        date.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+fn line_error(line: usize, message: &str) -> TodoError {
+    TodoError::Editor(format!("line {}: {}", line, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample_task() -> Task {
+        let mut task = Task::new(3, "Write report".to_string());
+        task.priority = Some(2);
+        task.due_date = Local.with_ymd_and_hms(2026, 10, 20, 17, 30, 15).single();
+        task.tags = vec!["work".to_string(), "q4".to_string()];
+        task.notes = Some("Ask Sam for the numbers.\n\n# Outline\n- intro".to_string());
+        task
+    }
+
+    #[test]
+    fn test_render_and_parse_round_trip() {
+        let task = sample_task();
+        let document = TaskDocument::from_task(&task);
+        let text = document.render(task.id);
+
+        assert!(text.contains("priority: 2\ndue: 2026-10-20 17:30\ntags: work, q4\n"));
+        assert!(text.ends_with("---\nAsk Sam for the numbers.\n\n# Outline\n- intro\n"));
+        // The due date keeps its seconds when the line is left alone.
+        assert_eq!(TaskDocument::parse(&text, &task).unwrap(), document);
+    }
+
+    #[test]
+    fn test_parse_changes() {
+        let task = sample_task();
+        let text = "description: Write the Q4 report\n\
+                    priority:\n\
+                    due: 2026-11-02\n\
+                    tags: work, , \n";
+        assert!(matches!(
+            TaskDocument::parse(text, &task),
+            Err(TodoError::InvalidTag(_))
+        ));
+
+        let text = "description: Write the Q4 report\n\
+                    priority:\n\
+                    due: 2026-11-02\n\
+                    tags:\n\
+                    state: Started\n\
+                    completed: yes\n\
+                    ---\n\n  New notes\n";
+        let document = TaskDocument::parse(text, &task).unwrap();
+        assert_eq!(document.description, "Write the Q4 report");
+        assert_eq!(document.priority, None);
+        assert_eq!(format_due(document.due_date.unwrap()), "2026-11-02");
+        assert!(document.tags.is_empty());
+        assert_eq!(document.state, TaskState::Started);
+        assert_eq!(document.notes.as_deref(), Some("New notes"));
+
+        let mut storage = TaskStorage::default();
+        let id = storage.add_task(task);
+        document
+            .apply(&mut storage, id, CompletionPolicy::Warn)
+            .unwrap();
+        let updated = storage.get_task(id).unwrap();
+        assert_eq!(updated.description, "Write the Q4 report");
+        assert!(updated.completed && updated.completed_at.is_some());
+    }
+
+    #[test]
+    fn test_apply_completion_with_open_subtasks() {
+        let mut storage = TaskStorage::default();
+        let parent = storage.add_task(sample_task());
+        let subtask = storage.add_task(Task::new(0, "Collect numbers".to_string()));
+        let uuid = storage.get_task(parent).unwrap().uuid;
+        storage.set_parent(subtask, Some(uuid)).unwrap();
+        let mut document = TaskDocument::from_task(storage.get_task(parent).unwrap());
+        document.completed = true;
+
+        assert!(matches!(
+            document
+                .clone()
+                .apply(&mut storage, parent, CompletionPolicy::Refuse),
+            Err(TodoError::OpenSubtasks { .. })
+        ));
+        let completed = document
+            .apply(&mut storage, parent, CompletionPolicy::Cascade)
+            .unwrap();
+        assert_eq!(completed.cascaded, [subtask]);
+        assert!(storage.get_task(subtask).unwrap().completed);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let task = sample_task();
+        let error = |text: &str| TaskDocument::parse(text, &task).unwrap_err().to_string();
+
+        assert_eq!(
+            error("description: x\npriority: 9\n"),
+            "Invalid priority value: 9. Priority must be between 1 and 5"
+        );
+        assert_eq!(
+            error("# comment\ndescription: x\ncolour: red\n"),
+            "Editor error: line 3: unknown field 'colour'"
+        );
+        assert_eq!(
+            error("description:\n"),
+            "Editor error: line 1: description can't be empty"
+        );
+        assert_eq!(
+            error("priority: 1\n"),
+            "Editor error: the description field is missing"
+        );
+        assert!(error("description: x\ndue: someday maybe\n").starts_with("Date parsing error"));
+        assert_eq!(
+            with_error("# Error: old\ndescription: x\n", "new"),
+            "# Error: new\ndescription: x\n"
+        );
+    }
+
+    #[test]
+    fn test_edit_in_editor_without_changes() {
+        let task = sample_task();
+        assert!(edit_in_editor(&task, "true").unwrap().is_none());
+        assert!(matches!(
+            edit_in_editor(&task, "false"),
+            Err(TodoError::Editor(_))
+        ));
+    }
+
+    #[test]
+    fn test_private_dir() {
+        let dir = private_dir().unwrap();
+        let other = private_dir().unwrap();
+        assert_ne!(dir, other);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        fs::remove_dir(dir).unwrap();
+        fs::remove_dir(other).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unchanged_document_is_none() {
+        let task = sample_task();
+        assert_eq!(edit_in_editor(&task, "true").unwrap(), None);
+    }
+}
@@ -15,15 +15,132 @@ pub enum TodoError {
     /// Task not found by ID.
     #[error("Task not found with ID: {0}")]
     TaskNotFound(u64),
+    /// No task matches a UUID prefix or other task reference.
+    #[error("No task matches '{0}'")]
+    UnknownTask(String),
+    /// A UUID prefix or short ID matches more than one task.
+    #[error("'{0}' matches {1} tasks; use a longer UUID prefix")]
+    AmbiguousId(String, usize),
     /// Invalid priority value (must be 1-5).
     #[error("Invalid priority value: {0}. Priority must be between 1 and 5")]
     InvalidPriority(u8),
+    /// A task can't be made a subtask of this one.
+    #[error("Invalid parent: {0}")]
+    InvalidParent(String),
+    /// Completing a task with open subtasks was refused.
+    #[error(
+        "Task {id} has open subtasks: {}; complete them first or set complete_with_open_subtasks",
+        join_ids(subtasks)
+    )]
+    OpenSubtasks {
+        /// The task that wasn't completed.
+        id: u64,
+        /// Its open subtasks.
+        subtasks: Vec<u64>,
+    },
     /// Invalid tag (empty or malformed).
     #[error("Invalid tag: {0}. Tags cannot be empty")]
     InvalidTag(String),
+    /// No task has the tag.
+    #[error("No task has the tag: {0}")]
+    TagNotFound(String),
+    /// The tag is already in use.
+    #[error("Tag already exists: {0}. Use 'todo tags merge' to combine tags")]
+    TagExists(String),
     /// Data file corruption or unreadable.
     #[error("Data file corruption: {0}")]
     DataCorruption(String),
+    /// Invalid config file or setting.
+    #[error("Config error: {0}")]
+    Config(String),
+    /// Invalid output template.
+    #[error("Template error: {0}")]
+    Template(String),
+    /// Invalid filter expression.
+    #[error("Filter error: {0}")]
+    Filter(String),
+    /// A bulk change needs confirmation that can't be asked for.
+    #[error("{0} tasks would be changed; pass --yes to confirm")]
+    ConfirmationRequired(usize),
+    /// The external editor couldn't be run or its document was invalid.
+    #[error("Editor error: {0}")]
+    Editor(String),
+    /// `todo sync` isn't set up or a git command failed.
+    #[error("Sync error: {0}")]
+    Sync(String),
+    /// `todo merge` found conflicts and wasn't told how to resolve them.
+    #[error("{0} conflicts left unresolved; use --interactive or --prefer")]
+    MergeConflicts(usize),
+    /// The data file changed on disk while a change to it was being made.
+    #[error("The data file was changed by another process; try again")]
+    DataChanged,
+    /// An API request that can't be understood.
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    /// A hook couldn't be run, timed out or printed an invalid task.
+    #[error("Hook error: {0}")]
+    Hook(String),
+    /// A hook vetoed a change by exiting with a non-zero status.
+    #[error("Hook '{hook}' rejected the change: {message}")]
+    HookRejected {
+        /// The hook command.
+        hook: String,
+        /// What the hook printed to explain why.
+        message: String,
+    },
+    /// Malformed org-mode input.
+    #[error("Org-mode parse error on line {line}: {message}")]
+    OrgParse {
+        /// Line number, starting at 1.
+        line: usize,
+        /// What is wrong with the line.
+        message: String,
+    },
+    /// Arguments that parse but don't make sense together.
+    #[error("{0}")]
+    Usage(String),
+}
+
+impl TodoError {
+    /// Stable, machine-readable code for this error, used in JSON output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TodoError::Io(_) => "io_error",
+            TodoError::Json(_) => "json_error",
+            TodoError::DateParse(_) => "date_parse_error",
+            TodoError::TaskNotFound(_) => "task_not_found",
+            TodoError::UnknownTask(_) => "task_not_found",
+            TodoError::AmbiguousId(..) => "ambiguous_id",
+            TodoError::InvalidPriority(_) => "invalid_priority",
+            TodoError::InvalidParent(_) => "invalid_parent",
+            TodoError::OpenSubtasks { .. } => "open_subtasks",
+            TodoError::InvalidTag(_) => "invalid_tag",
+            TodoError::TagNotFound(_) => "tag_not_found",
+            TodoError::TagExists(_) => "tag_exists",
+            TodoError::DataCorruption(_) => "data_corruption",
+            TodoError::Config(_) => "config_error",
+            TodoError::Template(_) => "template_error",
+            TodoError::Filter(_) => "filter_error",
+            TodoError::ConfirmationRequired(_) => "confirmation_required",
+            TodoError::Editor(_) => "editor_error",
+            TodoError::Sync(_) => "sync_error",
+            TodoError::MergeConflicts(_) => "merge_conflict",
+            TodoError::DataChanged => "data_changed",
+            TodoError::BadRequest(_) => "bad_request",
+            TodoError::Hook(_) => "hook_error",
+            TodoError::HookRejected { .. } => "hook_rejected",
+            TodoError::OrgParse { .. } => "org_parse_error",
+            TodoError::Usage(_) => "usage_error",
+        }
+    }
+}
+
+/// Task IDs as a comma-separated list.
+pub(crate) fn join_ids(ids: &[u64]) -> String {
+    ids.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Result type for all todo CLI operations.
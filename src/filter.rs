@@ -1,4 +1,7 @@
-use crate::task::Task;
+use crate::date_parser::parse_date;
+use crate::error::{Result, TodoError};
+use crate::task::{Task, TaskState};
+use chrono::NaiveDate;
 
 /// Sorts tasks in-place using their Ord implementation.
 pub fn sort_tasks(tasks: &mut [&Task]) {
@@ -29,6 +32,494 @@ pub fn filter_tasks<'a>(
         .collect()
 }
 
+/// A parsed filter expression such as
+/// `tag:work and not (completed or due:after:friday)`.
+///
+/// Terms:
+/// - `completed`/`done`, `open`, `pending`, `started`, `waiting`, `overdue`
+/// - `tag:NAME` or `#NAME`
+/// - `priority:N` or `priority:none`
+/// - `due:`, `created:` or `completed:` followed by `before:DATE`,
+///   `after:DATE`, `DATE` (that day) or `none`
+/// - `desc:TEXT` (case-insensitive substring of the description)
+/// - `ID`, `ID-ID` or `id:ID` to match task IDs
+/// - `uuid:PREFIX`, or a bare hex prefix of at least 4 characters such as
+///   `1f0c9a`, to match tasks whose UUID starts with it
+///
+/// Terms are combined with `and` (also implied between adjacent terms),
+/// `or`, `not` and parentheses. Dates accept everything `parse_date` does;
+/// double quotes group words, e.g. `completed:before:"30 days ago"`.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Completed,
+    Open,
+    State(TaskState),
+    Overdue,
+    Tag(String),
+    Priority(Option<u8>),
+    Date(DateField, DateTest),
+    Ids(u64, u64),
+    Uuid(String),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DateField {
+    Due,
+    Created,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DateTest {
+    Before(NaiveDate),
+    After(NaiveDate),
+    On(NaiveDate),
+    None,
+}
+
+impl Filter {
+    /// Parse a filter expression.
+    ///
+    /// # Errors
+    /// Returns a `TodoError::Filter` for unknown `key:value` terms, unbalanced
+    /// parentheses or unterminated quotes, and a `TodoError::DateParse`
+    /// for dates that can't be parsed.
+    ///
+    /// # Examples
+    /// ```
+    /// use todo_cli::Task;
+    /// use todo_cli::filter::Filter;
+    ///
+    /// let filter = Filter::parse("priority:1 and not tag:someday")?;
+    /// let mut task = Task::new(1, "Fix login".to_string());
+    /// task.priority = Some(1);
+    /// assert!(filter.matches(&task));
+    /// # Ok::<(), todo_cli::TodoError>(())
+    /// ```
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err(filter_error("empty filter"));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(filter_error(&format!("unexpected '{}'", token)));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Returns true if the task matches the expression.
+    pub fn matches(&self, task: &Task) -> bool {
+        self.expr.matches(task)
+    }
+}
+
+/// Tasks picked on the command line by IDs, ID ranges, UUID prefixes and
+/// filter terms.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    ids: Vec<(u64, u64)>,
+    uuids: Vec<String>,
+    filter: Option<Filter>,
+}
+
+impl Selection {
+    /// Build a selection from positional targets and an optional `--filter`.
+    ///
+    /// Targets that are IDs, ranges or UUID prefixes (`3`, `8-12`,
+    /// `1f0c9a`) add to the set of tasks; any other target is a filter
+    /// expression. All filter expressions, and the set of tasks if there is
+    /// one, must match.
+    ///
+    /// # Errors
+    /// Returns an error if a filter expression or ID range is invalid.
+    pub fn parse(targets: &[String], filter: Option<&str>) -> Result<Self> {
+        let mut ids = Vec::new();
+        let mut uuids = Vec::new();
+        let mut expressions = Vec::new();
+        let mut expr: Option<Expr> = None;
+
+        for target in targets {
+            if let Some(range) = parse_id_range(target)? {
+                ids.push(range);
+            } else if let Some(prefix) = parse_uuid_prefix(target, MIN_BARE_UUID_FILTER) {
+                uuids.push(prefix);
+            } else {
+                expressions.push(target.as_str());
+            }
+        }
+        for source in expressions.into_iter().chain(filter) {
+            let parsed = Filter::parse(source)?.expr;
+            expr = Some(match expr {
+                Some(previous) => Expr::And(Box::new(previous), Box::new(parsed)),
+                None => parsed,
+            });
+        }
+
+        Ok(Self {
+            ids,
+            uuids,
+            filter: expr.map(|expr| Filter { expr }),
+        })
+    }
+
+    /// The ID if the selection names exactly one task, by ID or UUID prefix.
+    ///
+    /// `ids` is what `select` returned for this selection.
+    pub fn single_id(&self, ids: &[u64]) -> Option<u64> {
+        let single = match (self.ids.as_slice(), self.uuids.len(), &self.filter) {
+            ([(first, last)], 0, None) => first == last,
+            ([], 1, None) => true,
+            _ => false,
+        };
+        if single { ids.first().copied() } else { None }
+    }
+
+    /// IDs of the selected tasks, in storage order.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TaskNotFound` if a single ID (not a range) doesn't
+    /// exist, and the errors of `resolve_uuid_prefix` for UUID prefixes.
+    pub fn select(&self, tasks: &[Task]) -> Result<Vec<u64>> {
+        let mut ids = self.ids.clone();
+        for &(first, last) in &self.ids {
+            if first == last && !tasks.iter().any(|task| task.id == first) {
+                return Err(TodoError::TaskNotFound(first));
+            }
+        }
+        for prefix in &self.uuids {
+            let id = resolve_uuid_prefix(tasks, prefix)?.id;
+            ids.push((id, id));
+        }
+
+        Ok(tasks
+            .iter()
+            .filter(|task| {
+                ids.is_empty()
+                    || ids
+                        .iter()
+                        .any(|(first, last)| (*first..=*last).contains(&task.id))
+            })
+            .filter(|task| self.filter.as_ref().is_none_or(|f| f.matches(task)))
+            .map(|task| task.id)
+            .collect())
+    }
+}
+
+impl Expr {
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(task) && right.matches(task),
+            Expr::Or(left, right) => left.matches(task) || right.matches(task),
+            Expr::Not(inner) => !inner.matches(task),
+            Expr::Term(term) => term.matches(task),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            Term::Completed => task.completed,
+            Term::Open => !task.completed,
+            Term::State(state) => !task.completed && task.state == *state,
+            Term::Overdue => task.is_overdue(),
+            Term::Tag(tag) => task.tags.iter().any(|t| t == tag),
+            Term::Priority(priority) => task.priority == *priority,
+            Term::Date(field, test) => {
+                let date = match field {
+                    DateField::Due => task.due_date,
+                    DateField::Created => Some(task.created_at),
+                    DateField::Completed => task.completed_at,
+                }
+                .map(|date| date.date_naive());
+                match (test, date) {
+                    (DateTest::None, date) => date.is_none(),
+                    (DateTest::Before(limit), Some(date)) => date < *limit,
+                    (DateTest::After(limit), Some(date)) => date > *limit,
+                    (DateTest::On(day), Some(date)) => date == *day,
+                    (_, None) => false,
+                }
+            }
+            Term::Ids(first, last) => (*first..=*last).contains(&task.id),
+            Term::Uuid(prefix) => task.uuid.to_string().starts_with(prefix.as_str()),
+            Term::Text(text) => task.description.to_lowercase().contains(text),
+        }
+    }
+
+    fn parse(token: &str) -> Result<Self> {
+        let lower = token.to_lowercase();
+        let term = match lower.as_str() {
+            "completed" | "done" => Term::Completed,
+            "open" => Term::Open,
+            "pending" => Term::State(TaskState::Pending),
+            "started" => Term::State(TaskState::Started),
+            "waiting" => Term::State(TaskState::Waiting),
+            "overdue" => Term::Overdue,
+            _ => {
+                if let Some((first, last)) = parse_id_range(token)? {
+                    return Ok(Term::Ids(first, last));
+                }
+                if let Some(tag) = token.strip_prefix('#') {
+                    return Ok(Term::Tag(tag.to_string()));
+                }
+                if let Some(prefix) = parse_uuid_prefix(token, MIN_BARE_UUID_FILTER) {
+                    return Ok(Term::Uuid(prefix));
+                }
+                let Some((key, value)) = token.split_once(':') else {
+                    return Ok(Term::Text(lower));
+                };
+                match key.to_lowercase().as_str() {
+                    "tag" => Term::Tag(value.to_string()),
+                    "desc" | "description" => Term::Text(value.to_lowercase()),
+                    "id" => parse_id_range(value)?
+                        .map(|(first, last)| Term::Ids(first, last))
+                        .ok_or_else(|| filter_error(&format!("invalid ID '{}'", value)))?,
+                    "uuid" => Term::Uuid(value.to_lowercase()),
+                    "priority" if value == "none" => Term::Priority(None),
+                    "priority" => match value.parse::<u8>() {
+                        Ok(priority) if (1..=5).contains(&priority) => {
+                            Term::Priority(Some(priority))
+                        }
+                        Ok(priority) => return Err(TodoError::InvalidPriority(priority)),
+                        Err(_) => {
+                            return Err(filter_error(&format!("invalid priority '{}'", value)));
+                        }
+                    },
+                    "due" => Term::Date(DateField::Due, parse_date_test(value)?),
+                    "created" => Term::Date(DateField::Created, parse_date_test(value)?),
+                    "completed" | "done" => {
+                        Term::Date(DateField::Completed, parse_date_test(value)?)
+                    }
+                    _ => {
+                        return Err(filter_error(&format!("unknown filter term '{}'", token)));
+                    }
+                }
+            }
+        };
+        Ok(term)
+    }
+}
+
+fn parse_date_test(value: &str) -> Result<DateTest> {
+    let day = |phrase: &str| parse_date(phrase).map(|date| date.date_naive());
+    let test = if value == "none" {
+        DateTest::None
+    } else if let Some(phrase) = value.strip_prefix("before:") {
+        DateTest::Before(day(phrase)?)
+    } else if let Some(phrase) = value.strip_prefix("after:") {
+        DateTest::After(day(phrase)?)
+    } else {
+        DateTest::On(day(value)?)
+    };
+    Ok(test)
+}
+
+/// Parse `7` or `8-12` into an inclusive ID range. Returns `None` for
+/// anything else.
+///
+/// # Errors
+/// Returns `TodoError::Filter` for a reversed range such as `12-8`.
+pub fn parse_id_range(token: &str) -> Result<Option<(u64, u64)>> {
+    let Some((first, last)) = token.split_once('-') else {
+        return Ok(token.parse().ok().map(|id| (id, id)));
+    };
+    let (Ok(first), Ok(last)) = (first.parse::<u64>(), last.parse::<u64>()) else {
+        return Ok(None);
+    };
+    if first > last {
+        return Err(filter_error(&format!(
+            "ID range '{}' is reversed; did you mean '{}-{}'?",
+            token, last, first
+        )));
+    }
+    Ok(Some((first, last)))
+}
+
+/// Shortest bare UUID prefix accepted where a single task is expected.
+const MIN_UUID_PREFIX: usize = 4;
+/// Shortest bare UUID prefix in selections and filters, where other words
+/// search the description: a whole UUID group, so words such as `face` or
+/// `added` stay words.
+const MIN_BARE_UUID_FILTER: usize = 8;
+
+/// Parse a UUID prefix target: `uuid:PREFIX`, or at least `min_bare` hex
+/// digits and dashes (with a letter, so plain IDs stay IDs).
+fn parse_uuid_prefix(token: &str, min_bare: usize) -> Option<String> {
+    let (prefix, bare) = match token.strip_prefix("uuid:") {
+        Some(prefix) => (prefix, false),
+        None => (token, true),
+    };
+    let prefix = prefix.to_lowercase();
+    let valid = !prefix.is_empty()
+        && prefix.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+        && (!bare || (prefix.len() >= min_bare && prefix.chars().any(|c| c.is_ascii_alphabetic())));
+    valid.then_some(prefix)
+}
+
+/// The one task whose UUID starts with `prefix`.
+///
+/// # Errors
+/// Returns `TodoError::UnknownTask` if no task matches and
+/// `TodoError::AmbiguousId` if several do.
+pub fn resolve_uuid_prefix<'a>(
+    tasks: impl IntoIterator<Item = &'a Task>,
+    prefix: &str,
+) -> Result<&'a Task> {
+    let prefix = prefix.to_lowercase();
+    let mut matches = tasks
+        .into_iter()
+        .filter(|task| task.uuid.to_string().starts_with(&prefix));
+    let first = matches
+        .next()
+        .ok_or_else(|| TodoError::UnknownTask(prefix.clone()))?;
+    match matches.count() {
+        0 => Ok(first),
+        others => Err(TodoError::AmbiguousId(prefix, others + 1)),
+    }
+}
+
+/// The task a command-line reference names: a short ID, or a UUID prefix.
+///
+/// # Errors
+/// Returns `TodoError::TaskNotFound` or `TodoError::UnknownTask` if no task
+/// matches, and `TodoError::AmbiguousId` if several do.
+pub fn resolve_task<'a>(tasks: &[&'a Task], reference: &str) -> Result<&'a Task> {
+    if let Ok(id) = reference.parse::<u64>() {
+        let mut matches = tasks.iter().filter(|task| task.id == id);
+        let first = matches.next().ok_or(TodoError::TaskNotFound(id))?;
+        return match matches.count() {
+            0 => Ok(first),
+            others => Err(TodoError::AmbiguousId(reference.to_string(), others + 1)),
+        };
+    }
+    let prefix = parse_uuid_prefix(reference, MIN_UUID_PREFIX)
+        .ok_or_else(|| TodoError::UnknownTask(reference.to_string()))?;
+    resolve_uuid_prefix(tasks.iter().copied(), &prefix)
+}
+
+/// Split an expression into words and parentheses; quotes group words.
+fn tokenize(source: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in source.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            _ if in_quotes => current.push(c),
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+            _ if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(filter_error("unterminated quote"));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        self.peek()
+            .is_some_and(|token| token.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        loop {
+            if self.keyword("and") {
+                self.pos += 1;
+            } else if self.peek().is_none() || self.peek() == Some(")") || self.keyword("or") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self
+            .peek()
+            .ok_or_else(|| filter_error("expected a term at the end of the filter"))?
+            .to_string();
+        self.pos += 1;
+
+        match token.as_str() {
+            "(" => {
+                let expr = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err(filter_error("missing ')'"));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            ")" => Err(filter_error("unexpected ')'")),
+            _ if ["and", "or"].iter().any(|k| token.eq_ignore_ascii_case(k)) => {
+                Err(filter_error(&format!("expected a term before '{}'", token)))
+            }
+            _ => Ok(Expr::Term(Term::parse(&token)?)),
+        }
+    }
+}
+
+fn filter_error(message: &str) -> TodoError {
+    TodoError::Filter(message.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,4 +585,147 @@ mod tests {
         let filtered_with_completed = filter_tasks(&tasks, None, None, true);
         assert_eq!(filtered_with_completed.len(), 3);
     }
+
+    #[test]
+    fn test_filter_expressions() {
+        let mut tasks = [
+            create_test_task(1, "Buy milk", vec!["home".to_string()], false),
+            create_test_task(2, "Write report", vec!["work".to_string()], true),
+            create_test_task(3, "Review PR", vec!["work".to_string()], false),
+        ];
+        tasks[0].priority = Some(1);
+        tasks[1].completed_at = Some(chrono::Local::now() - chrono::Duration::days(40));
+        tasks[2].state = TaskState::Started;
+
+        let ids = |expr: &str| -> Vec<u64> {
+            let filter = Filter::parse(expr).unwrap();
+            tasks
+                .iter()
+                .filter(|t| filter.matches(t))
+                .map(|t| t.id)
+                .collect()
+        };
+
+        assert_eq!(ids("tag:work"), vec![2, 3]);
+        assert_eq!(ids("#work open"), vec![3]);
+        assert_eq!(ids("tag:work and not started"), vec![2]);
+        assert_eq!(ids("priority:1 or (started and desc:review)"), vec![1, 3]);
+        assert_eq!(
+            ids(r#"completed and completed:before:"30 days ago""#),
+            vec![2]
+        );
+        assert_eq!(ids("completed:none"), vec![1, 3]);
+        assert_eq!(ids("2-3 and not id:2"), vec![3]);
+    }
+
+    #[test]
+    fn test_selection() {
+        let tasks = vec![
+            create_test_task(1, "One", vec!["inbox".to_string()], false),
+            create_test_task(2, "Two", vec![], false),
+            create_test_task(3, "Three", vec!["inbox".to_string()], false),
+            create_test_task(5, "Five", vec!["inbox".to_string()], true),
+        ];
+        let targets =
+            |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+        let selection = Selection::parse(&targets(&["1", "3-9"]), None).unwrap();
+        let ids = selection.select(&tasks).unwrap();
+        assert_eq!(ids, vec![1, 3, 5]);
+        assert_eq!(selection.single_id(&ids), None);
+
+        let selection = Selection::parse(&targets(&["tag:inbox"]), Some("open")).unwrap();
+        assert_eq!(selection.select(&tasks).unwrap(), vec![1, 3]);
+
+        let selection = Selection::parse(&targets(&["2-5", "tag:inbox"]), None).unwrap();
+        assert_eq!(selection.select(&tasks).unwrap(), vec![3, 5]);
+
+        let selection = Selection::parse(&targets(&["4"]), None).unwrap();
+        assert!(matches!(
+            selection.select(&tasks),
+            Err(TodoError::TaskNotFound(4))
+        ));
+
+        // A reversed range is a mistake, not a description search.
+        assert!(matches!(
+            Selection::parse(&targets(&["9-3"]), None),
+            Err(TodoError::Filter(_))
+        ));
+    }
+
+    #[test]
+    fn test_uuid_prefixes() {
+        let mut tasks = vec![
+            create_test_task(1, "One", vec![], false),
+            create_test_task(2, "Two", vec![], false),
+            create_test_task(3, "Three", vec![], false),
+        ];
+        tasks[0].uuid = "1f0c9a00-0000-4000-8000-000000000001".parse().unwrap();
+        tasks[1].uuid = "1f0c9b00-0000-4000-8000-000000000002".parse().unwrap();
+        tasks[2].uuid = "abcdef00-0000-4000-8000-000000000003".parse().unwrap();
+        let targets =
+            |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+        let selection = Selection::parse(&targets(&["1F0C9A00"]), None).unwrap();
+        let ids = selection.select(&tasks).unwrap();
+        assert_eq!(selection.single_id(&ids), Some(1));
+
+        let selection = Selection::parse(&targets(&["uuid:1f0c"]), None).unwrap();
+        assert!(matches!(
+            selection.select(&tasks),
+            Err(TodoError::AmbiguousId(_, 2))
+        ));
+        let selection = Selection::parse(&targets(&["2", "uuid:abcd"]), None).unwrap();
+        assert_eq!(selection.select(&tasks).unwrap(), vec![2, 3]);
+        let selection = Selection::parse(&[], Some("uuid:1f0c and not 1")).unwrap();
+        assert_eq!(selection.select(&tasks).unwrap(), vec![2]);
+
+        let refs: Vec<&Task> = tasks.iter().collect();
+        assert_eq!(resolve_task(&refs, "3").unwrap().id, 3);
+        assert_eq!(resolve_task(&refs, "abcdef").unwrap().id, 3);
+        assert!(matches!(
+            resolve_task(&refs, "dead"),
+            Err(TodoError::UnknownTask(_))
+        ));
+        assert!(matches!(
+            resolve_task(&refs, "abc"),
+            Err(TodoError::UnknownTask(_))
+        ));
+    }
+
+    #[test]
+    fn test_hex_words_filter_by_text() {
+        let mut tasks = vec![
+            create_test_task(1, "Buy face paint", vec![], false),
+            create_test_task(2, "Fix the build", vec![], false),
+        ];
+        tasks[1].uuid = "face0000-0000-4000-8000-000000000002".parse().unwrap();
+        let targets = vec!["face".to_string()];
+
+        let selection = Selection::parse(&targets, None).unwrap();
+        assert_eq!(selection.select(&tasks).unwrap(), vec![1]);
+        let filter = Filter::parse("face or uuid:face").unwrap();
+        assert!(tasks.iter().all(|task| filter.matches(task)));
+        let filter = Filter::parse("face0000").unwrap();
+        assert!(!filter.matches(&tasks[0]) && filter.matches(&tasks[1]));
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        assert!(Filter::parse("").is_err());
+        assert!(Filter::parse("bogus:term").is_err());
+        assert!(Filter::parse("(tag:work").is_err());
+        assert!(Filter::parse("tag:work or").is_err());
+        assert!(Filter::parse("due:before:\"next").is_err());
+        assert!(matches!(Filter::parse("99-1"), Err(TodoError::Filter(_))));
+        assert!(matches!(Filter::parse("id:5-2"), Err(TodoError::Filter(_))));
+        assert!(matches!(
+            Filter::parse("due:before:whenever"),
+            Err(TodoError::DateParse(_))
+        ));
+        assert!(matches!(
+            Filter::parse("priority:9"),
+            Err(TodoError::InvalidPriority(9))
+        ));
+    }
 }
@@ -0,0 +1,350 @@
+use crate::config::HooksConfig;
+use crate::error::{Result, TodoError};
+use crate::storage::TaskStorage;
+use crate::task::Task;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default for `HooksConfig::timeout_secs`.
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// What happened to a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The task is new, or was restored from the trash.
+    Add,
+    /// Fields of the task changed; reopening counts as a change.
+    Modify,
+    /// The task was completed.
+    Complete,
+    /// The task was moved to the trash.
+    Delete,
+}
+
+impl Event {
+    /// Name used in the hook input.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Event::Add => "add",
+            Event::Modify => "modify",
+            Event::Complete => "complete",
+            Event::Delete => "delete",
+        }
+    }
+
+    fn of(old: Option<&Task>, new: Option<&Task>) -> Self {
+        match (old, new) {
+            (None, _) => Event::Add,
+            (Some(_), None) => Event::Delete,
+            (Some(old), Some(new)) if !old.completed && new.completed => Event::Complete,
+            (Some(_), Some(_)) => Event::Modify,
+        }
+    }
+
+    fn hooks(self, config: &HooksConfig) -> &[String] {
+        match self {
+            Event::Add => &config.on_add,
+            Event::Modify => &config.on_modify,
+            Event::Complete => &config.on_complete,
+            Event::Delete => &config.on_delete,
+        }
+    }
+}
+
+/// Run the configured hooks for every task that changed since `storage` was
+/// loaded or last saved, before it is saved.
+///
+/// Each hook is a shell command run with `sh -c`, and gets
+/// `{"event", "old", "new"}` on stdin, with the tasks as they are stored in
+/// the data file (`null` for the old task of an add and the new task of a
+/// delete). Exiting with a non-zero status rejects the change; printing a
+/// task replaces the new one, except for deletes. The ID and UUID can't be
+/// changed. Hooks for the same event run in order, each seeing the task the
+/// previous one printed.
+///
+/// # Errors
+/// Returns `TodoError::HookRejected` when a hook rejects a change, and
+/// `TodoError::Hook` when one can't be started, runs longer than the timeout
+/// or prints something that isn't a valid task. Nothing should be saved then.
+pub fn run_hooks(config: &HooksConfig, storage: &mut TaskStorage) -> Result<()> {
+    let timeout = Duration::from_secs(config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let changes: Vec<(Option<Task>, Option<Task>)> = storage
+        .unsaved_changes()
+        .into_iter()
+        .map(|(old, new)| (old.cloned(), new.cloned()))
+        .collect();
+
+    for (old, mut new) in changes {
+        let event = Event::of(old.as_ref(), new.as_ref());
+        let hooks = event.hooks(config);
+        if hooks.is_empty() {
+            continue;
+        }
+        for hook in hooks {
+            let input = json!({ "event": event.as_str(), "old": old, "new": new });
+            let output = run_hook(hook, &format!("{}\n", input), timeout)?;
+            if let Some(current) = &new
+                && !output.trim().is_empty()
+            {
+                new = Some(rewritten_task(hook, current, &output)?);
+            }
+        }
+        if let Some(new) = new
+            && let Some(task) = storage.tasks.iter_mut().find(|task| task.uuid == new.uuid)
+        {
+            *task = new;
+        }
+    }
+    Ok(())
+}
+
+/// Run a hook with `input` on stdin and return what it printed.
+fn run_hook(hook: &str, input: &str, timeout: Duration) -> Result<String> {
+    if hook.trim().is_empty() {
+        return Err(TodoError::Hook("empty hook command".to_string()));
+    }
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| TodoError::Hook(format!("couldn't start '{}': {}", hook, e)))?;
+
+    // Feed and drain the pipes on their own threads so a hook that writes a
+    // lot, or never reads its input, can't block the wait below.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_string();
+    thread::spawn(move || {
+        // A hook that doesn't read its input closes the pipe early.
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let stdout = read_in_background(child.stdout.take().expect("stdout is piped"));
+    let stderr = read_in_background(child.stderr.take().expect("stderr is piped"));
+
+    let timed_out = || {
+        TodoError::Hook(format!(
+            "'{}' didn't finish within {} seconds",
+            hook,
+            timeout.as_secs()
+        ))
+    };
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out());
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // Processes the hook started in the background can keep its output open
+    // after it exits, so the reads get the rest of the time and no more.
+    let stdout = stdout
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .map_err(|_| timed_out())?;
+    if !status.success() {
+        let stderr = stderr
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+        let message = [stderr.trim(), stdout.trim()]
+            .into_iter()
+            .find(|message| !message.is_empty())
+            .map_or_else(|| format!("exited with {}", status), str::to_string);
+        return Err(TodoError::HookRejected {
+            hook: hook.to_string(),
+            message,
+        });
+    }
+    Ok(stdout)
+}
+
+/// Read a pipe to its end on another thread; the text arrives on the
+/// returned channel.
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut content = String::new();
+        let _ = pipe.read_to_string(&mut content);
+        let _ = sender.send(content);
+    });
+    receiver
+}
+
+/// The task a hook printed, keeping the ID and UUID of the task it was given.
+fn rewritten_task(hook: &str, current: &Task, output: &str) -> Result<Task> {
+    let invalid = |reason: String| TodoError::Hook(format!("'{}' printed {}", hook, reason));
+    let mut task: Task =
+        serde_json::from_str(output).map_err(|e| invalid(format!("an invalid task: {}", e)))?;
+    if task.description.trim().is_empty() {
+        return Err(invalid("a task without a description".to_string()));
+    }
+    if let Some(priority) = task.priority.filter(|p| !(1..=5).contains(p)) {
+        return Err(invalid(format!("a task with priority {}", priority)));
+    }
+    task.id = current.id;
+    task.uuid = current.uuid;
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// Write a shell script for a hook and return the command running it.
+    fn script(dir: &TempDir, name: &str, body: &str) -> String {
+        let path = dir.join(&format!("{}.sh", name));
+        std::fs::write(&path, body).unwrap();
+        format!("sh {}", path.display())
+    }
+
+    fn storage_with(descriptions: &[&str]) -> TaskStorage {
+        let mut storage = TaskStorage::default();
+        for description in descriptions {
+            storage.add_task(Task::new(0, description.to_string()));
+        }
+        storage
+    }
+
+    #[test]
+    fn test_events() {
+        let dir = TempDir::new("hook-events");
+        let log = dir.join("log");
+        let hook = script(
+            &dir,
+            "log",
+            &format!("cat >> {}\necho >> {}\n", log.display(), log.display()),
+        );
+        let config = HooksConfig {
+            on_add: vec![hook.clone()],
+            on_modify: vec![hook.clone()],
+            on_complete: vec![hook.clone()],
+            on_delete: vec![hook],
+            ..HooksConfig::default()
+        };
+
+        let path = dir.join("tasks.json");
+        let mut storage = storage_with(&["Keep", "Complete", "Delete"]);
+        storage.save_to_file(&path).unwrap();
+        storage.add_task(Task::new(0, "Add".to_string()));
+        storage.get_task_mut(1).unwrap().priority = Some(2);
+        storage.get_task_mut(2).unwrap().complete();
+        storage.delete_task(3).unwrap();
+        run_hooks(&config, &mut storage).unwrap();
+
+        let inputs: Vec<serde_json::Value> = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let events: Vec<&str> = inputs
+            .iter()
+            .map(|input| input["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(events, ["modify", "complete", "add", "delete"]);
+        assert_eq!(inputs[0]["old"]["priority"], serde_json::Value::Null);
+        assert_eq!(inputs[0]["new"]["priority"], 2);
+        assert_eq!(inputs[2]["old"], serde_json::Value::Null);
+        assert_eq!(inputs[3]["new"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_rewrite_and_reject() {
+        let dir = TempDir::new("hook-rewrite");
+        let mut storage = storage_with(&["Buy milk"]);
+
+        // The hook tags the task and tries to change its ID.
+        let tag = script(
+            &dir,
+            "tag",
+            "sed -e 's/\"tags\":\\[\\]/\"tags\":[\"inbox\"]/' -e 's/\"id\":1/\"id\":7/' | \
+             sed -n 's/.*\"new\":\\(.*\\),\"old\":null}$/\\1/p'\n",
+        );
+        let config = HooksConfig {
+            on_add: vec![tag],
+            ..HooksConfig::default()
+        };
+        run_hooks(&config, &mut storage).unwrap();
+        assert_eq!(storage.tasks[0].tags, ["inbox"]);
+        assert_eq!(storage.tasks[0].id, 1);
+
+        let reject = script(&dir, "reject", "echo 'tasks need a tag' >&2\nexit 1\n");
+        let config = HooksConfig {
+            on_add: vec![reject.clone()],
+            ..HooksConfig::default()
+        };
+        match run_hooks(&config, &mut storage) {
+            Err(TodoError::HookRejected { hook, message }) => {
+                assert_eq!(hook, reject);
+                assert_eq!(message, "tasks need a tag");
+            }
+            other => panic!("expected a rejection, got {:?}", other),
+        }
+
+        let garbage = script(&dir, "garbage", "cat > /dev/null\necho 'not json'\n");
+        let config = HooksConfig {
+            on_add: vec![garbage],
+            ..HooksConfig::default()
+        };
+        assert!(matches!(
+            run_hooks(&config, &mut storage),
+            Err(TodoError::Hook(_))
+        ));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let dir = TempDir::new("hook-timeout");
+        let mut storage = storage_with(&["Slow"]);
+        let config = HooksConfig {
+            on_add: vec![script(&dir, "slow", "sleep 5\n")],
+            timeout_secs: Some(0),
+            ..HooksConfig::default()
+        };
+        let started = Instant::now();
+        let error = run_hooks(&config, &mut storage).unwrap_err();
+        assert_eq!(error.code(), "hook_error");
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // The hook exits at once, but what it left running holds its output.
+        let config = HooksConfig {
+            on_add: vec!["sleep 5 & echo started".to_string()],
+            timeout_secs: Some(1),
+            ..HooksConfig::default()
+        };
+        let started = Instant::now();
+        let error = run_hooks(&config, &mut storage).unwrap_err();
+        assert_eq!(error.code(), "hook_error");
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_shell_command() {
+        let mut storage = storage_with(&["Buy milk"]);
+        let config = HooksConfig {
+            on_add: vec!["grep -q '\"description\":\"Buy milk\"'".to_string()],
+            ..HooksConfig::default()
+        };
+        run_hooks(&config, &mut storage).unwrap();
+
+        let config = HooksConfig {
+            on_add: vec!["echo 'no tags' >&2 && exit 3".to_string()],
+            ..HooksConfig::default()
+        };
+        match run_hooks(&config, &mut storage) {
+            Err(TodoError::HookRejected { message, .. }) => assert_eq!(message, "no tags"),
+            other => panic!("expected a rejection, got {:?}", other),
+        }
+    }
+}
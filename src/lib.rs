@@ -0,0 +1,133 @@
+//! Task management behind the `todo` command line tool.
+//!
+//! The `todo` binary is a thin client: it parses the command line into a
+//! [`cli::Cli`] and hands it to [`run`]. The same building blocks can be used
+//! to write other tools over the data file:
+//!
+//! - [`task`]: [`Task`] and its fields
+//! - [`storage`]: [`TaskStorage`], which loads and saves the data file
+//! - [`filter`]: filter expressions, task references and sorting
+//! - [`date_parser`]: the dates `--due` accepts
+//! - [`merge`]: three-way merges of diverged task lists
+//! - [`renderer`] and [`theme`]: terminal output
+//! - [`output`]: the documents `--output json` prints
+//!
+//! ```
+//! use todo_cli::filter::Filter;
+//! use todo_cli::{Task, TaskStorage};
+//!
+//! let mut storage = TaskStorage::default();
+//! let mut task = Task::new(0, "Write release notes".to_string());
+//! task.tags = vec!["work".to_string()];
+//! let id = storage.add_task(task);
+//! storage.add_task(Task::new(0, "Buy milk".to_string()));
+//!
+//! let filter = Filter::parse("tag:work and open")?;
+//! let work: Vec<&Task> = storage.tasks.iter().filter(|t| filter.matches(t)).collect();
+//! assert_eq!(work.len(), 1);
+//! assert_eq!(work[0].id, id);
+//! # Ok::<(), todo_cli::TodoError>(())
+//! ```
+
+#![warn(missing_docs)]
+
+/// Command line arguments.
+pub mod cli;
+/// The config file.
+pub mod config;
+/// Parsing the dates `--due` and filters accept.
+pub mod date_parser;
+/// Errors and their machine-readable codes.
+pub mod error;
+/// Filter expressions, task selections and sorting.
+pub mod filter;
+/// Three-way merges of task lists that diverged.
+pub mod merge;
+/// JSON documents printed with `--output json`.
+pub mod output;
+/// Terminal output of tasks and messages.
+pub mod renderer;
+/// The data file and the tasks in it.
+pub mod storage;
+/// Tasks and their fields.
+pub mod task;
+/// Colors and styles used by the renderer.
+pub mod theme;
+
+/// Operations shared by the HTTP and JSON-RPC servers.
+mod api;
+/// The archive file that `todo archive` moves old tasks to.
+mod archive;
+/// `todo board`: tasks in columns by state.
+mod board;
+/// `todo burndown`: open tasks over time.
+mod burndown;
+/// `todo calendar`: tasks by due date in a month or week.
+mod calendar;
+/// Execution of each subcommand.
+mod commands;
+/// `todo edit --interactive`: a task edited in `$EDITOR`.
+mod editor;
+/// Commands run when tasks change.
+mod hooks;
+/// Import and export of org-mode files.
+mod org;
+/// `todo rpc`: JSON-RPC over stdio.
+mod rpc;
+/// `todo serve`: the HTTP/JSON API.
+mod server;
+/// `todo shell`: the interactive prompt.
+mod shell;
+/// `todo stats`: counts, trends and busiest tags.
+mod stats;
+/// `todo sync`: the data file shared through a git repository.
+mod sync;
+/// `todo list --table`: tasks as a table.
+mod table;
+/// Output templates for `todo list --format`.
+mod template;
+#[cfg(test)]
+/// Helpers shared by the unit tests.
+mod test_util;
+/// `todo tui`: the full-screen task list.
+mod tui;
+
+pub use error::{Result, TodoError};
+pub use storage::TaskStorage;
+pub use task::{Task, TaskState};
+
+use chrono::{Duration, Local};
+use cli::Cli;
+use commands::{Session, execute};
+use config::{Config, get_config_file_path};
+use storage::get_data_file_path;
+
+/// Run a parsed command line the way `todo` does: load the config file and
+/// the data file, expire old tasks from the trash and execute the command.
+///
+/// # Errors
+/// Returns whatever error the command fails with; the caller reports it.
+pub fn run(cli: Cli) -> Result<()> {
+    let config = Config::load_from_file(&get_config_file_path(cli.config.as_deref()))?;
+    theme::set_theme(config.resolve_theme()?);
+
+    let data_path = get_data_file_path(cli.data_file.as_deref());
+    let mut storage = TaskStorage::load_from_file(&data_path)?;
+    let cutoff = i64::try_from(config.trash_retention_days())
+        .ok()
+        .and_then(Duration::try_days)
+        .and_then(|retention| Local::now().checked_sub_signed(retention));
+    if let Some(cutoff) = cutoff
+        && storage.expire_trash(cutoff) > 0
+    {
+        storage.save_to_file(&data_path)?;
+    }
+
+    let mut session = Session {
+        storage,
+        data_path,
+        config,
+    };
+
+    execute(&mut session, cli.command, cli.output_format)
+}
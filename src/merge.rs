@@ -0,0 +1,451 @@
+use crate::storage::{TaskStorage, TrashedTask};
+use crate::task::Task;
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// One of the two copies being merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The copy the result replaces.
+    Ours,
+    /// The other copy.
+    Theirs,
+}
+
+impl Side {
+    /// Lowercase name, as used on the command line and in JSON.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Ours => "ours",
+            Side::Theirs => "theirs",
+        }
+    }
+}
+
+/// A task that both sides changed in different ways since the common ancestor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The task's UUID.
+    pub uuid: Uuid,
+    /// The field both sides changed, or `None` when one side deleted the
+    /// task and the other changed it.
+    pub field: Option<&'static str>,
+    /// Our version of the task, `None` if we deleted it.
+    pub ours: Option<Task>,
+    /// Their version of the task, `None` if they deleted it.
+    pub theirs: Option<Task>,
+    /// When the task was deleted, if it went to the trash.
+    pub deleted_at: Option<DateTime<Local>>,
+    /// The side the merge took: the most recently changed one until resolved.
+    pub taken: Side,
+}
+
+impl Conflict {
+    /// The task as one of the sides has it.
+    pub fn task(&self) -> &Task {
+        self.ours
+            .as_ref()
+            .or(self.theirs.as_ref())
+            .expect("a conflict has the task on at least one side")
+    }
+
+    /// What `side` has for the conflicting field as plain text, or
+    /// `deleted`/`changed` for a deleted task.
+    pub fn value(&self, side: Side) -> String {
+        match (self.side(side), self.field) {
+            (Some(task), Some(field)) => task.field_value(field),
+            (Some(_), None) => "changed".to_string(),
+            (None, _) => "deleted".to_string(),
+        }
+    }
+
+    /// The version of the task on `side`.
+    pub fn side(&self, side: Side) -> Option<&Task> {
+        match side {
+            Side::Ours => self.ours.as_ref(),
+            Side::Theirs => self.theirs.as_ref(),
+        }
+    }
+}
+
+/// What a merge took from the other copy of the tasks.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Tasks only the other side had.
+    pub added: usize,
+    /// Tasks on both sides that took at least one field from the other side.
+    pub updated: usize,
+    /// Tasks the other side deleted.
+    pub removed: usize,
+    /// `(old, new)` IDs of added tasks whose ID was already taken.
+    pub renumbered: Vec<(u64, u64)>,
+}
+
+/// The result of merging two copies of the task database.
+#[derive(Debug, Clone)]
+pub struct Merge {
+    /// The merged tasks.
+    pub storage: TaskStorage,
+    /// Changes both sides made differently, each already decided one way.
+    pub conflicts: Vec<Conflict>,
+    /// `(old, new)` IDs of tasks from `theirs` whose ID was already taken.
+    pub renumbered: Vec<(u64, u64)>,
+}
+
+/// Merge two copies of the task database that diverged from `base`.
+///
+/// Tasks are matched by UUID. A field changed on one side only takes that
+/// side's value; a field both sides changed differently is a conflict, as is
+/// a task one side deleted and the other changed. Conflicts are decided for
+/// the side that changed last (`ours` on a tie) and can be resolved the
+/// other way with `Merge::resolve`. Tasks deleted on one side and untouched
+/// on the other are deleted, including tasks purged or archived since `base`.
+///
+/// Without a `base` every difference is a conflict, and tasks missing from
+/// one side are kept.
+///
+/// Tasks keep their IDs from `ours`; tasks only in `theirs` keep theirs
+/// unless it is taken, in which case they get a new one.
+pub fn merge(base: Option<&TaskStorage>, ours: &TaskStorage, theirs: &TaskStorage) -> Merge {
+    let base_task = |uuid: Uuid| base.and_then(|base| find_task(base, uuid));
+    let their_tasks: HashMap<Uuid, &Task> =
+        theirs.tasks.iter().map(|task| (task.uuid, task)).collect();
+
+    let mut conflicts = Vec::new();
+    let mut tasks = Vec::new();
+    for task in &ours.tasks {
+        if let Some(their) = their_tasks.get(&task.uuid) {
+            tasks.push(merge_task(
+                base_task(task.uuid),
+                task,
+                their,
+                &mut conflicts,
+            ));
+        } else if let Some(conflict) = deletion(base_task(task.uuid), task, theirs, Side::Ours) {
+            if conflict.taken == Side::Ours {
+                tasks.push(task.clone());
+            }
+            conflicts.push(conflict);
+        } else if trashed(theirs, task.uuid).is_none() && base_task(task.uuid).is_none() {
+            tasks.push(task.clone());
+        }
+    }
+    tasks.sort_by_key(|task| task.id);
+
+    let ids = ours.tasks.iter().chain(&theirs.tasks).map(|task| task.id);
+    let mut merged = Merge {
+        storage: TaskStorage::default(),
+        conflicts: Vec::new(),
+        renumbered: Vec::new(),
+    };
+    merged.storage.next_id = ids.fold(ours.next_id.max(theirs.next_id), |next, id| {
+        next.max(id + 1)
+    });
+    merged.storage.tasks = tasks;
+
+    let ours_uuids: HashSet<Uuid> = ours.tasks.iter().map(|task| task.uuid).collect();
+    for their in &theirs.tasks {
+        if ours_uuids.contains(&their.uuid) {
+            continue;
+        }
+        if let Some(conflict) = deletion(base_task(their.uuid), their, ours, Side::Theirs) {
+            if conflict.taken == Side::Theirs {
+                merged.insert(their.clone());
+            }
+            conflicts.push(conflict);
+        } else if trashed(ours, their.uuid).is_none() && base_task(their.uuid).is_none() {
+            merged.insert(their.clone());
+        }
+    }
+
+    for trashed in ours.trash.iter().chain(&theirs.trash) {
+        if find_task(&merged.storage, trashed.task.uuid).is_none() {
+            merged.storage.trash.push(trashed.clone());
+        }
+    }
+    merged
+        .storage
+        .trash
+        .sort_by_key(|trashed| trashed.deleted_at);
+    merged.conflicts = conflicts;
+    merged
+}
+
+impl Merge {
+    /// Take `side` for the conflict at `index`.
+    pub fn resolve(&mut self, index: usize, side: Side) {
+        let conflict = &mut self.conflicts[index];
+        if conflict.taken == side {
+            return;
+        }
+        conflict.taken = side;
+        let conflict = conflict.clone();
+        let uuid = conflict.uuid;
+
+        match (conflict.field, conflict.side(side)) {
+            (Some(field), Some(task)) => {
+                if let Some(merged) = self.storage.tasks.iter_mut().find(|t| t.uuid == uuid) {
+                    merged.copy_field(task, field);
+                }
+            }
+            (None, Some(task)) => {
+                self.storage
+                    .trash
+                    .retain(|trashed| trashed.task.uuid != uuid);
+                self.insert(task.clone());
+            }
+            (_, None) => {
+                let Some(index) = self.storage.tasks.iter().position(|t| t.uuid == uuid) else {
+                    return;
+                };
+                let task = self.storage.tasks.remove(index);
+                self.renumbered.retain(|&(_, new)| new != task.id);
+                if let Some(deleted_at) = conflict.deleted_at {
+                    self.storage.trash.push(TrashedTask { task, deleted_at });
+                    self.storage.trash.sort_by_key(|trashed| trashed.deleted_at);
+                }
+            }
+        }
+    }
+
+    /// What the merge changed compared to `ours`.
+    pub fn summary(&self, ours: &TaskStorage) -> MergeSummary {
+        let ours_by_uuid: HashMap<Uuid, &Task> =
+            ours.tasks.iter().map(|task| (task.uuid, task)).collect();
+        let merged_uuids: HashSet<Uuid> = self.storage.tasks.iter().map(|task| task.uuid).collect();
+
+        let mut summary = MergeSummary {
+            renumbered: self.renumbered.clone(),
+            ..MergeSummary::default()
+        };
+        for task in &self.storage.tasks {
+            match ours_by_uuid.get(&task.uuid) {
+                Some(our) => summary.updated += usize::from(!task.changed_fields(our).is_empty()),
+                None => summary.added += 1,
+            }
+        }
+        summary.removed = ours_by_uuid
+            .keys()
+            .filter(|uuid| !merged_uuids.contains(uuid))
+            .count();
+        summary
+    }
+
+    /// Add a task from `theirs`, giving it the next free ID if its own is taken.
+    fn insert(&mut self, mut task: Task) {
+        let storage = &mut self.storage;
+        if storage.tasks.iter().any(|other| other.id == task.id) {
+            self.renumbered.push((task.id, storage.next_id));
+            task.id = storage.next_id;
+            storage.next_id += 1;
+        }
+        let index = storage.tasks.partition_point(|other| other.id < task.id);
+        storage.tasks.insert(index, task);
+    }
+}
+
+/// Merge the two versions of a task field by field.
+fn merge_task(
+    base: Option<&Task>,
+    ours: &Task,
+    theirs: &Task,
+    conflicts: &mut Vec<Conflict>,
+) -> Task {
+    let mut merged = ours.clone();
+    for field in ours.changed_fields(theirs) {
+        let take_theirs = match base {
+            Some(base) if base.same_field(ours, field) => true,
+            Some(base) if base.same_field(theirs, field) => false,
+            _ => {
+                let latest = theirs.modified_at(field) > ours.modified_at(field);
+                conflicts.push(Conflict {
+                    uuid: ours.uuid,
+                    field: Some(field),
+                    ours: Some(ours.clone()),
+                    theirs: Some(theirs.clone()),
+                    deleted_at: None,
+                    taken: if latest { Side::Theirs } else { Side::Ours },
+                });
+                latest
+            }
+        };
+        if take_theirs {
+            merged.copy_field(theirs, field);
+        }
+    }
+    merged
+}
+
+/// The conflict when `task`, which only `side` has, was deleted by the other
+/// side after `side` changed it. `None` when it wasn't deleted there or
+/// `side` didn't change it.
+fn deletion(base: Option<&Task>, task: &Task, other: &TaskStorage, side: Side) -> Option<Conflict> {
+    let deleted_at = trashed(other, task.uuid);
+    if deleted_at.is_none() && base.is_none() {
+        return None;
+    }
+    if base.is_some_and(|base| base.changed_fields(task).is_empty()) {
+        return None;
+    }
+
+    let keep = deleted_at.is_none_or(|at| task.last_modified() > at);
+    let (ours, theirs, deleted_side) = match side {
+        Side::Ours => (Some(task.clone()), None, Side::Theirs),
+        Side::Theirs => (None, Some(task.clone()), Side::Ours),
+    };
+    Some(Conflict {
+        uuid: task.uuid,
+        field: None,
+        ours,
+        theirs,
+        deleted_at,
+        taken: if keep { side } else { deleted_side },
+    })
+}
+
+/// When the task went to the storage's trash, if it is there.
+fn trashed(storage: &TaskStorage, uuid: Uuid) -> Option<DateTime<Local>> {
+    storage
+        .trash
+        .iter()
+        .find(|trashed| trashed.task.uuid == uuid)
+        .map(|trashed| trashed.deleted_at)
+}
+
+/// A live or trashed task of the storage.
+fn find_task(storage: &TaskStorage, uuid: Uuid) -> Option<&Task> {
+    let trashed = storage.trash.iter().map(|trashed| &trashed.task);
+    storage
+        .tasks
+        .iter()
+        .chain(trashed)
+        .find(|task| task.uuid == uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 1, hour, 0, 0).unwrap()
+    }
+
+    fn storage(tasks: &[&Task]) -> TaskStorage {
+        let mut storage = TaskStorage::default();
+        for &task in tasks {
+            storage.tasks.push(task.clone());
+            storage.next_id = storage.next_id.max(task.id + 1);
+        }
+        storage
+    }
+
+    fn task(id: u64, description: &str) -> Task {
+        let mut task = Task::new(id, description.to_string());
+        task.created_at = at(1);
+        task
+    }
+
+    fn edited(task: &Task, hour: u32, edit: impl FnOnce(&mut Task)) -> Task {
+        let mut edited = task.clone();
+        edit(&mut edited);
+        edited.stamp_changes(task, at(hour));
+        edited
+    }
+
+    fn descriptions(storage: &TaskStorage) -> Vec<(u64, &str)> {
+        storage
+            .tasks
+            .iter()
+            .map(|task| (task.id, task.description.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_fields() {
+        let original = task(1, "Write report");
+        let ours = edited(&original, 3, |t| t.priority = Some(1));
+        let ours = edited(&ours, 5, |t| t.description = "Write the report".to_string());
+        let theirs = edited(&original, 4, |t| {
+            t.priority = Some(2);
+            t.tags = vec!["work".to_string()];
+        });
+
+        let base = storage(&[&original]);
+        let ours = storage(&[&ours]);
+        let mut merged = merge(Some(&base), &ours, &storage(&[&theirs]));
+        let task = &merged.storage.tasks[0];
+        assert_eq!(task.description, "Write the report");
+        assert_eq!(task.tags, ["work"]);
+        assert_eq!(task.priority, Some(2));
+        assert_eq!(task.modified_at("priority"), at(4));
+        assert_eq!(merged.summary(&ours).updated, 1);
+
+        // Only the priority was changed on both sides.
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].field, Some("priority"));
+        assert_eq!(merged.conflicts[0].taken, Side::Theirs);
+        merged.resolve(0, Side::Ours);
+        assert_eq!(merged.storage.tasks[0].priority, Some(1));
+        assert_eq!(merged.storage.tasks[0].tags, ["work"]);
+    }
+
+    #[test]
+    fn test_merge_additions_and_deletions() {
+        let kept = task(1, "Kept");
+        let purged = task(2, "Purged on their side");
+        let deleted = task(3, "Deleted on our side");
+        let base = storage(&[&kept, &purged, &deleted]);
+
+        let mut ours = storage(&[&kept, &purged, &task(4, "Ours")]);
+        ours.trash.push(TrashedTask {
+            task: deleted.clone(),
+            deleted_at: at(2),
+        });
+        let theirs = storage(&[&kept, &deleted, &task(4, "Theirs")]);
+
+        let merged = merge(Some(&base), &ours, &theirs);
+        assert_eq!(
+            descriptions(&merged.storage),
+            [(1, "Kept"), (4, "Ours"), (5, "Theirs")]
+        );
+        assert!(merged.conflicts.is_empty());
+        let summary = merged.summary(&ours);
+        assert_eq!((summary.added, summary.removed), (1, 1));
+        assert_eq!(summary.renumbered, [(4, 5)]);
+        assert_eq!(merged.storage.trash.len(), 1);
+        assert_eq!(merged.storage.next_id, 6);
+
+        // Without a common ancestor nothing is dropped.
+        let merged = merge(None, &ours, &storage(&[&kept]));
+        assert_eq!(merged.storage.tasks.len(), 3);
+    }
+
+    #[test]
+    fn test_deleted_and_changed() {
+        let original = task(1, "Call the bank");
+        let base = storage(&[&original]);
+        let mut ours = storage(&[]);
+        ours.trash.push(TrashedTask {
+            task: original.clone(),
+            deleted_at: at(2),
+        });
+
+        // Changed after it was deleted on our side: it comes back.
+        let theirs = storage(&[&edited(&original, 3, |t| t.priority = Some(1))]);
+        let mut merged = merge(Some(&base), &ours, &theirs);
+        assert_eq!(merged.storage.tasks.len(), 1);
+        assert!(merged.storage.trash.is_empty());
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].field, None);
+        assert_eq!(merged.conflicts[0].taken, Side::Theirs);
+
+        merged.resolve(0, Side::Ours);
+        assert!(merged.storage.tasks.is_empty());
+        assert_eq!(merged.storage.trash.len(), 1);
+        merged.resolve(0, Side::Theirs);
+        assert_eq!(merged.storage.tasks.len(), 1);
+        assert!(merged.storage.trash.is_empty());
+    }
+}
@@ -0,0 +1,695 @@
+use crate::error::{Result, TodoError};
+use crate::task::{TRACKED_FIELDS, Task, TaskState};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Timelike};
+
+/// Property holding the task ID inside the `:PROPERTIES:` drawer.
+const ID_PROPERTY: &str = "TODO_ID";
+/// Property holding the task UUID, org-mode's own `ID` property.
+const UUID_PROPERTY: &str = "ID";
+/// Property holding the UUID of the task's parent, for subtasks.
+const PARENT_PROPERTY: &str = "PARENT";
+/// Property holding the creation timestamp inside the `:PROPERTIES:` drawer.
+const CREATED_PROPERTY: &str = "CREATED";
+/// Planning keyword we don't map to a task field but keep in `properties`.
+const SCHEDULED_KEYWORD: &str = "SCHEDULED";
+/// Properties holding the exact RFC 3339 creation, completion and due
+/// timestamps, written only when the org timestamp would lose precision.
+const CREATED_AT_PROPERTY: &str = "CREATED_AT";
+const CLOSED_AT_PROPERTY: &str = "CLOSED_AT";
+const DEADLINE_AT_PROPERTY: &str = "DEADLINE_AT";
+/// Property holding the note given when the task was completed, with
+/// backslashes and line breaks escaped as `\\` and `\n`.
+const COMPLETION_NOTE_PROPERTY: &str = "COMPLETION_NOTE";
+/// Prefix of the properties holding when each tracked field last changed,
+/// e.g. `MODIFIED_DUE_DATE`, as RFC 3339.
+const MODIFIED_PREFIX: &str = "MODIFIED_";
+
+/// Serialize tasks into an org-mode document.
+///
+/// Each task becomes a top-level headline:
+///
+/// ```org
+/// * TODO [#A] Fix bug :work:urgent:
+/// DEADLINE: <2025-07-15 Tue>
+/// :PROPERTIES:
+/// :TODO_ID: 2
+/// :CREATED: [2025-07-08 Tue 13:29]
+/// :END:
+/// Notes go into the body.
+/// ```
+///
+/// Priorities 1-5 map to `[#A]`-`[#E]`; started and waiting tasks use the
+/// `STARTED` and `WAITING` keywords instead of `TODO`. Properties stored on
+/// the task are written back into the drawer so org files survive a
+/// round-trip. Subtasks get a `PARENT` property with the parent's UUID, and
+/// the modification time of each changed field goes into a `MODIFIED_<FIELD>`
+/// property so `todo sync` can still merge the tasks after a round-trip. Org
+/// timestamps only go down to the minute, so timestamps with seconds are also
+/// written as RFC 3339 into `CREATED_AT`, `CLOSED_AT` and `DEADLINE_AT`. Note
+/// lines that org-mode would read as a headline, planning line or drawer are
+/// escaped with a leading comma, as org does in blocks.
+pub fn to_org(tasks: &[Task]) -> String {
+    let mut output = String::new();
+
+    for task in tasks {
+        output.push_str("* ");
+        output.push_str(match (task.completed, task.state) {
+            (true, _) => "DONE",
+            (false, TaskState::Pending) => "TODO",
+            (false, TaskState::Started) => "STARTED",
+            (false, TaskState::Waiting) => "WAITING",
+        });
+
+        if let Some(priority) = task.priority {
+            output.push_str(&format!(" [#{}]", priority_to_letter(priority)));
+        }
+
+        output.push(' ');
+        output.push_str(&task.description);
+
+        if !task.tags.is_empty() {
+            output.push_str(&format!(" :{}:", task.tags.join(":")));
+        }
+        output.push('\n');
+
+        let mut planning = Vec::new();
+        if let Some(completed_at) = task.completed_at {
+            planning.push(format!("CLOSED: {}", format_timestamp(completed_at, false)));
+        }
+        if let Some(due_date) = task.due_date {
+            planning.push(format!("DEADLINE: {}", format_timestamp(due_date, true)));
+        }
+        if let Some(scheduled) = task.properties.get(SCHEDULED_KEYWORD) {
+            planning.push(format!("{}: {}", SCHEDULED_KEYWORD, scheduled));
+        }
+        if !planning.is_empty() {
+            output.push_str(&planning.join(" "));
+            output.push('\n');
+        }
+
+        output.push_str(":PROPERTIES:\n");
+        output.push_str(&format!(":{}: {}\n", ID_PROPERTY, task.id));
+        output.push_str(&format!(":{}: {}\n", UUID_PROPERTY, task.uuid));
+        if let Some(parent) = task.parent {
+            output.push_str(&format!(":{}: {}\n", PARENT_PROPERTY, parent));
+        }
+        output.push_str(&format!(
+            ":{}: {}\n",
+            CREATED_PROPERTY,
+            format_timestamp(task.created_at, false)
+        ));
+        let exact = [
+            (CREATED_AT_PROPERTY, Some(task.created_at), false),
+            (CLOSED_AT_PROPERTY, task.completed_at, false),
+            (DEADLINE_AT_PROPERTY, task.due_date, true),
+        ];
+        for (key, date, active) in exact {
+            if let Some(date) = date.filter(|&date| !is_exact(date, active)) {
+                output.push_str(&format!(":{}: {}\n", key, date.to_rfc3339()));
+            }
+        }
+        if let Some(note) = &task.completion_note {
+            output.push_str(&format!(
+                ":{}: {}\n",
+                COMPLETION_NOTE_PROPERTY,
+                escape_property(note)
+            ));
+        }
+        for (field, at) in &task.modified {
+            output.push_str(&format!(
+                ":{}{}: {}\n",
+                MODIFIED_PREFIX,
+                field.to_uppercase(),
+                at.to_rfc3339()
+            ));
+        }
+        for (key, value) in &task.properties {
+            if key == SCHEDULED_KEYWORD {
+                continue;
+            }
+            output.push_str(&format!(":{}: {}\n", key, value));
+        }
+        output.push_str(":END:\n");
+
+        if let Some(notes) = &task.notes {
+            for (index, line) in notes.lines().enumerate() {
+                if needs_escape(line, index == 0) {
+                    output.push(',');
+                }
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+/// Parse an org-mode document into tasks.
+///
+/// Only headlines with a `TODO`, `STARTED`, `WAITING` or `DONE` keyword are
+/// imported; any other headline or text is ignored. Unknown drawer properties
+/// are kept in `Task::properties`. Task IDs are read from the `TODO_ID`
+/// property when present and default to 0 otherwise. The RFC 3339
+/// `CREATED_AT`, `CLOSED_AT` and `DEADLINE_AT` properties take precedence
+/// over the org timestamps they refine. The body of a headline becomes the
+/// task's notes, without surrounding blank lines.
+///
+/// # Errors
+/// Returns a `TodoError::OrgParse` if a timestamp, priority or drawer is
+/// malformed.
+pub fn from_org(input: &str) -> Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+    let mut current: Option<Task> = None;
+    let mut in_drawer = false;
+    let mut body: Vec<&str> = Vec::new();
+
+    for (index, raw_line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+
+        if raw_line.starts_with('*') {
+            if in_drawer {
+                return Err(org_error(
+                    line_number,
+                    "headline inside an unterminated drawer",
+                ));
+            }
+            if let Some(task) = current.take() {
+                tasks.push(finish(task, std::mem::take(&mut body)));
+            }
+            current = parse_headline(raw_line, line_number)?;
+            continue;
+        }
+
+        let Some(task) = current.as_mut() else {
+            continue;
+        };
+
+        if in_drawer {
+            if line.eq_ignore_ascii_case(":END:") {
+                in_drawer = false;
+            } else {
+                parse_property(task, line, line_number)?;
+            }
+        } else if !body.is_empty() {
+            body.push(unescape(raw_line));
+        } else if line.eq_ignore_ascii_case(":PROPERTIES:") {
+            in_drawer = true;
+        } else if is_planning_line(line) {
+            parse_planning(task, line, line_number)?;
+        } else if !line.is_empty() {
+            body.push(unescape(raw_line));
+        }
+    }
+
+    if in_drawer {
+        return Err(org_error(
+            input.lines().count(),
+            "unterminated :PROPERTIES: drawer",
+        ));
+    }
+    if let Some(task) = current.take() {
+        tasks.push(finish(task, body));
+    }
+
+    Ok(tasks)
+}
+
+fn parse_headline(line: &str, line_number: usize) -> Result<Option<Task>> {
+    let rest = line.trim_start_matches('*');
+    if !rest.starts_with(' ') {
+        return Ok(None);
+    }
+    let rest = rest.trim();
+
+    let (keyword, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let (completed, state) = match keyword {
+        "TODO" => (false, TaskState::Pending),
+        "STARTED" => (false, TaskState::Started),
+        "WAITING" => (false, TaskState::Waiting),
+        "DONE" => (true, TaskState::Pending),
+        _ => return Ok(None),
+    };
+    let mut rest = rest.trim();
+
+    let mut priority = None;
+    if let Some(after) = rest.strip_prefix("[#") {
+        let (letter, after) = after
+            .split_once(']')
+            .ok_or_else(|| org_error(line_number, "unterminated priority cookie"))?;
+        priority =
+            Some(letter_to_priority(letter).ok_or_else(|| {
+                org_error(line_number, &format!("invalid priority '[#{}]'", letter))
+            })?);
+        rest = after.trim();
+    }
+
+    let mut tags = Vec::new();
+    if let Some((title, last)) = rest.rsplit_once(char::is_whitespace)
+        && is_tag_group(last)
+    {
+        tags = split_tags(last);
+        rest = title.trim_end();
+    } else if is_tag_group(rest) {
+        tags = split_tags(rest);
+        rest = "";
+    }
+
+    let mut task = Task::new(0, rest.to_string());
+    task.priority = priority;
+    task.tags = tags;
+    task.completed = completed;
+    task.state = state;
+    Ok(Some(task))
+}
+
+fn parse_property(task: &mut Task, line: &str, line_number: usize) -> Result<()> {
+    let body = line
+        .strip_prefix(':')
+        .ok_or_else(|| org_error(line_number, "expected ':KEY: value' inside drawer"))?;
+    let (key, value) = body
+        .split_once(':')
+        .ok_or_else(|| org_error(line_number, "expected ':KEY: value' inside drawer"))?;
+    let value = value.trim();
+
+    match key {
+        ID_PROPERTY => {
+            task.id = value
+                .parse()
+                .map_err(|_| org_error(line_number, &format!("invalid task ID '{}'", value)))?;
+        }
+        UUID_PROPERTY => {
+            task.uuid = value
+                .parse()
+                .map_err(|_| org_error(line_number, &format!("invalid UUID '{}'", value)))?;
+        }
+        PARENT_PROPERTY => {
+            let parent = value
+                .parse()
+                .map_err(|_| org_error(line_number, &format!("invalid UUID '{}'", value)))?;
+            task.parent = Some(parent);
+        }
+        CREATED_PROPERTY => {
+            task.created_at = parse_timestamp(value)
+                .ok_or_else(|| org_error(line_number, &format!("invalid timestamp '{}'", value)))?;
+        }
+        COMPLETION_NOTE_PROPERTY => {
+            task.completion_note = Some(unescape_property(value));
+        }
+        CREATED_AT_PROPERTY | CLOSED_AT_PROPERTY | DEADLINE_AT_PROPERTY => {
+            // Kept as a property until the whole entry is read, so it wins
+            // over the org timestamp wherever that appears.
+            DateTime::parse_from_rfc3339(value)
+                .map_err(|_| org_error(line_number, &format!("invalid timestamp '{}'", value)))?;
+            task.properties.insert(key.to_string(), value.to_string());
+        }
+        _ => {
+            if let Some(field) = key.strip_prefix(MODIFIED_PREFIX).map(str::to_lowercase)
+                && TRACKED_FIELDS.contains(&field.as_str())
+            {
+                let at = DateTime::parse_from_rfc3339(value).map_err(|_| {
+                    org_error(line_number, &format!("invalid timestamp '{}'", value))
+                })?;
+                task.modified.insert(field, at.with_timezone(&Local));
+            } else {
+                task.properties.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply the body and exact timestamps of a fully parsed entry.
+fn finish(mut task: Task, mut body: Vec<&str>) -> Task {
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+    if !body.is_empty() {
+        task.notes = Some(body.join("\n"));
+    }
+
+    let mut exact = |key: &str| {
+        task.properties
+            .remove(key)
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|date| date.with_timezone(&Local))
+    };
+    let created_at = exact(CREATED_AT_PROPERTY);
+    let completed_at = exact(CLOSED_AT_PROPERTY);
+    let due_date = exact(DEADLINE_AT_PROPERTY);
+
+    if let Some(created_at) = created_at {
+        task.created_at = created_at;
+    }
+    if completed_at.is_some() {
+        task.completed_at = completed_at;
+    }
+    if due_date.is_some() {
+        task.due_date = due_date;
+    }
+    task
+}
+
+fn escape_property(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape_property(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Whether a note line would be read back as something other than text. Only
+/// the first line can be taken for a planning line or drawer.
+fn needs_escape(line: &str, first: bool) -> bool {
+    let trimmed = line.trim();
+    line.starts_with(['*', ',']) || first && (trimmed.starts_with(':') || is_planning_line(trimmed))
+}
+
+fn unescape(line: &str) -> &str {
+    line.strip_prefix(',').unwrap_or(line)
+}
+
+fn is_planning_line(line: &str) -> bool {
+    ["DEADLINE:", "CLOSED:", "SCHEDULED:"]
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+}
+
+fn parse_planning(task: &mut Task, line: &str, line_number: usize) -> Result<()> {
+    let mut rest = line.trim();
+
+    while !rest.is_empty() {
+        let (keyword, after) = rest
+            .split_once(':')
+            .ok_or_else(|| org_error(line_number, "malformed planning line"))?;
+        let after = after.trim_start();
+        let close = match after.chars().next() {
+            Some('<') => '>',
+            Some('[') => ']',
+            _ => {
+                return Err(org_error(
+                    line_number,
+                    "expected a timestamp after planning keyword",
+                ));
+            }
+        };
+        let end = after
+            .find(close)
+            .ok_or_else(|| org_error(line_number, "unterminated timestamp"))?;
+        let stamp = &after[..=end];
+        let invalid = || org_error(line_number, &format!("invalid timestamp '{}'", stamp));
+
+        match keyword.trim() {
+            "DEADLINE" => task.due_date = Some(parse_timestamp(stamp).ok_or_else(invalid)?),
+            "CLOSED" => task.completed_at = Some(parse_timestamp(stamp).ok_or_else(invalid)?),
+            SCHEDULED_KEYWORD => {
+                task.properties
+                    .insert(SCHEDULED_KEYWORD.to_string(), stamp.to_string());
+            }
+            other => {
+                return Err(org_error(
+                    line_number,
+                    &format!("unknown planning keyword '{}'", other),
+                ));
+            }
+        }
+
+        rest = after[end + 1..].trim_start();
+    }
+
+    Ok(())
+}
+
+/// Format a timestamp the way org-mode writes it.
+///
+/// Active timestamps (`<...>`) are used for deadlines. A deadline at 23:59:59
+/// is the repo-wide convention for "date only", so it is written without a
+/// time.
+fn format_timestamp(date: DateTime<Local>, active: bool) -> String {
+    let (open, close) = if active { ('<', '>') } else { ('[', ']') };
+    let is_end_of_day = date.hour() == 23 && date.minute() == 59 && date.second() == 59;
+
+    if active && is_end_of_day {
+        format!("{}{}{}", open, date.format("%Y-%m-%d %a"), close)
+    } else {
+        format!("{}{}{}", open, date.format("%Y-%m-%d %a %H:%M"), close)
+    }
+}
+
+/// Parse an org timestamp such as `<2025-07-15 Tue>` or
+/// `[2025-07-08 Tue 13:29]`.
+///
+/// Timestamps without a time of day resolve to 23:59:59, matching `parse_date`.
+fn parse_timestamp(stamp: &str) -> Option<DateTime<Local>> {
+    let inner = stamp
+        .trim()
+        .strip_prefix(['<', '['])?
+        .strip_suffix(['>', ']'])?;
+    let mut parts = inner.split_whitespace();
+
+    let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+    let time = parts
+        .find_map(|part| NaiveTime::parse_from_str(part, "%H:%M").ok())
+        .unwrap_or_else(|| NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+
+    Local.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+/// Whether `format_timestamp` keeps all of `date`.
+fn is_exact(date: DateTime<Local>, active: bool) -> bool {
+    parse_timestamp(&format_timestamp(date, active)) == Some(date)
+}
+
+fn is_tag_group(word: &str) -> bool {
+    word.len() > 2
+        && word.starts_with(':')
+        && word.ends_with(':')
+        && !word.contains(char::is_whitespace)
+}
+
+fn split_tags(group: &str) -> Vec<String> {
+    group
+        .split(':')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn priority_to_letter(priority: u8) -> char {
+    (b'A' + priority.saturating_sub(1)) as char
+}
+
+fn letter_to_priority(letter: &str) -> Option<u8> {
+    match letter {
+        "A" => Some(1),
+        "B" => Some(2),
+        "C" => Some(3),
+        "D" => Some(4),
+        "E" => Some(5),
+        _ => None,
+    }
+}
+
+fn org_error(line: usize, message: &str) -> TodoError {
+    TodoError::OrgParse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, hour: u32, minute: u32, second: u32) -> DateTime<Local> {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Local
+            .from_local_datetime(&date.and_hms_opt(hour, minute, second).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_to_org_headline() {
+        let mut task = Task::new(3, "Fix bug".to_string());
+        task.priority = Some(1);
+        task.tags = vec!["work".to_string(), "urgent".to_string()];
+        task.due_date = Some(at("2025-07-15", 23, 59, 59));
+        task.created_at = at("2025-07-08", 13, 29, 0);
+        task.uuid = "5f1c2a4e-8d3b-4c6a-9e7f-0a1b2c3d4e5f".parse().unwrap();
+
+        let org = to_org(&[task]);
+        assert_eq!(
+            org,
+            "* TODO [#A] Fix bug :work:urgent:\n\
+             DEADLINE: <2025-07-15 Tue>\n\
+             :PROPERTIES:\n\
+             :TODO_ID: 3\n\
+             :ID: 5f1c2a4e-8d3b-4c6a-9e7f-0a1b2c3d4e5f\n\
+             :CREATED: [2025-07-08 Tue 13:29]\n\
+             :END:\n"
+        );
+    }
+
+    #[test]
+    fn test_from_org_round_trip_is_lossless() {
+        let input = "* DONE [#C] Write report :docs:\n\
+                     CLOSED: [2025-07-10 Thu 09:15] DEADLINE: <2025-07-12 Sat 17:00> SCHEDULED: <2025-07-09 Wed>\n\
+                     :PROPERTIES:\n\
+                     :TODO_ID: 7\n\
+                     :ID: 0b7e6f4c-3a2d-4e1f-8c9b-5a6d7e8f9a0b\n\
+                     :PARENT: 5f1c2a4e-8d3b-4c6a-9e7f-0a1b2c3d4e5f\n\
+                     :CREATED: [2025-07-01 Tue 08:00]\n\
+                     :EFFORT: 1:30\n\
+                     :OWNER: kevin\n\
+                     :END:\n";
+
+        let tasks = from_org(input).unwrap();
+        assert_eq!(tasks.len(), 1);
+        let task = &tasks[0];
+        assert_eq!(task.id, 7);
+        assert_eq!(
+            task.uuid.to_string(),
+            "0b7e6f4c-3a2d-4e1f-8c9b-5a6d7e8f9a0b"
+        );
+        assert_eq!(
+            task.parent.map(|uuid| uuid.to_string()).as_deref(),
+            Some("5f1c2a4e-8d3b-4c6a-9e7f-0a1b2c3d4e5f")
+        );
+        assert_eq!(task.description, "Write report");
+        assert_eq!(task.priority, Some(3));
+        assert!(task.completed);
+        assert_eq!(task.tags, vec!["docs".to_string()]);
+        assert_eq!(task.due_date, Some(at("2025-07-12", 17, 0, 0)));
+        assert_eq!(task.completed_at, Some(at("2025-07-10", 9, 15, 0)));
+        assert_eq!(
+            task.properties.get("EFFORT").map(String::as_str),
+            Some("1:30")
+        );
+
+        assert_eq!(to_org(&tasks), input);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_seconds() {
+        let mut task = Task::new(4, "Call back".to_string());
+        task.created_at = at("2025-07-08", 13, 29, 7) + chrono::Duration::microseconds(250);
+        task.due_date = Some(at("2025-07-15", 17, 0, 30));
+        task.complete();
+        task.completed_at = Some(at("2025-07-10", 9, 15, 42));
+
+        let org = to_org(std::slice::from_ref(&task));
+        assert!(org.contains("CLOSED: [2025-07-10 Thu 09:15]"));
+        assert!(org.contains(&format!(":CREATED_AT: {}\n", task.created_at.to_rfc3339())));
+
+        let parsed = from_org(&org).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].created_at, task.created_at);
+        assert_eq!(parsed[0].completed_at, task.completed_at);
+        assert_eq!(parsed[0].due_date, task.due_date);
+        assert!(parsed[0].properties.is_empty());
+        assert_eq!(to_org(&parsed), org);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_notes() {
+        let mut task = Task::new(5, "Plan talk".to_string());
+        task.created_at = at("2025-07-08", 13, 29, 0);
+        task.notes = Some(
+            ":PROPERTIES:\n* not a headline\n\n  DEADLINE: not planning\n,kept comma".to_string(),
+        );
+
+        let org = to_org(std::slice::from_ref(&task));
+        assert!(org.ends_with(
+            ":END:\n,:PROPERTIES:\n,* not a headline\n\n  DEADLINE: not planning\n,,kept comma\n"
+        ));
+        let parsed = from_org(&org).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].notes, task.notes);
+
+        let parsed = from_org("* TODO Bare\n\n  Some text\n\n* TODO Empty\n").unwrap();
+        assert_eq!(parsed[0].notes.as_deref(), Some("  Some text"));
+        assert_eq!(parsed[1].notes, None);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_completion_note() {
+        let mut task = Task::new(6, "Ship release".to_string());
+        task.created_at = at("2025-07-08", 13, 29, 0);
+        task.complete();
+        task.completed_at = Some(at("2025-07-10", 9, 15, 0));
+        task.completion_note = Some("Tagged v1.2\nsee C:\\builds\\n".to_string());
+
+        let org = to_org(std::slice::from_ref(&task));
+        assert!(org.contains(":COMPLETION_NOTE: Tagged v1.2\\nsee C:\\\\builds\\\\n\n"));
+        let parsed = from_org(&org).unwrap();
+        assert_eq!(parsed[0].completion_note, task.completion_note);
+        assert!(parsed[0].properties.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_keeps_modification_times() {
+        let mut task = Task::new(8, "Review budget".to_string());
+        task.created_at = at("2025-07-08", 13, 29, 0);
+        task.modified
+            .insert("due_date".to_string(), at("2025-07-09", 10, 0, 5));
+        task.modified
+            .insert("tags".to_string(), at("2025-07-09", 11, 30, 0));
+
+        let org = to_org(std::slice::from_ref(&task));
+        assert!(org.contains(&format!(
+            ":MODIFIED_DUE_DATE: {}\n",
+            at("2025-07-09", 10, 0, 5).to_rfc3339()
+        )));
+        let parsed = from_org(&org).unwrap();
+        assert_eq!(parsed[0].modified, task.modified);
+        assert!(parsed[0].properties.is_empty());
+
+        // Only tracked fields count; anything else stays a plain property.
+        let parsed = from_org("* TODO X\n:PROPERTIES:\n:MODIFIED_BY: kevin\n:END:\n").unwrap();
+        assert!(parsed[0].modified.is_empty());
+        assert_eq!(
+            parsed[0].properties.get("MODIFIED_BY").map(String::as_str),
+            Some("kevin")
+        );
+    }
+
+    #[test]
+    fn test_from_org_skips_non_task_headlines() {
+        let input = "#+TITLE: Tasks\n* Notes\nSome text\n** TODO Nested task\n";
+        let tasks = from_org(input).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Nested task");
+        assert_eq!(tasks[0].due_date, None);
+    }
+
+    #[test]
+    fn test_from_org_rejects_bad_parent() {
+        let input = "* TODO Child\n:PROPERTIES:\n:PARENT: 12\n:END:\n";
+        assert!(matches!(
+            from_org(input),
+            Err(TodoError::OrgParse { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_org_rejects_bad_priority() {
+        let result = from_org("* TODO [#Z] Broken\n");
+        assert!(matches!(result, Err(TodoError::OrgParse { line: 1, .. })));
+    }
+}
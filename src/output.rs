@@ -0,0 +1,452 @@
+use crate::board::BoardColumn;
+use crate::burndown::DayPoint;
+use crate::cli::OutputFormat;
+use crate::error::TodoError;
+use crate::merge::{Conflict, MergeSummary, Side};
+use crate::stats::Stats;
+use crate::storage::{Completed, TagCount, TrashedTask};
+use crate::sync::{Pull, SyncReport};
+use crate::task::Task;
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, Utc};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Counts shown below `todo list` and included in its JSON output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    /// Number of tasks matching the filters.
+    pub shown: usize,
+    /// Number of tasks in storage.
+    pub total: usize,
+    /// Number of completed tasks in storage.
+    pub completed: usize,
+    /// Number of overdue tasks in storage.
+    pub overdue: usize,
+}
+
+/// Convert a task into its stable JSON representation.
+///
+/// Every key is always present; unset optional fields are `null`.
+/// Timestamps are RFC 3339 in UTC with second precision.
+pub fn task_json(task: &Task) -> Value {
+    json!({
+        "id": task.id,
+        "uuid": task.uuid.to_string(),
+        "description": task.description,
+        "priority": task.priority,
+        "due_date": task.due_date.map(timestamp),
+        "tags": task.tags,
+        "parent": task.parent.map(|uuid| uuid.to_string()),
+        "completed": task.completed,
+        "state": task.state,
+        "overdue": task.is_overdue(),
+        "created_at": timestamp(task.created_at),
+        "completed_at": task.completed_at.map(timestamp),
+        "completion_note": task.completion_note,
+        "notes": task.notes,
+        "properties": task.properties,
+    })
+}
+
+/// JSON document for `todo list`.
+pub fn list_json(tasks: &[&Task], summary: Summary) -> Value {
+    json!({
+        "tasks": tasks.iter().map(|task| task_json(task)).collect::<Vec<_>>(),
+        "summary": {
+            "shown": summary.shown,
+            "total": summary.total,
+            "completed": summary.completed,
+            "overdue": summary.overdue,
+        },
+    })
+}
+
+/// JSON document for `todo calendar`: the shown range and the days that have tasks.
+pub fn calendar_json(
+    start: NaiveDate,
+    end: NaiveDate,
+    days: &BTreeMap<NaiveDate, Vec<&Task>>,
+) -> Value {
+    json!({
+        "start": start.to_string(),
+        "end": end.to_string(),
+        "days": days
+            .iter()
+            .map(|(date, tasks)| json!({
+                "date": date.to_string(),
+                "tasks": tasks.iter().map(|task| task_json(task)).collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for `todo board`: one entry per column.
+pub(crate) fn board_json(columns: &[BoardColumn]) -> Value {
+    json!({
+        "columns": columns
+            .iter()
+            .map(|column| json!({
+                "name": column.name,
+                "count": column.tasks.len(),
+                "limit": column.limit,
+                "over_limit": column.over_limit(),
+                "tasks": column.tasks.iter().map(|task| task_json(task)).collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for `todo stats`.
+///
+/// The lead time is in seconds; ratios are between 0 and 1.
+pub(crate) fn stats_json(stats: &Stats) -> Value {
+    json!({
+        "from": stats.from.to_string(),
+        "to": stats.to.to_string(),
+        "created": stats.created,
+        "completed": stats.completed,
+        "completion_rate": stats.completion_rate,
+        "average_lead_time_seconds": stats.average_lead_time.map(|d| d.num_seconds()),
+        "completed_late": stats.completed_late,
+        "open": stats.open,
+        "overdue": stats.overdue,
+        "busiest_tags": stats
+            .busiest_tags
+            .iter()
+            .map(|(tag, count)| json!({ "tag": tag, "count": count }))
+            .collect::<Vec<_>>(),
+        "periods": stats
+            .periods
+            .iter()
+            .map(|period| json!({
+                "start": period.start.to_string(),
+                "created": period.created,
+                "completed": period.completed,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for `todo burndown`: one entry per day.
+pub(crate) fn burndown_json(points: &[DayPoint]) -> Value {
+    json!({
+        "days": points
+            .iter()
+            .map(|point| json!({
+                "date": point.date.to_string(),
+                "open": point.open,
+                "completed": point.completed,
+                "total": point.total,
+                "ideal": point.ideal,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for a command that changed a single task.
+pub fn action_json(action: &str, task: &Task) -> Value {
+    json!({
+        "action": action,
+        "id": task.id,
+        "task": task_json(task),
+    })
+}
+
+/// JSON document for a command that changed several tasks at once.
+pub fn bulk_action_json(action: &str, tasks: &[&Task]) -> Value {
+    json!({
+        "action": action,
+        "ids": tasks.iter().map(|task| task.id).collect::<Vec<_>>(),
+        "tasks": tasks.iter().map(|task| task_json(task)).collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for completing a single task.
+pub fn complete_json(task: &Task, completed: &Completed) -> Value {
+    let mut value = action_json("complete", task);
+    value["already_completed"] = (!completed.already_completed.is_empty()).into();
+    value["open_subtasks"] = completed.open_subtasks.clone().into();
+    value["cascaded"] = completed.cascaded.clone().into();
+    value
+}
+
+/// JSON document for completing several tasks at once.
+pub fn bulk_complete_json(tasks: &[&Task], completed: &Completed) -> Value {
+    let mut value = bulk_action_json("complete", tasks);
+    value["already_completed"] = completed.already_completed.clone().into();
+    value["open_subtasks"] = completed.open_subtasks.clone().into();
+    value["cascaded"] = completed.cascaded.clone().into();
+    value
+}
+
+/// JSON document for `todo tags`.
+pub fn tags_json(counts: &[TagCount]) -> Value {
+    json!({
+        "tags": counts
+            .iter()
+            .map(|count| json!({
+                "tag": count.tag,
+                "tasks": count.tasks,
+                "open": count.open,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for `todo tags rename` and `todo tags merge`.
+pub fn tag_change_json(action: &str, from: &str, to: &str, ids: &[u64]) -> Value {
+    json!({
+        "action": action,
+        "from": from,
+        "to": to,
+        "ids": ids,
+    })
+}
+
+/// JSON document for `todo trash`, most recently deleted first.
+pub fn trash_json(trash: &[TrashedTask]) -> Value {
+    json!({
+        "trash": trash
+            .iter()
+            .rev()
+            .map(|trashed| json!({
+                "deleted_at": timestamp(trashed.deleted_at),
+                "task": task_json(&trashed.task),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for `todo compact`: each renumbered task's UUID with its
+/// old and new ID.
+pub fn compact_json(changes: &[(u64, u64)], uuids: &[String], dry_run: bool) -> Value {
+    json!({
+        "action": "compact",
+        "dry_run": dry_run,
+        "changes": changes
+            .iter()
+            .zip(uuids)
+            .map(|((old, new), uuid)| json!({ "uuid": uuid, "from": old, "to": new }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON document for `todo sync`.
+pub(crate) fn sync_json(report: &SyncReport) -> Value {
+    let merge = match &report.pull {
+        Some(Pull::Merged(summary)) => json!({
+            "added": summary.added,
+            "updated": summary.updated,
+            "removed": summary.removed,
+            "renumbered": renumbered_json(&summary.renumbered),
+        }),
+        _ => Value::Null,
+    };
+    json!({
+        "action": "sync",
+        "committed": report.committed,
+        "remote": report.remote,
+        "pull": report.pull.as_ref().map(Pull::as_str),
+        "merge": merge,
+        "pushed": report.pushed,
+    })
+}
+
+/// JSON document for `todo merge`. `file` is where the result was written,
+/// if it was.
+pub fn merge_json(summary: &MergeSummary, conflicts: &[Conflict], file: Option<&Path>) -> Value {
+    json!({
+        "action": "merge",
+        "file": file.map(|path| path.display().to_string()),
+        "added": summary.added,
+        "updated": summary.updated,
+        "removed": summary.removed,
+        "renumbered": renumbered_json(&summary.renumbered),
+        "conflicts": conflicts
+            .iter()
+            .map(|conflict| {
+                json!({
+                    "id": conflict.task().id,
+                    "uuid": conflict.uuid.to_string(),
+                    "description": conflict.task().description,
+                    "field": conflict.field,
+                    "ours": conflict.value(Side::Ours),
+                    "theirs": conflict.value(Side::Theirs),
+                    "taken": conflict.taken.as_str(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn renumbered_json(renumbered: &[(u64, u64)]) -> Vec<Value> {
+    renumbered
+        .iter()
+        .map(|(old, new)| json!({ "from": old, "to": new }))
+        .collect()
+}
+
+/// JSON document for `todo archive` and `todo purge`.
+pub fn cleanup_json(action: &str, ids: &[u64], dry_run: bool, archive: Option<&Path>) -> Value {
+    let mut value = json!({
+        "action": action,
+        "ids": ids,
+        "dry_run": dry_run,
+    });
+    if let Some(path) = archive {
+        value["archive_file"] = path.display().to_string().into();
+    }
+    value
+}
+
+/// JSON document describing a failed command.
+pub fn error_json(code: &str, message: &str) -> Value {
+    json!({
+        "error": {
+            "code": code,
+            "message": message,
+        },
+    })
+}
+
+/// Print a JSON document in the requested format.
+///
+/// `Json` pretty-prints the document, `Jsonl` writes it on a single line.
+pub fn print_json(value: &Value, format: OutputFormat) {
+    match format {
+        OutputFormat::Jsonl => println!("{}", value),
+        _ => println!("{:#}", value),
+    }
+}
+
+/// Print a task list in the requested JSON format.
+///
+/// `Jsonl` writes one task per line and omits the summary.
+pub fn print_task_list(tasks: &[&Task], summary: Summary, format: OutputFormat) {
+    match format {
+        OutputFormat::Jsonl => {
+            for task in tasks {
+                println!("{}", task_json(task));
+            }
+        }
+        _ => print_json(&list_json(tasks, summary), format),
+    }
+}
+
+/// Print an error in the requested JSON format.
+pub fn print_error(error: &TodoError, format: OutputFormat) {
+    print_json(&error_json(error.code(), &error.to_string()), format);
+}
+
+fn timestamp(date: DateTime<Local>) -> String {
+    date.with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fixed_task() -> Task {
+        let mut task = Task::new(4, "Write report".to_string());
+        task.priority = Some(2);
+        task.tags = vec!["work".to_string()];
+        task.uuid = "5f1c2a4e-8d3b-4c6a-9e7f-0a1b2c3d4e5f".parse().unwrap();
+        task.created_at = Utc
+            .with_ymd_and_hms(2025, 7, 1, 8, 30, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        task.due_date = Some(
+            Utc.with_ymd_and_hms(2099, 1, 15, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Local),
+        );
+        task
+    }
+
+    #[test]
+    fn test_task_json_schema() {
+        let task = fixed_task();
+        assert_eq!(
+            serde_json::to_string_pretty(&task_json(&task)).unwrap(),
+            r#"{
+  "completed": false,
+  "completed_at": null,
+  "completion_note": null,
+  "created_at": "2025-07-01T08:30:00Z",
+  "description": "Write report",
+  "due_date": "2099-01-15T12:00:00Z",
+  "id": 4,
+  "notes": null,
+  "overdue": false,
+  "parent": null,
+  "priority": 2,
+  "properties": {},
+  "state": "pending",
+  "tags": [
+    "work"
+  ],
+  "uuid": "5f1c2a4e-8d3b-4c6a-9e7f-0a1b2c3d4e5f"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_list_json_schema() {
+        let task = fixed_task();
+        let summary = Summary {
+            shown: 1,
+            total: 3,
+            completed: 2,
+            overdue: 0,
+        };
+        let value = list_json(&[&task], summary);
+        assert_eq!(value["tasks"][0], task_json(&task));
+        assert_eq!(
+            value["summary"].to_string(),
+            r#"{"completed":2,"overdue":0,"shown":1,"total":3}"#
+        );
+    }
+
+    #[test]
+    fn test_action_json_schema() {
+        let task = fixed_task();
+        let value = action_json("add", &task);
+        assert_eq!(value["action"], "add");
+        assert_eq!(value["id"], 4);
+        assert_eq!(value["task"], task_json(&task));
+        assert_eq!(value.as_object().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_complete_json_schema() {
+        let task = fixed_task();
+        let completed = Completed {
+            already_completed: Vec::new(),
+            open_subtasks: vec![5, 6],
+            cascaded: Vec::new(),
+        };
+        let value = complete_json(&task, &completed);
+        assert_eq!(value["action"], "complete");
+        assert_eq!(value["task"], task_json(&task));
+        assert_eq!(value["already_completed"], false);
+        assert_eq!(value["open_subtasks"].to_string(), "[5,6]");
+        assert_eq!(value["cascaded"].to_string(), "[]");
+
+        let value = bulk_complete_json(&[&task], &completed);
+        assert_eq!(value["ids"].to_string(), "[4]");
+        assert_eq!(value["already_completed"].to_string(), "[]");
+        assert_eq!(value["open_subtasks"].to_string(), "[5,6]");
+    }
+
+    #[test]
+    fn test_error_json_schema() {
+        let error = TodoError::TaskNotFound(99);
+        assert_eq!(
+            error_json(error.code(), &error.to_string()).to_string(),
+            r#"{"error":{"code":"task_not_found","message":"Task not found with ID: 99"}}"#
+        );
+    }
+}
@@ -1,11 +1,16 @@
+use crate::merge::{Conflict, Side};
+use crate::storage::{TagCount, TrashedTask};
+use crate::table::pad;
 use crate::task::Task;
+use crate::theme::{Style, theme};
 use chrono::{DateTime, Local};
 use colored::*;
+use unicode_width::UnicodeWidthStr;
 
 /// Render a list of tasks to the terminal.
 pub fn render_task_list(tasks: &[&Task]) {
     if tasks.is_empty() {
-        println!("{}", "No tasks found.".dimmed());
+        println!("{}", theme().muted.apply("No tasks found."));
         return;
     }
 
@@ -16,27 +21,29 @@ pub fn render_task_list(tasks: &[&Task]) {
 
 /// Render a single task to the terminal, with color and formatting.
 pub fn render_task(task: &Task) {
+    println!("{}", format_task(task));
+}
+
+/// The line `render_task` prints for a task.
+pub fn format_task(task: &Task) -> String {
+    let theme = theme();
     let mut output = String::new();
 
-    output.push_str(&format!("[{}] ", task.id.to_string().cyan().bold()));
+    output.push_str(&format!("[{}] ", theme.id.apply(&task.id.to_string())));
 
     if let Some(priority) = task.priority {
         let priority_str = format!("P{} ", priority);
-        let colored_priority = match priority {
-            1 => priority_str.red().bold(),
-            2 => priority_str.yellow().bold(),
-            3 => priority_str.blue().bold(),
-            4 => priority_str.green().bold(),
-            5 => priority_str.cyan().bold(),
-            _ => priority_str.white().bold(),
-        };
-        output.push_str(&colored_priority.to_string());
+        output.push_str(&style_priority(&priority_str, priority).to_string());
     }
 
     let description = if task.completed {
-        task.description.strikethrough().dimmed().to_string()
+        theme.completed.apply(&task.description).to_string()
     } else if task.is_overdue() {
-        format!("⚠️  {}", task.description.red().bold())
+        format!(
+            "{}{}",
+            theme.overdue_marker(),
+            theme.overdue.apply(&task.description)
+        )
     } else {
         task.description.clone()
     };
@@ -45,7 +52,7 @@ pub fn render_task(task: &Task) {
     if !task.tags.is_empty() {
         output.push(' ');
         for tag in &task.tags {
-            output.push_str(&format!("#{} ", tag.bright_cyan()));
+            output.push_str(&format!("#{} ", theme.tag.apply(tag)));
         }
     }
 
@@ -58,36 +65,197 @@ pub fn render_task(task: &Task) {
         if let Some(completed_at) = task.completed_at {
             output.push_str(&format!(
                 " {}",
-                format!("(completed {})", format_relative_date(completed_at))
-                    .green()
-                    .dimmed()
+                theme.done.apply(&format!(
+                    "(completed {})",
+                    format_relative_date(completed_at)
+                ))
             ));
         } else {
-            output.push_str(&format!(" {}", "(completed)".green().dimmed()));
+            output.push_str(&format!(" {}", theme.done.apply("(completed)")));
         }
     }
 
-    println!("{}", output);
+    output
 }
 
-fn format_due_date(due_date: DateTime<Local>, is_overdue: bool) -> ColoredString {
-    let due_str = format!("(due {})", format_relative_date(due_date));
+/// Render the trash, most recently deleted first.
+pub fn render_trash(trash: &[TrashedTask]) {
+    let theme = theme();
+    if trash.is_empty() {
+        println!("{}", theme.muted.apply("The trash is empty."));
+        return;
+    }
+
+    for trashed in trash.iter().rev() {
+        println!(
+            "{} {}",
+            format_task(&trashed.task),
+            theme.muted.apply(&format!(
+                "(deleted {})",
+                format_relative_date(trashed.deleted_at)
+            ))
+        );
+    }
+}
+
+/// Render a merge conflict with both sides' versions, marking the side the
+/// merge currently takes.
+pub fn render_conflict(conflict: &Conflict) {
+    let theme = theme();
+    println!("{}", format_task(conflict.task()));
+    let what = match conflict.field {
+        Some(field) => format!("{} changed on both sides", field),
+        None => "deleted on one side and changed on the other".to_string(),
+    };
+    println!("   {}", theme.warning_text.apply(&what));
+
+    for side in [Side::Ours, Side::Theirs] {
+        let marker = if conflict.taken == side { " ←" } else { "" };
+        println!(
+            "   {} {}{}",
+            theme.muted.apply(&format!("{:>6}:", side.as_str())),
+            conflict.value(side),
+            theme.success.apply(marker)
+        );
+    }
+}
+
+/// Render every field of a single task, one per line.
+pub fn render_task_details(task: &Task) {
+    let theme = theme();
+    render_task(task);
+
+    let label = |name: &str| theme.muted.apply(&format!("{:>10}", name));
+    let status = if task.completed {
+        theme.done.apply("completed")
+    } else if task.is_overdue() {
+        theme.overdue.apply(&format!("{}, overdue", task.state))
+    } else {
+        task.state.as_str().normal()
+    };
+    println!("{} {}", label("Status:"), status);
+    println!(
+        "{} {}",
+        label("UUID:"),
+        theme.muted.apply(&task.uuid.to_string())
+    );
+    if let Some(parent) = task.parent {
+        println!(
+            "{} {}",
+            label("Parent:"),
+            theme.muted.apply(&parent.to_string())
+        );
+    }
+    println!(
+        "{} {}",
+        label("Created:"),
+        task.created_at.format("%Y-%m-%d %H:%M")
+    );
+    if let Some(due_date) = task.due_date {
+        println!(
+            "{} {} {}",
+            label("Due:"),
+            due_date.format("%Y-%m-%d %H:%M"),
+            format_due_date(due_date, task.is_overdue())
+        );
+    }
+    if let Some(completed_at) = task.completed_at {
+        println!(
+            "{} {}",
+            label("Completed:"),
+            completed_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    if let Some(note) = &task.completion_note {
+        println!("{} {}", label("Note:"), note);
+    }
+    for (key, value) in &task.properties {
+        println!("{} {}", label(&format!("{}:", key)), value);
+    }
+    if let Some(notes) = &task.notes {
+        println!();
+        for line in notes.lines() {
+            println!("  {}", line);
+        }
+    }
+}
+
+/// Render tags with how many tasks (and open tasks) carry each.
+pub fn render_tag_counts(counts: &[TagCount]) {
+    let theme = theme();
+    if counts.is_empty() {
+        println!("{}", theme.muted.apply("No tags found."));
+        return;
+    }
+
+    let width = counts
+        .iter()
+        .map(|count| count.tag.width() + 1)
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    println!(
+        "{}",
+        theme.header.apply(&format!(
+            "{} {:>5} {:>5}",
+            pad("Tag", width),
+            "Tasks",
+            "Open"
+        ))
+    );
+    for count in counts {
+        println!(
+            "{} {:>5} {:>5}",
+            theme.tag.apply(&pad(&format!("#{}", count.tag), width)),
+            count.tasks,
+            count.open
+        );
+    }
+}
 
+/// Color text with the color of the given priority level.
+pub fn style_priority(text: &str, priority: u8) -> ColoredString {
+    theme().priority_style(priority).apply(text)
+}
+
+/// Style for a task's title: completed, overdue, or its priority color.
+pub fn task_style(task: &Task) -> Style {
+    let theme = theme();
+    if task.completed {
+        theme.completed.clone()
+    } else if task.is_overdue() {
+        theme.overdue.clone()
+    } else {
+        task.priority
+            .map(|priority| theme.priority_style(priority))
+            .unwrap_or_default()
+    }
+}
+
+/// Color text according to how close the due date is.
+pub fn style_due(text: &str, due_date: DateTime<Local>, is_overdue: bool) -> ColoredString {
+    let theme = theme();
     if is_overdue {
-        due_str.red().bold()
+        theme.overdue.apply(text)
     } else {
         let now = Local::now();
         let days_until = (due_date.date_naive() - now.date_naive()).num_days();
 
         match days_until {
-            0 => due_str.yellow().bold(),
-            1..=3 => due_str.yellow(),
-            _ => due_str.normal(),
+            0 => theme.due_today.apply(text),
+            1..=3 => theme.due_soon.apply(text),
+            _ => text.normal(),
         }
     }
 }
 
-fn format_relative_date(date: DateTime<Local>) -> String {
+fn format_due_date(due_date: DateTime<Local>, is_overdue: bool) -> ColoredString {
+    let due_str = format!("(due {})", format_relative_date(due_date));
+    style_due(&due_str, due_date, is_overdue)
+}
+
+/// Format a date relative to today ("today", "in 3 days", "2025-07-15").
+pub fn format_relative_date(date: DateTime<Local>) -> String {
     let now = Local::now();
     let date_naive = date.date_naive();
     let now_naive = now.date_naive();
@@ -104,21 +272,34 @@ fn format_relative_date(date: DateTime<Local>) -> String {
     }
 }
 
+/// Print a success message.
 pub fn render_success(message: &str) {
-    println!("{} {}", "✓".green().bold(), message);
+    println!("{} {}", theme().success.apply("✓"), message);
 }
 
+/// Print an error message to stderr.
 pub fn render_error(message: &str) {
-    eprintln!("{} {}", "✗".red().bold(), message.red());
+    let theme = theme();
+    eprintln!(
+        "{} {}",
+        theme.error.apply("✗"),
+        theme.error_text.apply(message)
+    );
 }
 
-#[allow(dead_code)]
+/// Print a warning.
 pub fn render_warning(message: &str) {
-    println!("{} {}", "⚠".yellow().bold(), message.yellow());
+    let theme = theme();
+    println!(
+        "{} {}",
+        theme.warning.apply(theme.warning_marker()),
+        theme.warning_text.apply(message)
+    );
 }
 
+/// Print an informational message.
 pub fn render_info(message: &str) {
-    println!("{} {}", "ℹ".blue().bold(), message);
+    println!("{} {}", theme().info.apply("ℹ"), message);
 }
 
 #[cfg(test)]
@@ -0,0 +1,574 @@
+use crate::api::{self, Completion, ListQuery, NewTask, TaskUpdate};
+use crate::config::{CompletionPolicy, HooksConfig};
+use crate::error::{Result, TodoError};
+use crate::hooks::run_hooks;
+use crate::output::{self, action_json, complete_json, task_json};
+use crate::storage::{TaskStorage, content_stamp};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// How often the data file is checked for changes made by other processes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Any other error from a method; `data.code` holds the error code the
+/// command line would report.
+const TASK_ERROR: i64 = -32000;
+
+/// Fingerprint of the data file's contents, or `None` while it doesn't exist.
+/// Hashing the contents catches writes that keep the size and land within
+/// the same modification time.
+type FileStamp = Option<u64>;
+
+/// JSON-RPC 2.0 over newline-delimited JSON: one request or batch per input
+/// line, one response per output line.
+///
+/// Like `todo serve`, the data file is read again for every request. When
+/// another process changes it, a `changed` notification is sent so clients
+/// know to refresh.
+pub struct Rpc {
+    data_path: PathBuf,
+    hooks: HooksConfig,
+    policy: CompletionPolicy,
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// The data file as last seen by this process. Locked while a request
+    /// runs so the watcher doesn't report the request's own changes.
+    stamp: Arc<Mutex<FileStamp>>,
+}
+
+/// A JSON-RPC error object.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut error = json!({ "code": self.code, "message": self.message });
+        if let Some(data) = &self.data {
+            error["data"] = data.clone();
+        }
+        error
+    }
+}
+
+impl From<TodoError> for RpcError {
+    fn from(error: TodoError) -> Self {
+        let code = match error {
+            TodoError::BadRequest(_) => INVALID_PARAMS,
+            _ => TASK_ERROR,
+        };
+        Self {
+            code,
+            message: error.to_string(),
+            data: Some(json!({ "code": error.code() })),
+        }
+    }
+}
+
+/// Parameters of methods that take nothing but the task.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoParams {}
+
+impl Rpc {
+    /// Serve the tasks in `data_path`, writing responses and notifications
+    /// to `output`.
+    pub fn new(
+        data_path: PathBuf,
+        hooks: HooksConfig,
+        policy: CompletionPolicy,
+        output: Box<dyn Write + Send>,
+    ) -> Self {
+        let stamp = content_stamp(&data_path);
+        Self {
+            data_path,
+            hooks,
+            policy,
+            output: Arc::new(Mutex::new(output)),
+            stamp: Arc::new(Mutex::new(stamp)),
+        }
+    }
+
+    /// Answer requests from `input` until it ends.
+    pub fn run(&self, input: impl BufRead) -> Result<()> {
+        self.watch(POLL_INTERVAL);
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                send(&self.output, &response)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the data file every `interval` in the background and send a
+    /// `changed` notification when someone else modified it.
+    fn watch(&self, interval: Duration) {
+        let data_path = self.data_path.clone();
+        let output = Arc::clone(&self.output);
+        let stamp = Arc::clone(&self.stamp);
+        std::thread::spawn(move || {
+            loop {
+                std::thread::sleep(interval);
+                if !changed_elsewhere(&data_path, &stamp) {
+                    continue;
+                }
+                let notification = json!({ "jsonrpc": "2.0", "method": "changed", "params": {} });
+                if send(&output, &notification).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Answer one line of input: a request, a notification or a batch.
+    /// Returns `None` when there is nothing to send back.
+    fn handle(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, format!("parse error: {}", e));
+                return Some(error_response(Value::Null, &error));
+            }
+        };
+        match message {
+            Value::Array(batch) if batch.is_empty() => Some(error_response(
+                Value::Null,
+                &RpcError::new(INVALID_REQUEST, "empty batch"),
+            )),
+            Value::Array(batch) => {
+                let responses: Vec<Value> = batch
+                    .into_iter()
+                    .filter_map(|request| self.call(request))
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            request => self.call(request),
+        }
+    }
+
+    /// Run a single request. Well-formed notifications (requests without an
+    /// `id`) get no response, even when they fail.
+    fn call(&self, request: Value) -> Option<Value> {
+        let Value::Object(mut request) = request else {
+            let error = RpcError::new(INVALID_REQUEST, "request must be an object");
+            return Some(error_response(Value::Null, &error));
+        };
+        let id = request.remove("id");
+        if let Some(invalid) = id
+            .as_ref()
+            .filter(|id| !(id.is_string() || id.is_number() || id.is_null()))
+        {
+            let error = RpcError::new(
+                INVALID_REQUEST,
+                format!("id must be a string, number or null, not {}", invalid),
+            );
+            return Some(error_response(Value::Null, &error));
+        }
+
+        let result = validate(request).and_then(|(method, params)| self.dispatch(&method, params));
+        // A malformed request is answered even without an id, as it may have
+        // been meant to have one.
+        let id = match (id, &result) {
+            (None, Err(error)) if error.code == INVALID_REQUEST => Value::Null,
+            (id, _) => id?,
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err(error) => error_response(id, &error),
+        })
+    }
+
+    /// Run a method against the data file, saving it if the method changed
+    /// any tasks.
+    fn dispatch(
+        &self,
+        method: &str,
+        mut params: Map<String, Value>,
+    ) -> std::result::Result<Value, RpcError> {
+        let mut known = lock(&self.stamp);
+        let seen = content_stamp(&self.data_path);
+        let mut storage = TaskStorage::load_from_file(&self.data_path)?;
+
+        let (result, changed) = match method {
+            "list" => (
+                api::list(&storage, &parse_params::<ListQuery>(params)?)?,
+                false,
+            ),
+            "show" => {
+                let reference = take_task(&mut params)?;
+                parse_params::<NoParams>(params)?;
+                (task_json(api::task(&storage, &reference)?), false)
+            }
+            "add" => {
+                let id = api::create(&mut storage, parse_params::<NewTask>(params)?)?;
+                (
+                    action_json("add", api::task(&storage, &id.to_string())?),
+                    true,
+                )
+            }
+            "edit" => {
+                let id = api::task(&storage, &take_task(&mut params)?)?.id;
+                api::apply_update(&mut storage, id, parse_params::<TaskUpdate>(params)?)?;
+                (
+                    action_json("edit", api::task(&storage, &id.to_string())?),
+                    true,
+                )
+            }
+            "complete" => {
+                let id = api::task(&storage, &take_task(&mut params)?)?.id;
+                let completion = parse_params::<Completion>(params)?;
+                let sets_note = completion.note.is_some();
+                let completed = api::complete(&mut storage, id, completion, self.policy)?;
+                let task = api::task(&storage, &id.to_string())?;
+                let value = complete_json(task, &completed);
+                (value, completed.already_completed.is_empty() || sets_note)
+            }
+            "reopen" => {
+                let id = api::task(&storage, &take_task(&mut params)?)?.id;
+                parse_params::<NoParams>(params)?;
+                let already_open = api::reopen(&mut storage, id)?;
+                let mut value = action_json("reopen", api::task(&storage, &id.to_string())?);
+                value["already_open"] = already_open.into();
+                (value, !already_open)
+            }
+            "delete" => {
+                let id = api::task(&storage, &take_task(&mut params)?)?.id;
+                parse_params::<NoParams>(params)?;
+                (action_json("delete", &storage.delete_task(id)?), true)
+            }
+            "restore" => {
+                let uuid = api::trashed(&storage, &take_task(&mut params)?)?;
+                parse_params::<NoParams>(params)?;
+                (action_json("restore", storage.restore_task(uuid)?), true)
+            }
+            "tags" => {
+                parse_params::<NoParams>(params)?;
+                (output::tags_json(&storage.tag_counts()), false)
+            }
+            _ => {
+                return Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("unknown method '{}'", method),
+                ));
+            }
+        };
+
+        if changed {
+            run_hooks(&self.hooks, &mut storage)?;
+            // Saving a copy loaded before someone else's change would undo it.
+            if content_stamp(&self.data_path) != seen {
+                return Err(TodoError::DataChanged.into());
+            }
+            storage.save_to_file(&self.data_path)?;
+            // A change someone else made before this request is still
+            // reported by the watcher.
+            if seen == *known {
+                *known = content_stamp(&self.data_path);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Check the envelope of a request and return its method and parameters.
+fn validate(
+    mut request: Map<String, Value>,
+) -> std::result::Result<(String, Map<String, Value>), RpcError> {
+    if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+    }
+    let Some(Value::String(method)) = request.remove("method") else {
+        return Err(RpcError::new(INVALID_REQUEST, "method must be a string"));
+    };
+    let params = match request.remove("params") {
+        None => Map::new(),
+        Some(Value::Object(params)) => params,
+        Some(Value::Array(_)) => {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                "params must be an object; positional params aren't supported",
+            ));
+        }
+        Some(_) => return Err(RpcError::new(INVALID_REQUEST, "params must be an object")),
+    };
+    Ok((method, params))
+}
+
+fn parse_params<T: DeserializeOwned>(
+    params: Map<String, Value>,
+) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(Value::Object(params))
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("invalid params: {}", e)))
+}
+
+/// Remove the `task` parameter: a task ID or UUID prefix.
+fn take_task(params: &mut Map<String, Value>) -> std::result::Result<String, RpcError> {
+    match params.remove("task") {
+        Some(Value::String(reference)) => Ok(reference),
+        Some(Value::Number(id)) => Ok(id.to_string()),
+        Some(_) => Err(RpcError::new(
+            INVALID_PARAMS,
+            "task must be an ID or a UUID prefix",
+        )),
+        None => Err(RpcError::new(INVALID_PARAMS, "missing param 'task'")),
+    }
+}
+
+fn error_response(id: Value, error: &RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "error": error.to_json(), "id": id })
+}
+
+fn send(output: &Mutex<Box<dyn Write + Send>>, message: &Value) -> io::Result<()> {
+    let mut output = lock(output);
+    writeln!(output, "{}", message)?;
+    output.flush()
+}
+
+/// Lock a mutex, carrying on if another thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Whether the data file changed since it was last seen, remembering its new
+/// stamp if so.
+fn changed_elsewhere(data_path: &Path, stamp: &Mutex<FileStamp>) -> bool {
+    let current = content_stamp(data_path);
+    let mut known = lock(stamp);
+    if *known == current {
+        return false;
+    }
+    *known = current;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Task;
+    use crate::test_util::TempDir;
+    use std::sync::Condvar;
+
+    /// Output shared with the test so responses can be inspected.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<(Mutex<Vec<u8>>, Condvar)>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            lock(&self.0.0).extend_from_slice(bytes);
+            self.0.1.notify_all();
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn messages(&self) -> Vec<Value> {
+            String::from_utf8_lossy(&lock(&self.0.0))
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+
+        /// Wait until a whole message has been written, or panic after a
+        /// generous timeout.
+        fn wait_for_message(&self) -> Value {
+            let (bytes, written) = &*self.0;
+            let (bytes, timeout) = written
+                .wait_timeout_while(lock(bytes), Duration::from_secs(10), |bytes| {
+                    !bytes.contains(&b'\n')
+                })
+                .unwrap_or_else(PoisonError::into_inner);
+            assert!(!timeout.timed_out(), "no message within 10 seconds");
+            drop(bytes);
+            self.messages().remove(0)
+        }
+    }
+
+    /// A connection over a new `tasks.json` in the returned directory.
+    fn start(name: &str) -> (Rpc, Buffer, TempDir) {
+        let dir = TempDir::new(name);
+        let buffer = Buffer::default();
+        let rpc = Rpc::new(
+            dir.join("tasks.json"),
+            HooksConfig::default(),
+            CompletionPolicy::default(),
+            Box::new(buffer.clone()),
+        );
+        (rpc, buffer, dir)
+    }
+
+    fn call(rpc: &Rpc, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+        rpc.handle(&request.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_methods() {
+        let (rpc, _, _dir) = start("rpc-methods");
+
+        let response = call(
+            &rpc,
+            "add",
+            json!({ "description": "Fix login", "priority": 1, "tags": ["work"] }),
+        );
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["action"], "add");
+        let uuid = response["result"]["task"]["uuid"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        call(&rpc, "add", json!({ "description": "Buy milk" }));
+
+        let response = call(&rpc, "list", json!({ "filter": "tag:work" }));
+        assert_eq!(response["result"]["summary"]["shown"], 1);
+        let response = call(
+            &rpc,
+            "show",
+            json!({ "task": format!("uuid:{}", &uuid[..8]) }),
+        );
+        assert_eq!(response["result"]["id"], 1);
+
+        let response = call(&rpc, "edit", json!({ "task": 1, "priority": null }));
+        assert_eq!(response["result"]["task"]["priority"], Value::Null);
+        let response = call(&rpc, "complete", json!({ "task": "1", "note": "Done" }));
+        assert_eq!(response["result"]["already_completed"], false);
+        assert_eq!(response["result"]["task"]["completion_note"], "Done");
+        let response = call(&rpc, "reopen", json!({ "task": 1 }));
+        assert_eq!(response["result"]["already_open"], false);
+
+        call(&rpc, "delete", json!({ "task": 2 }));
+        let response = call(&rpc, "show", json!({ "task": 2 }));
+        assert_eq!(response["error"]["code"], TASK_ERROR);
+        assert_eq!(response["error"]["data"]["code"], "task_not_found");
+        let response = call(&rpc, "restore", json!({ "task": 2 }));
+        assert_eq!(response["result"]["task"]["description"], "Buy milk");
+
+        let response = call(&rpc, "tags", json!({}));
+        assert_eq!(response["result"]["tags"][0]["tag"], "work");
+
+        let storage = TaskStorage::load_from_file(&rpc.data_path).unwrap();
+        assert_eq!(storage.tasks.len(), 2);
+        assert!(!storage.get_task(1).unwrap().completed);
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let (rpc, _, _dir) = start("rpc-errors");
+        let error = |line: &str| rpc.handle(line).unwrap()["error"]["code"].clone();
+
+        assert_eq!(error("{"), PARSE_ERROR);
+        assert_eq!(error("[]"), INVALID_REQUEST);
+        assert_eq!(error("42"), INVALID_REQUEST);
+        assert_eq!(error(r#"{"method": "list", "id": 1}"#), INVALID_REQUEST);
+        assert_eq!(
+            error(r#"{"jsonrpc": "2.0", "method": "list", "id": {}}"#),
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            error(r#"{"jsonrpc": "2.0", "method": "nope", "id": 1}"#),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            error(r#"{"jsonrpc": "2.0", "method": "list", "params": [1], "id": 1}"#),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            call(&rpc, "show", json!({}))["error"]["code"],
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            call(&rpc, "add", json!({ "title": "X" }))["error"]["code"],
+            INVALID_PARAMS
+        );
+        let response = call(&rpc, "add", json!({ "description": "X", "priority": 9 }));
+        assert_eq!(response["error"]["data"]["code"], "invalid_priority");
+
+        // The id of a failed request is echoed back.
+        let response = rpc
+            .handle(r#"{"jsonrpc": "2.0", "method": "nope", "id": "abc"}"#)
+            .unwrap();
+        assert_eq!(response["id"], "abc");
+    }
+
+    #[test]
+    fn test_batches_and_notifications() {
+        let (rpc, _, _dir) = start("rpc-batch");
+
+        // Notifications run but get no response.
+        let add = r#"{"jsonrpc": "2.0", "method": "add", "params": {"description": "Quiet"}}"#;
+        assert_eq!(rpc.handle(add), None);
+        assert_eq!(rpc.handle(r#"{"jsonrpc": "2.0", "method": "nope"}"#), None);
+        let response = rpc.handle(r#"{"method": "list"}"#).unwrap();
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(rpc.handle(&format!("[{}]", add)), None);
+
+        let response = rpc
+            .handle(
+                r#"[{"jsonrpc": "2.0", "method": "list", "id": 1},
+                    {"jsonrpc": "2.0", "method": "nope", "id": 2},
+                    {"jsonrpc": "2.0", "method": "tags"}]"#,
+            )
+            .unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["result"]["summary"]["shown"], 2);
+        assert_eq!(responses[1]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_change_detection() {
+        let (rpc, _, _dir) = start("rpc-detect");
+
+        // Changes made through the connection aren't reported back.
+        call(&rpc, "add", json!({ "description": "Own change" }));
+        assert!(!changed_elsewhere(&rpc.data_path, &rpc.stamp));
+
+        // A write that keeps the size, right after the last one, is noticed.
+        let mut storage = TaskStorage::load_from_file(&rpc.data_path).unwrap();
+        storage.tasks[0].description = "Own chance".to_string();
+        storage.save_to_file(&rpc.data_path).unwrap();
+        assert!(changed_elsewhere(&rpc.data_path, &rpc.stamp));
+        assert!(!changed_elsewhere(&rpc.data_path, &rpc.stamp));
+    }
+
+    #[test]
+    fn test_change_notifications() {
+        let (rpc, buffer, _dir) = start("rpc-watch");
+        call(&rpc, "add", json!({ "description": "Own change" }));
+        rpc.watch(Duration::from_millis(20));
+
+        let mut storage = TaskStorage::load_from_file(&rpc.data_path).unwrap();
+        storage.add_task(Task::new(0, "Someone else's change".to_string()));
+        storage.save_to_file(&rpc.data_path).unwrap();
+        let message = buffer.wait_for_message();
+        assert_eq!(message["method"], "changed");
+        assert!(message.get("id").is_none());
+    }
+}
@@ -0,0 +1,458 @@
+use crate::api::{self, Completion, ListQuery, NewTask, TaskUpdate, bad_request};
+use crate::config::{CompletionPolicy, HooksConfig};
+use crate::error::{Result, TodoError};
+use crate::hooks::run_hooks;
+use crate::output::{action_json, complete_json, error_json, task_json};
+use crate::storage::{TaskStorage, content_stamp};
+use crate::task::Task;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use tiny_http::{Header, Request, Response};
+
+/// Port `todo serve` listens on without `--port`.
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Largest request body accepted, in bytes.
+const MAX_BODY: u64 = 1024 * 1024;
+
+/// HTTP/JSON API over the data file, listening on localhost only.
+///
+/// The data file is read again for every request, so changes made with the
+/// command line while the server runs are picked up.
+pub struct Server {
+    http: tiny_http::Server,
+    data_path: PathBuf,
+    token: String,
+    hooks: HooksConfig,
+    policy: CompletionPolicy,
+}
+
+impl Server {
+    /// Listen on `127.0.0.1:port`; port 0 picks a free one.
+    ///
+    /// # Errors
+    /// Returns `TodoError::Config` for an empty token, which would let any
+    /// client in with a bare `Bearer`.
+    pub fn bind(
+        port: u16,
+        data_path: PathBuf,
+        token: String,
+        hooks: HooksConfig,
+        policy: CompletionPolicy,
+    ) -> Result<Self> {
+        if token.trim().is_empty() {
+            return Err(TodoError::Config(
+                "server.token must not be empty".to_string(),
+            ));
+        }
+        let http = tiny_http::Server::http(("127.0.0.1", port)).map_err(io::Error::other)?;
+        Ok(Self {
+            http,
+            data_path,
+            token,
+            hooks,
+            policy,
+        })
+    }
+
+    /// The port the server listens on.
+    pub fn port(&self) -> u16 {
+        self.http
+            .server_addr()
+            .to_ip()
+            .map_or(0, |address| address.port())
+    }
+
+    /// Answer requests until the process is stopped.
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            self.respond(request);
+        }
+    }
+
+    fn respond(&self, mut request: Request) {
+        let (status, body) = if !self.authorized(&request) {
+            let message = "missing or wrong token; send 'Authorization: Bearer <server.token>'";
+            (401, error_json("unauthorized", message))
+        } else {
+            // Read one byte past the limit to tell a body that fits exactly
+            // from one that doesn't.
+            let mut body = String::new();
+            let read = request
+                .as_reader()
+                .take(MAX_BODY + 1)
+                .read_to_string(&mut body);
+            let method = request.method().as_str().to_string();
+            if body.len() as u64 > MAX_BODY {
+                let message = format!("request body is larger than {} bytes", MAX_BODY);
+                (413, error_json("payload_too_large", &message))
+            } else {
+                read.map_err(TodoError::from)
+                    .and_then(|_| self.handle(&method, request.url(), &body))
+                    .unwrap_or_else(|e| (status_for(&e), error_json(e.code(), &e.to_string())))
+            }
+        };
+
+        let content_type =
+            Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            eprintln!("Warning: Failed to send response: {}", e);
+        }
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let expected = format!("Bearer {}", self.token);
+        request.headers().iter().any(|header| {
+            header.field.equiv("Authorization")
+                && constant_time_eq(header.value.as_bytes(), expected.as_bytes())
+        })
+    }
+
+    /// Run the hooks for the request's changes and save them, unless the
+    /// data file no longer has the `loaded` stamp: the changes were made to
+    /// an outdated copy, and saving would undo someone else's.
+    fn save(&self, storage: &mut TaskStorage, loaded: Option<u64>) -> Result<()> {
+        run_hooks(&self.hooks, storage)?;
+        if content_stamp(&self.data_path) != loaded {
+            return Err(TodoError::DataChanged);
+        }
+        storage.save_to_file(&self.data_path)
+    }
+
+    /// Route a request and run it against the data file.
+    fn handle(&self, method: &str, url: &str, body: &str) -> Result<(u16, Value)> {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let loaded = content_stamp(&self.data_path);
+        let mut storage = TaskStorage::load_from_file(&self.data_path)?;
+
+        match (method, segments.as_slice()) {
+            ("GET", ["tasks"]) => Ok((200, list(&storage, query)?)),
+            ("POST", ["tasks"]) => {
+                let new: NewTask = parse_body(body)?;
+                let id = api::create(&mut storage, new)?;
+                self.save(&mut storage, loaded)?;
+                Ok((201, action_json("add", task(&storage, &id.to_string())?)))
+            }
+            ("GET", ["tasks", reference]) => Ok((200, task_json(task(&storage, reference)?))),
+            ("PATCH", ["tasks", reference]) => {
+                let update: TaskUpdate = parse_body(body)?;
+                let id = task(&storage, reference)?.id;
+                api::apply_update(&mut storage, id, update)?;
+                self.save(&mut storage, loaded)?;
+                Ok((200, action_json("edit", task(&storage, &id.to_string())?)))
+            }
+            ("POST", ["tasks", reference, "complete"]) => {
+                let completion: Completion = if body.trim().is_empty() {
+                    Completion::default()
+                } else {
+                    parse_body(body)?
+                };
+                let id = task(&storage, reference)?.id;
+                let completed = api::complete(&mut storage, id, completion, self.policy)?;
+                self.save(&mut storage, loaded)?;
+                let value = complete_json(task(&storage, &id.to_string())?, &completed);
+                Ok((200, value))
+            }
+            ("DELETE", ["tasks", reference]) => {
+                let id = task(&storage, reference)?.id;
+                let deleted = storage.delete_task(id)?;
+                self.save(&mut storage, loaded)?;
+                Ok((200, action_json("delete", &deleted)))
+            }
+            (_, ["tasks"] | ["tasks", _] | ["tasks", _, "complete"]) => {
+                Ok((405, error_json("method_not_allowed", "method not allowed")))
+            }
+            _ => Ok((
+                404,
+                error_json("not_found", &format!("no such endpoint: {}", path)),
+            )),
+        }
+    }
+}
+
+/// `GET /tasks`: the same document as `todo list --output json`.
+///
+/// Query parameters: `filter` (a filter expression), `tag`, `exclude_tag`
+/// and `completed=true` to include completed tasks.
+fn list(storage: &TaskStorage, query: &str) -> Result<Value> {
+    let mut list = ListQuery::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        match key {
+            "filter" => list.filter = Some(value),
+            "tag" => list.tag = Some(value),
+            "exclude_tag" => list.exclude_tag = Some(value),
+            "completed" => list.completed = matches!(value.as_str(), "true" | "1"),
+            _ => return Err(bad_request(&format!("unknown query parameter '{}'", key))),
+        }
+    }
+    api::list(storage, &list)
+}
+
+/// Look up a task by ID or UUID prefix.
+fn task<'a>(storage: &'a TaskStorage, reference: &str) -> Result<&'a Task> {
+    api::task(storage, &percent_decode(reference))
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T> {
+    serde_json::from_str(body).map_err(|e| bad_request(&format!("invalid request body: {}", e)))
+}
+
+/// Compare secrets in time that depends only on their length, so the
+/// token can't be guessed byte by byte from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// HTTP status for an error.
+fn status_for(error: &TodoError) -> u16 {
+    match error {
+        TodoError::TaskNotFound(_) | TodoError::UnknownTask(_) => 404,
+        TodoError::HookRejected { .. }
+        | TodoError::OpenSubtasks { .. }
+        | TodoError::DataChanged => 409,
+        TodoError::BadRequest(_)
+        | TodoError::DateParse(_)
+        | TodoError::AmbiguousId(..)
+        | TodoError::InvalidPriority(_)
+        | TodoError::InvalidTag(_)
+        | TodoError::Filter(_) => 400,
+        _ => 500,
+    }
+}
+
+/// Decode `%XX` escapes and `+` in a URL query value or path segment.
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = input.clone().take(2).collect();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) if hex.len() == 2 => {
+                        bytes.push(decoded);
+                        input.nth(1);
+                    }
+                    _ => bytes.push(b'%'),
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    const TOKEN: &str = "secret";
+
+    /// Start a server on a free port over a new `tasks.json` in the returned
+    /// directory.
+    fn start(name: &str) -> (u16, TempDir) {
+        let dir = TempDir::new(name);
+        let server = Server::bind(
+            0,
+            dir.join("tasks.json"),
+            TOKEN.to_string(),
+            HooksConfig::default(),
+            CompletionPolicy::default(),
+        )
+        .unwrap();
+        let port = server.port();
+        std::thread::spawn(move || server.run());
+        (port, dir)
+    }
+
+    /// Send a request and return the status and JSON body of the response.
+    fn send(port: u16, method: &str, path: &str, token: Option<&str>, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let auth = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            auth,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn request(port: u16, method: &str, path: &str, body: &str) -> (u16, Value) {
+        send(port, method, path, Some(TOKEN), body)
+    }
+
+    #[test]
+    fn test_requires_token() {
+        let (port, _dir) = start("server-auth");
+        let (status, body) = send(port, "GET", "/tasks", None, "");
+        assert_eq!(status, 401);
+        assert_eq!(body["error"]["code"], "unauthorized");
+        assert_eq!(send(port, "GET", "/tasks", Some("wrong"), "").0, 401);
+        assert_eq!(send(port, "GET", "/tasks", Some("secreT"), "").0, 401);
+        assert_eq!(request(port, "GET", "/tasks", "").0, 200);
+
+        for token in ["", "  "] {
+            let bound = Server::bind(
+                0,
+                std::env::temp_dir(),
+                token.to_string(),
+                HooksConfig::default(),
+                CompletionPolicy::default(),
+            );
+            assert!(matches!(bound, Err(TodoError::Config(_))));
+        }
+        assert!(constant_time_eq(b"Bearer a", b"Bearer a"));
+        assert!(!constant_time_eq(b"Bearer a", b"Bearer "));
+    }
+
+    #[test]
+    fn test_task_endpoints() {
+        let (port, dir) = start("server-crud");
+
+        let (status, body) = request(
+            port,
+            "POST",
+            "/tasks",
+            r#"{"description": "Fix login", "priority": 1, "tags": ["work"]}"#,
+        );
+        assert_eq!(status, 201);
+        assert_eq!(body["id"], 1);
+        let uuid = body["task"]["uuid"].as_str().unwrap().to_string();
+        request(port, "POST", "/tasks", r#"{"description": "Buy milk"}"#);
+
+        let (status, body) = request(port, "GET", "/tasks?filter=tag%3Awork", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["summary"]["shown"], 1);
+        assert_eq!(body["tasks"][0]["description"], "Fix login");
+
+        let (status, body) = request(port, "GET", &format!("/tasks/uuid:{}", &uuid[..8]), "");
+        assert_eq!(status, 200);
+        assert_eq!(body["id"], 1);
+
+        let (status, body) = request(
+            port,
+            "PATCH",
+            "/tasks/1",
+            r#"{"priority": null, "state": "started"}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["task"]["priority"], Value::Null);
+        assert_eq!(body["task"]["state"], "started");
+
+        let (status, body) = request(port, "POST", "/tasks/1/complete", r#"{"note": "Done"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["already_completed"], false);
+        assert_eq!(body["task"]["completion_note"], "Done");
+
+        let (status, _) = request(port, "DELETE", "/tasks/2", "");
+        assert_eq!(status, 200);
+        let (status, body) = request(port, "GET", "/tasks/2", "");
+        assert_eq!(status, 404);
+        assert_eq!(body["error"]["code"], "task_not_found");
+
+        // The changes are in the data file.
+        let storage = TaskStorage::load_from_file(&dir.join("tasks.json")).unwrap();
+        assert!(storage.get_task(1).unwrap().completed);
+        assert_eq!(storage.trash.len(), 1);
+    }
+
+    #[test]
+    fn test_save_refuses_outdated_copy() {
+        let dir = TempDir::new("server-race");
+        let path = dir.join("tasks.json");
+        let mut cli = TaskStorage::default();
+        cli.add_task(Task::new(0, "Before".to_string()));
+        cli.save_to_file(&path).unwrap();
+        let server = Server::bind(
+            0,
+            path.clone(),
+            TOKEN.to_string(),
+            HooksConfig::default(),
+            CompletionPolicy::default(),
+        )
+        .unwrap();
+
+        let loaded = content_stamp(&path);
+        let mut storage = TaskStorage::load_from_file(&path).unwrap();
+        storage.add_task(Task::new(0, "From the API".to_string()));
+        // Meanwhile the command line renames the task, keeping the file size.
+        cli.get_task_mut(1).unwrap().description = "Change".to_string();
+        cli.save_to_file(&path).unwrap();
+
+        assert!(matches!(
+            server.save(&mut storage, loaded),
+            Err(TodoError::DataChanged)
+        ));
+        let on_disk = TaskStorage::load_from_file(&path).unwrap();
+        assert_eq!(on_disk.tasks.len(), 1);
+        assert_eq!(on_disk.tasks[0].description, "Change");
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let (port, _dir) = start("server-invalid");
+        let (status, body) = request(
+            port,
+            "POST",
+            "/tasks",
+            r#"{"description": "X", "priority": 9}"#,
+        );
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "invalid_priority");
+
+        let (status, body) = request(port, "POST", "/tasks", r#"{"title": "X"}"#);
+        assert_eq!(status, 400);
+        assert_eq!(body["error"]["code"], "bad_request");
+
+        assert_eq!(request(port, "GET", "/tasks?filter=%28", "").0, 400);
+        assert_eq!(request(port, "PUT", "/tasks", "").0, 405);
+        assert_eq!(request(port, "GET", "/nothing", "").0, 404);
+
+        // A body right at the limit is read and parsed; one byte more isn't.
+        let body = |length: u64| {
+            let padding = "x".repeat(length as usize - r#"{"title": ""}"#.len());
+            format!(r#"{{"title": "{}"}}"#, padding)
+        };
+        let (status, response) = request(port, "POST", "/tasks", &body(MAX_BODY + 1));
+        assert_eq!(status, 413);
+        assert_eq!(response["error"]["code"], "payload_too_large");
+        let (status, response) = request(port, "POST", "/tasks", &body(MAX_BODY));
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["code"], "bad_request");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("tag%3Awork+and+open"), "tag:work and open");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%E2%9C%93"), "✓");
+    }
+}
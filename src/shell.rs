@@ -0,0 +1,329 @@
+use crate::cli::{Cli, Commands, OutputFormat};
+use crate::commands::{Session, execute};
+use crate::error::{Result, TodoError};
+use crate::output::print_error;
+use crate::renderer::{render_error, render_info};
+use crate::storage::TaskStorage;
+use clap::{CommandFactory, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::collections::BTreeSet;
+use std::io;
+use std::path::PathBuf;
+
+/// Subcommands whose positional argument is a task ID.
+const ID_COMMANDS: [&str; 5] = ["show", "complete", "reopen", "edit", "delete"];
+/// Options whose value is a tag.
+const TAG_OPTIONS: [&str; 5] = ["-t", "--tag", "--tags", "--exclude-tag", "--remove-tag"];
+
+/// Run an interactive shell that accepts the same subcommands as `todo`.
+///
+/// Tasks stay loaded between commands; commands that change tasks save
+/// them before the next prompt. `exit`, `quit` or Ctrl-D leave the shell.
+pub fn run_shell(session: &mut Session, format: OutputFormat) -> Result<()> {
+    let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::new().map_err(readline_error)?;
+    editor.set_helper(Some(ShellHelper::new(&session.storage)));
+
+    let history_path = history_file_path();
+    if let Some(path) = &history_path {
+        // A missing history file is expected on first use.
+        let _ = editor.load_history(path);
+    }
+
+    render_info("Type a command (e.g. 'list', 'add Buy milk -p 2'), 'help', or 'exit'.");
+
+    loop {
+        let line = match editor.readline("todo> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(readline_error(e)),
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        match line {
+            "exit" | "quit" => break,
+            "help" => {
+                Cli::command().print_help()?;
+                continue;
+            }
+            _ => {}
+        }
+
+        let args = match split_args(line) {
+            Ok(args) => args,
+            Err(message) => {
+                render_error(&message);
+                continue;
+            }
+        };
+
+        let cli = match Cli::try_parse_from(std::iter::once("todo".to_string()).chain(args)) {
+            Ok(cli) => cli,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+
+        let line_format = if cli.output_format == OutputFormat::Text {
+            format
+        } else {
+            cli.output_format
+        };
+
+        if matches!(cli.command, Commands::Shell) {
+            render_error("Already in the shell");
+            continue;
+        }
+        if cli.data_file.is_some() || cli.config.is_some() {
+            render_error("--data-file and --config can't be changed inside the shell");
+            continue;
+        }
+
+        if let Err(e) = execute(session, cli.command, line_format) {
+            match line_format {
+                OutputFormat::Text => render_error(&e.to_string()),
+                _ => print_error(&e, line_format),
+            }
+        }
+
+        if let Some(helper) = editor.helper_mut() {
+            helper.refresh(&session.storage);
+        }
+    }
+
+    if let Some(path) = &history_path {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        editor.save_history(path).map_err(readline_error)?;
+    }
+
+    Ok(())
+}
+
+/// Location of the shell history file, if a data directory is known.
+fn history_file_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("todo").join("shell_history"))
+}
+
+fn readline_error(error: ReadlineError) -> TodoError {
+    match error {
+        ReadlineError::Io(e) => TodoError::Io(e),
+        other => TodoError::Io(io::Error::other(other.to_string())),
+    }
+}
+
+/// Split a command line into arguments, honoring quotes and backslashes.
+///
+/// # Errors
+/// Returns a message if a quote is left open.
+fn split_args(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Unterminated quote in command".to_string());
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Tab completion for subcommands, task IDs and tags.
+struct ShellHelper {
+    commands: Vec<String>,
+    tasks: Vec<(u64, String)>,
+    tags: BTreeSet<String>,
+}
+
+impl ShellHelper {
+    fn new(storage: &TaskStorage) -> Self {
+        let mut commands: Vec<String> = Cli::command()
+            .get_subcommands()
+            .map(|command| command.get_name().to_string())
+            .filter(|name| name != "shell")
+            .collect();
+        commands.extend(["exit".to_string(), "help".to_string()]);
+
+        let mut helper = Self {
+            commands,
+            tasks: Vec::new(),
+            tags: BTreeSet::new(),
+        };
+        helper.refresh(storage);
+        helper
+    }
+
+    /// Refresh the known IDs and tags after a command ran.
+    fn refresh(&mut self, storage: &TaskStorage) {
+        self.tasks = storage
+            .tasks
+            .iter()
+            .map(|task| (task.id, task.description.clone()))
+            .collect();
+        self.tags = storage
+            .tasks
+            .iter()
+            .flat_map(|task| task.tags.iter().cloned())
+            .collect();
+    }
+
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let before = &line[..pos];
+        let start = before
+            .rfind(char::is_whitespace)
+            .map_or(0, |index| index + 1);
+        let word = &before[start..];
+        let previous: Vec<&str> = before[..start].split_whitespace().collect();
+
+        let pair = |display: String, replacement: String| Pair {
+            display,
+            replacement,
+        };
+
+        let candidates = if previous.is_empty() {
+            self.commands
+                .iter()
+                .filter(|name| name.starts_with(word))
+                .map(|name| pair(name.clone(), format!("{} ", name)))
+                .collect()
+        } else if let Some(prefix) = word.strip_prefix('@') {
+            self.tags
+                .iter()
+                .filter(|tag| tag.starts_with(prefix))
+                .map(|tag| pair(format!("@{}", tag), format!("@{} ", tag)))
+                .collect()
+        } else if previous.last().is_some_and(|arg| TAG_OPTIONS.contains(arg)) {
+            self.tags
+                .iter()
+                .filter(|tag| tag.starts_with(word))
+                .map(|tag| pair(tag.clone(), format!("{} ", tag)))
+                .collect()
+        } else if ID_COMMANDS.contains(&previous[0]) && !word.starts_with('-') {
+            self.tasks
+                .iter()
+                .filter(|(id, _)| id.to_string().starts_with(word))
+                .map(|(id, description)| {
+                    pair(format!("{:<4} {}", id, description), format!("{} ", id))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        (start, candidates)
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Task;
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#"add "Buy milk" -t 'home stuff' -p 2"#).unwrap(),
+            vec!["add", "Buy milk", "-t", "home stuff", "-p", "2"]
+        );
+        assert_eq!(
+            split_args(r#"edit 3 -d Fix\ bug "" x"#).unwrap(),
+            vec!["edit", "3", "-d", "Fix bug", "", "x"]
+        );
+        assert!(split_args("add \"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_completion_candidates() {
+        let mut storage = TaskStorage::default();
+        let mut task = Task::new(0, "Buy milk".to_string());
+        task.tags = vec!["home".to_string(), "errands".to_string()];
+        storage.add_task(task);
+        storage.add_task(Task::new(0, "Fix bug".to_string()));
+        let helper = ShellHelper::new(&storage);
+
+        let replacements = |line: &str| -> Vec<String> {
+            helper
+                .candidates(line, line.len())
+                .1
+                .into_iter()
+                .map(|pair| pair.replacement)
+                .collect()
+        };
+
+        assert_eq!(
+            replacements("comp"),
+            vec!["complete ", "compact ", "completions "]
+        );
+        assert_eq!(replacements("complete "), vec!["1 ", "2 "]);
+        assert_eq!(replacements("list -t h"), vec!["home "]);
+        assert_eq!(replacements("add Call mom @e"), vec!["@errands "]);
+        assert_eq!(helper.candidates("list -t h", 9).0, 8);
+    }
+}
@@ -0,0 +1,422 @@
+use crate::cli::StatsPeriod;
+use crate::table::{pad, truncate};
+use crate::task::Task;
+use crate::theme::theme;
+use chrono::{Days, Duration, NaiveDate, Weekday};
+use std::collections::HashMap;
+
+/// Blocks used by sparklines, from lowest to highest.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Partial blocks used at the end of bars, in eighths of a cell.
+const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+/// Tags listed under "Busiest tags".
+const TOP_TAGS: usize = 5;
+/// Width of the labels in front of statistics and bars.
+const LABEL_WIDTH: usize = 18;
+
+/// Activity in one day or week of the range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodStats {
+    /// First day of the period.
+    pub start: NaiveDate,
+    /// Tasks created during the period.
+    pub created: usize,
+    /// Tasks completed during the period.
+    pub completed: usize,
+}
+
+/// Productivity statistics over a date range.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// First day of the range.
+    pub from: NaiveDate,
+    /// Last day of the range (inclusive).
+    pub to: NaiveDate,
+    /// Size of the periods in `periods`.
+    pub period: StatsPeriod,
+    /// Tasks created in the range.
+    pub created: usize,
+    /// Tasks completed in the range.
+    pub completed: usize,
+    /// Share of the tasks created in the range that are completed by now.
+    pub completion_rate: Option<f64>,
+    /// Average time from creation to completion of tasks completed in the range.
+    pub average_lead_time: Option<Duration>,
+    /// Tasks completed in the range after their due date.
+    pub completed_late: usize,
+    /// Tasks open now.
+    pub open: usize,
+    /// Open tasks that are overdue now.
+    pub overdue: usize,
+    /// Tags of tasks created or completed in the range, most used first.
+    pub busiest_tags: Vec<(String, usize)>,
+    /// Activity per day or week.
+    pub periods: Vec<PeriodStats>,
+}
+
+impl Stats {
+    /// Compute statistics for tasks created or completed between `from` and `to`.
+    pub fn compute(tasks: &[&Task], from: NaiveDate, to: NaiveDate, period: StatsPeriod) -> Self {
+        let in_range = |date: NaiveDate| (from..=to).contains(&date);
+        let created_in_range: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| in_range(task.created_at.date_naive()))
+            .copied()
+            .collect();
+        let completed_in_range: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| {
+                task.completed
+                    && task
+                        .completed_at
+                        .is_some_and(|at| in_range(at.date_naive()))
+            })
+            .copied()
+            .collect();
+
+        let completion_rate = (!created_in_range.is_empty()).then(|| {
+            let done = created_in_range
+                .iter()
+                .filter(|task| task.completed)
+                .count();
+            done as f64 / created_in_range.len() as f64
+        });
+
+        let lead_times: Vec<Duration> = completed_in_range
+            .iter()
+            .filter_map(|task| task.completed_at.map(|at| at - task.created_at))
+            .collect();
+        let average_lead_time = (!lead_times.is_empty())
+            .then(|| lead_times.iter().sum::<Duration>() / lead_times.len() as i32);
+
+        let completed_late = completed_in_range
+            .iter()
+            .filter(|task| matches!((task.due_date, task.completed_at), (Some(due), Some(at)) if at > due))
+            .count();
+
+        let open = tasks.iter().filter(|task| !task.completed).count();
+        let overdue = tasks.iter().filter(|task| task.is_overdue()).count();
+
+        let mut tag_counts: HashMap<&str, usize> = HashMap::new();
+        for task in tasks.iter().filter(|task| {
+            created_in_range.iter().any(|t| t.id == task.id)
+                || completed_in_range.iter().any(|t| t.id == task.id)
+        }) {
+            for tag in &task.tags {
+                *tag_counts.entry(tag).or_default() += 1;
+            }
+        }
+        let mut busiest_tags: Vec<(String, usize)> = tag_counts
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        busiest_tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        busiest_tags.truncate(TOP_TAGS);
+
+        let mut periods: Vec<PeriodStats> = period_starts(from, to, period)
+            .into_iter()
+            .map(|start| PeriodStats {
+                start,
+                created: 0,
+                completed: 0,
+            })
+            .collect();
+        let index_of = |date: NaiveDate| periods.iter().rposition(|p| p.start <= date);
+        let created_indexes: Vec<usize> = created_in_range
+            .iter()
+            .filter_map(|task| index_of(task.created_at.date_naive()))
+            .collect();
+        let completed_indexes: Vec<usize> = completed_in_range
+            .iter()
+            .filter_map(|task| task.completed_at.and_then(|at| index_of(at.date_naive())))
+            .collect();
+        for index in created_indexes {
+            periods[index].created += 1;
+        }
+        for index in completed_indexes {
+            periods[index].completed += 1;
+        }
+
+        Self {
+            from,
+            to,
+            period,
+            created: created_in_range.len(),
+            completed: completed_in_range.len(),
+            completion_rate,
+            average_lead_time,
+            completed_late,
+            open,
+            overdue,
+            busiest_tags,
+            periods,
+        }
+    }
+}
+
+/// First day of every day or week (starting Monday) overlapping the range.
+fn period_starts(from: NaiveDate, to: NaiveDate, period: StatsPeriod) -> Vec<NaiveDate> {
+    let (mut start, step) = match period {
+        StatsPeriod::Day => (from, 1),
+        StatsPeriod::Week => (from.week(Weekday::Mon).first_day(), 7),
+    };
+    let mut starts = Vec::new();
+    while start <= to {
+        starts.push(start);
+        start = start + Days::new(step);
+    }
+    starts
+}
+
+/// Render statistics.
+pub fn render_stats(stats: &Stats, width: usize) {
+    for line in format_stats(stats, width) {
+        println!("{}", line);
+    }
+}
+
+/// Build the lines of the statistics report without printing them.
+pub fn format_stats(stats: &Stats, width: usize) -> Vec<String> {
+    let theme = theme();
+    let label = |name: &str| theme.muted.apply(&pad(name, LABEL_WIDTH)).to_string();
+    let days = (stats.to - stats.from).num_days() + 1;
+
+    let mut lines = vec![
+        theme
+            .header
+            .apply(&format!(
+                "Statistics for {} – {} ({} days)",
+                stats.from, stats.to, days
+            ))
+            .to_string(),
+        String::new(),
+        format!("{}{}", label("Created:"), stats.created),
+        format!("{}{}", label("Completed:"), stats.completed),
+        format!(
+            "{}{}",
+            label("Completion rate:"),
+            stats
+                .completion_rate
+                .map(|rate| format!("{} of tasks created in range", percent(rate)))
+                .unwrap_or_else(|| "-".to_string())
+        ),
+        format!(
+            "{}{}",
+            label("Avg lead time:"),
+            stats
+                .average_lead_time
+                .map(format_duration)
+                .unwrap_or_else(|| "-".to_string())
+        ),
+        format!(
+            "{}{} of {}",
+            label("Completed late:"),
+            stats.completed_late,
+            stats.completed
+        ),
+    ];
+
+    let overdue = if stats.open == 0 {
+        String::new()
+    } else {
+        format!(
+            " ({} overdue, {})",
+            stats.overdue,
+            percent(stats.overdue as f64 / stats.open as f64)
+        )
+    };
+    let overdue = if stats.overdue > 0 {
+        theme.overdue.apply(&overdue).to_string()
+    } else {
+        overdue
+    };
+    lines.push(format!("{}{}{}", label("Open now:"), stats.open, overdue));
+
+    let unit = match stats.period {
+        StatsPeriod::Day => "day",
+        StatsPeriod::Week => "week",
+    };
+    let completed: Vec<usize> = stats.periods.iter().map(|p| p.completed).collect();
+    let created: Vec<usize> = stats.periods.iter().map(|p| p.created).collect();
+    lines.push(String::new());
+    lines.push(format!(
+        "{}{}",
+        label(&format!("Completed/{}:", unit)),
+        theme.success.apply(&sparkline(&completed))
+    ));
+    lines.push(format!(
+        "{}{}",
+        label(&format!("Created/{}:", unit)),
+        theme.info.apply(&sparkline(&created))
+    ));
+
+    if !stats.busiest_tags.is_empty() {
+        lines.push(String::new());
+        lines.push(theme.header.apply("Busiest tags").to_string());
+        let max = stats.busiest_tags[0].1;
+        let bar_width = width.saturating_sub(LABEL_WIDTH + 6).max(1);
+        for (tag, count) in &stats.busiest_tags {
+            lines.push(format!(
+                "{}{} {}",
+                theme.tag.apply(&pad(
+                    &truncate(&format!("#{}", tag), LABEL_WIDTH - 1),
+                    LABEL_WIDTH
+                )),
+                theme.tag.apply(&bar(*count, max, bar_width)),
+                count
+            ));
+        }
+    }
+
+    lines.push(String::new());
+    lines.push(
+        theme
+            .header
+            .apply(&format!("Completed per {}", unit))
+            .to_string(),
+    );
+    let max = completed.iter().copied().max().unwrap_or(0);
+    let bar_width = width.saturating_sub(LABEL_WIDTH + 14).max(1);
+    for period in &stats.periods {
+        let name = match stats.period {
+            StatsPeriod::Day => period.start.format("%a %b %-d").to_string(),
+            StatsPeriod::Week => format!("Week of {}", period.start.format("%b %-d")),
+        };
+        lines.push(
+            format!(
+                "{}{} {} done, {} new",
+                label(&name),
+                theme.success.apply(&bar(period.completed, max, bar_width)),
+                period.completed,
+                period.created
+            )
+            .trim_end()
+            .to_string(),
+        );
+    }
+
+    lines
+}
+
+/// One block per value, scaled so the largest value gets a full block.
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&value| {
+            let level = (value * (SPARKS.len() - 1)).checked_div(max).unwrap_or(0);
+            SPARKS[level]
+        })
+        .collect()
+}
+
+/// Horizontal bar for `value`, where `max` fills `width` cells.
+///
+/// Bars are drawn in eighths of a cell and padded to `width`.
+pub fn bar(value: usize, max: usize, width: usize) -> String {
+    let eighths = (value * width * 8).checked_div(max).unwrap_or(0);
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(PARTIAL_BLOCKS[eighths % 8]);
+    }
+    pad(&bar, width)
+}
+
+/// Compact duration: "45m", "5h 12m", "3d 4h".
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn percent(ratio: f64) -> String {
+    format!("{:.0}%", ratio * 100.0)
+}
+
+/// Default first day of the range: 30 days, or 12 weeks, up to `to`.
+pub fn default_from(to: NaiveDate, period: StatsPeriod) -> NaiveDate {
+    match period {
+        StatsPeriod::Day => to - Days::new(29),
+        StatsPeriod::Week => (to - Days::new(7 * 11)).week(Weekday::Mon).first_day(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> chrono::DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    fn task(id: u64, tags: &[&str], created: chrono::DateTime<Local>) -> Task {
+        let mut task = Task::new(id, format!("Task {}", id));
+        task.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        task.created_at = created;
+        task
+    }
+
+    #[test]
+    fn test_compute() {
+        let mut done = task(1, &["work"], at(2025, 7, 1, 9));
+        done.completed = true;
+        done.completed_at = Some(at(2025, 7, 3, 9));
+        done.due_date = Some(at(2025, 7, 2, 23));
+        let mut quick = task(2, &["work", "home"], at(2025, 7, 2, 9));
+        quick.completed = true;
+        quick.completed_at = Some(at(2025, 7, 2, 13));
+        let open = task(3, &["home"], at(2025, 7, 7, 9));
+        let before = task(4, &["old"], at(2025, 6, 1, 9));
+        let tasks = [done, quick, open, before];
+        let refs: Vec<&Task> = tasks.iter().collect();
+
+        let from = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 7, 10).unwrap();
+        let stats = Stats::compute(&refs, from, to, StatsPeriod::Week);
+
+        assert_eq!((stats.created, stats.completed), (3, 2));
+        assert_eq!(stats.completion_rate, Some(2.0 / 3.0));
+        assert_eq!(stats.average_lead_time, Some(Duration::hours(26)));
+        assert_eq!(stats.completed_late, 1);
+        assert_eq!(stats.open, 2);
+        assert_eq!(
+            stats.busiest_tags,
+            vec![("home".to_string(), 2), ("work".to_string(), 2)]
+        );
+        let weeks: Vec<(NaiveDate, usize, usize)> = stats
+            .periods
+            .iter()
+            .map(|p| (p.start, p.created, p.completed))
+            .collect();
+        assert_eq!(
+            weeks,
+            vec![
+                (NaiveDate::from_ymd_opt(2025, 6, 30).unwrap(), 2, 2),
+                (NaiveDate::from_ymd_opt(2025, 7, 7).unwrap(), 1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sparkline_and_bar() {
+        assert_eq!(sparkline(&[0, 1, 7, 3]), "▁▂█▄");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(bar(4, 4, 3), "███");
+        assert_eq!(bar(1, 4, 3), "▊  ");
+        assert_eq!(bar(0, 0, 2), "  ");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(45)), "45m");
+        assert_eq!(format_duration(Duration::minutes(312)), "5h 12m");
+        assert_eq!(format_duration(Duration::hours(76)), "3d 4h");
+    }
+}
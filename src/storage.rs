@@ -1,16 +1,61 @@
+use crate::config::CompletionPolicy;
 use crate::error::{Result, TodoError};
 use crate::task::Task;
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// How many tasks carry a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    /// The tag.
+    pub tag: String,
+    /// Tasks with the tag.
+    pub tasks: usize,
+    /// Tasks with the tag that are not completed.
+    pub open: usize,
+}
+
+/// What `TaskStorage::complete_tasks` did besides completing the tasks.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Completed {
+    /// Tasks that were already completed.
+    pub already_completed: Vec<u64>,
+    /// Open subtasks left behind under `CompletionPolicy::Warn`.
+    pub open_subtasks: Vec<u64>,
+    /// Subtasks completed along with their parents under
+    /// `CompletionPolicy::Cascade`.
+    pub cascaded: Vec<u64>,
+}
+
+/// A deleted task kept in the trash until it is restored or expires.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrashedTask {
+    /// The task as it was when deleted.
+    pub task: Task,
+    /// When the task was deleted.
+    pub deleted_at: DateTime<Local>,
+}
 
 /// Persistent storage for tasks and their IDs.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TaskStorage {
     /// All tasks in the storage.
     pub tasks: Vec<Task>,
     /// Next available task ID.
     pub next_id: u64,
+    /// Deleted tasks, oldest deletion first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<TrashedTask>,
+    /// Tasks as they were last loaded or saved, by UUID, used to find the
+    /// fields that changed when saving.
+    #[serde(skip)]
+    saved: HashMap<Uuid, Task>,
 }
 
 impl Default for TaskStorage {
@@ -18,6 +63,8 @@ impl Default for TaskStorage {
         Self {
             tasks: Vec::new(),
             next_id: 1,
+            trash: Vec::new(),
+            saved: HashMap::new(),
         }
     }
 }
@@ -26,6 +73,23 @@ impl TaskStorage {
     /// Load tasks from a JSON file at the given path.
     ///
     /// Returns a default storage if the file does not exist or is empty.
+    /// Loading never writes to the file: tasks from files written before tasks
+    /// had UUIDs get them in memory, and they are stored with the next save.
+    ///
+    /// # Examples
+    /// ```
+    /// use todo_cli::{Task, TaskStorage};
+    ///
+    /// let path = std::env::temp_dir().join("todo-doc-storage.json");
+    /// let mut storage = TaskStorage::default();
+    /// storage.add_task(Task::new(0, "Water the plants".to_string()));
+    /// storage.save_to_file(&path)?;
+    ///
+    /// let loaded = TaskStorage::load_from_file(&path)?;
+    /// assert_eq!(loaded.tasks[0].description, "Water the plants");
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok::<(), todo_cli::TodoError>(())
+    /// ```
     pub fn load_from_file(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::default());
@@ -36,7 +100,7 @@ impl TaskStorage {
             return Ok(Self::default());
         }
 
-        match serde_json::from_str(&content) {
+        match Self::from_json(&content) {
             Ok(storage) => Ok(storage),
             Err(e) => {
                 let backup_path = path.with_extension("json.backup");
@@ -51,19 +115,139 @@ impl TaskStorage {
         }
     }
 
+    /// Read tasks from a JSON file without ever writing to it, e.g. another
+    /// copy of the data file that is being merged. An empty file has no tasks.
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        if content.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        Self::from_json(&content)
+            .map_err(|e| TodoError::DataCorruption(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse the contents of a data file.
+    ///
+    /// Tasks from files written before tasks had UUIDs get them now.
+    pub fn from_json(content: &str) -> serde_json::Result<Self> {
+        let mut storage: Self = serde_json::from_str(content)?;
+        storage.assign_missing_uuids();
+        storage.remember_saved();
+        Ok(storage)
+    }
+
     /// Save tasks to a JSON file at the given path.
-    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+    ///
+    /// Fields changed since the storage was loaded or last saved get the
+    /// current time in `Task::modified`. The data is written to a temporary
+    /// file next to it first and then renamed over the old file, so a failed
+    /// write never leaves a half-written data file behind.
+    pub fn save_to_file(&mut self, path: &Path) -> Result<()> {
+        let now = Local::now();
+        for task in &mut self.tasks {
+            if let Some(old) = self.saved.get(&task.uuid) {
+                task.stamp_changes(old, now);
+            }
+        }
+
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(path, json)?;
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+
+        fs::write(&temp_path, json)?;
+        if let Err(e) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        self.remember_saved();
         Ok(())
     }
 
+    fn remember_saved(&mut self) {
+        self.saved = self
+            .tasks
+            .iter()
+            .map(|task| (task.uuid, task.clone()))
+            .collect();
+    }
+
+    /// Tasks added, changed or removed since the storage was loaded or last
+    /// saved, as `(before, after)` pairs.
+    ///
+    /// Added tasks have no `before` and removed ones no `after`. Only the
+    /// fields in `TRACKED_FIELDS` count as changes.
+    pub fn unsaved_changes(&self) -> Vec<(Option<&Task>, Option<&Task>)> {
+        let mut changes: Vec<(Option<&Task>, Option<&Task>)> = self
+            .tasks
+            .iter()
+            .filter_map(|task| match self.saved.get(&task.uuid) {
+                None => Some((None, Some(task))),
+                Some(saved) if !saved.changed_fields(task).is_empty() => {
+                    Some((Some(saved), Some(task)))
+                }
+                Some(_) => None,
+            })
+            .collect();
+        let mut removed: Vec<&Task> = self
+            .saved
+            .values()
+            .filter(|saved| !self.tasks.iter().any(|task| task.uuid == saved.uuid))
+            .collect();
+        removed.sort_by_key(|task| task.id);
+        changes.extend(removed.into_iter().map(|task| (Some(task), None)));
+        changes
+    }
+
+    /// Give every task (including trashed ones) without a UUID one, and
+    /// point subtasks at their parent's UUID instead of its ID.
+    ///
+    /// The UUID is derived from the task's ID and creation time, so copies
+    /// of the same old file upgraded on different machines agree on it.
+    fn assign_missing_uuids(&mut self) {
+        let trashed = self.trash.iter_mut().map(|trashed| &mut trashed.task);
+        for task in self.tasks.iter_mut().chain(trashed) {
+            if task.uuid.is_nil() {
+                let created = task.created_at.timestamp_nanos_opt().unwrap_or_default();
+                let mut bytes = [0; 16];
+                bytes[..8].copy_from_slice(&task.id.to_be_bytes());
+                bytes[8..].copy_from_slice(&created.to_be_bytes());
+                task.uuid = uuid::Builder::from_random_bytes(bytes).into_uuid();
+            }
+        }
+        let uuids: Vec<(u64, Uuid)> = self
+            .tasks
+            .iter()
+            .chain(self.trash.iter().map(|trashed| &trashed.task))
+            .map(|task| (task.id, task.uuid))
+            .collect();
+        let trashed = self.trash.iter_mut().map(|trashed| &mut trashed.task);
+        for task in self.tasks.iter_mut().chain(trashed) {
+            if let Some(parent_id) = task.parent_id.take() {
+                task.parent = uuids
+                    .iter()
+                    .find(|(id, _)| *id == parent_id)
+                    .map(|&(_, uuid)| uuid);
+            }
+        }
+    }
+
     /// Add a new task to the storage and return its ID.
+    ///
+    /// The task gets the next free ID, and a new UUID if it has none or its
+    /// UUID is already taken (e.g. when importing the same file twice).
     pub fn add_task(&mut self, mut task: Task) -> u64 {
+        let taken = |uuid: Uuid| {
+            self.tasks.iter().any(|other| other.uuid == uuid)
+                || self.trash.iter().any(|trashed| trashed.task.uuid == uuid)
+        };
+        if task.uuid.is_nil() || taken(task.uuid) {
+            task.uuid = Uuid::new_v4();
+        }
         task.id = self.next_id;
         let task_id = self.next_id;
         self.next_id += 1;
@@ -71,7 +255,6 @@ impl TaskStorage {
         task_id
     }
 
-    #[allow(dead_code)]
     /// Get a reference to a task by its ID.
     pub fn get_task(&self, id: u64) -> Option<&Task> {
         self.tasks.iter().find(|task| task.id == id)
@@ -82,17 +265,295 @@ impl TaskStorage {
         self.tasks.iter_mut().find(|task| task.id == id)
     }
 
-    /// Delete a task by its ID.
-    pub fn delete_task(&mut self, id: u64) -> Result<()> {
+    /// Move a task to the trash by its ID and return it.
+    pub fn delete_task(&mut self, id: u64) -> Result<Task> {
         let index = self
             .tasks
             .iter()
             .position(|task| task.id == id)
             .ok_or(TodoError::TaskNotFound(id))?;
-        self.tasks.remove(index);
+        let task = self.tasks.remove(index);
+        self.trash.push(TrashedTask {
+            task: task.clone(),
+            deleted_at: Local::now(),
+        });
+        Ok(task)
+    }
+
+    /// Move a task from the trash back into the task list, keeping its ID
+    /// unless another task has taken it meanwhile.
+    ///
+    /// # Errors
+    /// Returns `TodoError::UnknownTask` if the trash has no task with this UUID.
+    pub fn restore_task(&mut self, uuid: Uuid) -> Result<&Task> {
+        let index = self
+            .trash
+            .iter()
+            .position(|trashed| trashed.task.uuid == uuid)
+            .ok_or_else(|| TodoError::UnknownTask(uuid.to_string()))?;
+        let mut task = self.trash.remove(index).task;
+        // The short ID may have been given to another task by `todo compact`.
+        if self.get_task(task.id).is_some() {
+            task.id = self.next_id;
+            self.next_id += 1;
+        }
+        let id = task.id;
+        let position = self
+            .tasks
+            .iter()
+            .position(|other| other.id > id)
+            .unwrap_or(self.tasks.len());
+        self.tasks.insert(position, task);
+        Ok(&self.tasks[position])
+    }
+
+    /// Permanently remove tasks deleted before `before` from the trash.
+    ///
+    /// Returns how many were removed.
+    pub fn expire_trash(&mut self, before: DateTime<Local>) -> usize {
+        let count = self.trash.len();
+        self.trash.retain(|trashed| trashed.deleted_at >= before);
+        count - self.trash.len()
+    }
+
+    /// Complete tasks, handling their open subtasks as `policy` says. A note
+    /// given for a task that is already completed replaces its note.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TaskNotFound` for an unknown ID, and
+    /// `TodoError::OpenSubtasks` under `CompletionPolicy::Refuse` when a task
+    /// has open subtasks that aren't completed along with it. Nothing changes
+    /// then.
+    pub fn complete_tasks(
+        &mut self,
+        ids: &[u64],
+        note: Option<&str>,
+        policy: CompletionPolicy,
+    ) -> Result<Completed> {
+        let mut completed = Completed::default();
+        let mut subtasks = Vec::new();
+        for &id in ids {
+            let task = self.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+            if task.completed {
+                completed.already_completed.push(id);
+                continue;
+            }
+            let open: Vec<u64> = self
+                .open_subtasks(id)
+                .into_iter()
+                .filter(|subtask| !ids.contains(subtask))
+                .collect();
+            if policy == CompletionPolicy::Refuse && !open.is_empty() {
+                return Err(TodoError::OpenSubtasks { id, subtasks: open });
+            }
+            subtasks.extend(open);
+        }
+        subtasks.sort_unstable();
+        subtasks.dedup();
+
+        for &id in ids {
+            let task = self.get_task_mut(id).ok_or(TodoError::TaskNotFound(id))?;
+            if !task.completed {
+                task.complete();
+                task.completion_note = note.map(str::to_string);
+            } else if let Some(note) = note {
+                task.completion_note = Some(note.to_string());
+            }
+        }
+        match policy {
+            CompletionPolicy::Refuse => {}
+            CompletionPolicy::Warn => completed.open_subtasks = subtasks,
+            CompletionPolicy::Cascade => {
+                for &id in &subtasks {
+                    if let Some(task) = self.get_task_mut(id) {
+                        task.complete();
+                    }
+                }
+                completed.cascaded = subtasks;
+            }
+        }
+        Ok(completed)
+    }
+
+    /// Reopen completed tasks. Returns the IDs of those that were already
+    /// open.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TaskNotFound` for an unknown ID. Nothing changes
+    /// then.
+    pub fn reopen_tasks(&mut self, ids: &[u64]) -> Result<Vec<u64>> {
+        let mut already_open = Vec::new();
+        for &id in ids {
+            let task = self.get_task(id).ok_or(TodoError::TaskNotFound(id))?;
+            if !task.completed {
+                already_open.push(id);
+            }
+        }
+        for &id in ids {
+            if let Some(task) = self.get_task_mut(id)
+                && task.completed
+            {
+                task.reopen();
+            }
+        }
+        Ok(already_open)
+    }
+
+    /// IDs of the open subtasks of a task, their subtasks and so on.
+    pub fn open_subtasks(&self, id: u64) -> Vec<u64> {
+        let Some(task) = self.get_task(id) else {
+            return Vec::new();
+        };
+        let mut seen = vec![task.uuid];
+        let mut parents = vec![task.uuid];
+        let mut open = Vec::new();
+        while let Some(parent) = parents.pop() {
+            for task in self.tasks.iter().filter(|task| task.parent == Some(parent)) {
+                if seen.contains(&task.uuid) {
+                    continue;
+                }
+                seen.push(task.uuid);
+                parents.push(task.uuid);
+                if !task.completed {
+                    open.push(task.id);
+                }
+            }
+        }
+        open.sort_unstable();
+        open
+    }
+
+    /// Make a task a subtask of the task with UUID `parent`, or a top-level
+    /// task with `None`.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TaskNotFound` for an unknown ID, and
+    /// `TodoError::InvalidParent` when `parent` is the task itself or one of
+    /// its subtasks.
+    pub fn set_parent(&mut self, id: u64, parent: Option<Uuid>) -> Result<()> {
+        let uuid = self.get_task(id).ok_or(TodoError::TaskNotFound(id))?.uuid;
+        // Walk up from the new parent; the steps are bounded in case the
+        // file already has a cycle.
+        let mut ancestor = parent;
+        for _ in 0..=self.tasks.len() {
+            let Some(current) = ancestor else {
+                break;
+            };
+            if current == uuid {
+                return Err(TodoError::InvalidParent(format!(
+                    "task {} can't be a subtask of itself or of its own subtasks",
+                    id
+                )));
+            }
+            ancestor = self
+                .tasks
+                .iter()
+                .find(|task| task.uuid == current)
+                .and_then(|task| task.parent);
+        }
+        if let Some(task) = self.get_task_mut(id) {
+            task.parent = parent;
+        }
         Ok(())
     }
 
+    /// Renumber short IDs: open tasks become 1, 2, 3, ... in their current
+    /// order, followed by completed tasks. UUIDs are unchanged.
+    ///
+    /// `next_id` ends up above every ID still in use, including those of
+    /// trashed tasks and the `archived` ones, so none of them is handed out
+    /// again. Returns `(old, new)` for every task whose ID changed.
+    pub fn compact_ids(&mut self, archived: &[u64]) -> Vec<(u64, u64)> {
+        self.tasks.sort_by_key(|task| (task.completed, task.id));
+        let mut changes = Vec::new();
+        for (task, new_id) in self.tasks.iter_mut().zip(1..) {
+            if task.id != new_id {
+                changes.push((task.id, new_id));
+                task.id = new_id;
+            }
+        }
+        self.tasks.sort_by_key(|task| task.id);
+        let in_use = self
+            .tasks
+            .iter()
+            .chain(self.trash.iter().map(|trashed| &trashed.task))
+            .map(|task| task.id)
+            .chain(archived.iter().copied());
+        self.next_id = in_use.max().unwrap_or(0) + 1;
+        changes
+    }
+
+    /// Every tag in use with its task counts, most used first.
+    pub fn tag_counts(&self) -> Vec<TagCount> {
+        let mut counts: Vec<TagCount> = Vec::new();
+        for task in &self.tasks {
+            for tag in &task.tags {
+                let index = match counts.iter().position(|count| &count.tag == tag) {
+                    Some(index) => index,
+                    None => {
+                        counts.push(TagCount {
+                            tag: tag.clone(),
+                            tasks: 0,
+                            open: 0,
+                        });
+                        counts.len() - 1
+                    }
+                };
+                counts[index].tasks += 1;
+                if !task.completed {
+                    counts[index].open += 1;
+                }
+            }
+        }
+        counts.sort_by(|a, b| b.tasks.cmp(&a.tasks).then_with(|| a.tag.cmp(&b.tag)));
+        counts
+    }
+
+    /// Rename a tag on every task that has it and return their IDs.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TagNotFound` if no task has `from`, and
+    /// `TodoError::TagExists` if `to` is already in use (merge it instead).
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<Vec<u64>> {
+        let to = valid_tag(to)?;
+        if self
+            .tasks
+            .iter()
+            .any(|task| task.tags.iter().any(|t| t == to))
+        {
+            return Err(TodoError::TagExists(to.to_string()));
+        }
+        self.merge_tag(from, to)
+    }
+
+    /// Replace a tag with another on every task that has it and return their
+    /// IDs. Tasks that already have `into` just lose `from`.
+    ///
+    /// # Errors
+    /// Returns `TodoError::TagNotFound` if no task has `from`.
+    pub fn merge_tag(&mut self, from: &str, into: &str) -> Result<Vec<u64>> {
+        let into = valid_tag(into)?;
+        let mut ids = Vec::new();
+        for task in &mut self.tasks {
+            let Some(index) = task.tags.iter().position(|t| t == from) else {
+                continue;
+            };
+            ids.push(task.id);
+            if from == into {
+                continue;
+            }
+            if task.tags.iter().any(|t| t == into) {
+                task.tags.remove(index);
+            } else {
+                task.tags[index] = into.to_string();
+            }
+        }
+        if ids.is_empty() {
+            return Err(TodoError::TagNotFound(from.to_string()));
+        }
+        Ok(ids)
+    }
+
     /// Get tasks filtered by included/excluded tags and completion status.
     pub fn get_filtered_tasks(
         &self,
@@ -104,6 +565,23 @@ impl TaskStorage {
     }
 }
 
+fn valid_tag(tag: &str) -> Result<&str> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(TodoError::InvalidTag(tag.to_string()));
+    }
+    Ok(tag)
+}
+
+/// Fingerprint of a file's contents, to tell whether another process changed
+/// it. `None` when the file can't be read, e.g. because it doesn't exist yet.
+pub(crate) fn content_stamp(path: &Path) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
 /// Get the data file path, prioritizing environment variable, then custom path, then default locations.
 pub fn get_data_file_path(custom_path: Option<&str>) -> PathBuf {
     if let Ok(env_path) = std::env::var("TODO_DATA_FILE") {
@@ -125,3 +603,281 @@ pub fn get_data_file_path(custom_path: Option<&str>) -> PathBuf {
 
     PathBuf::from("tasks.json")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn storage_with_tags(tags: &[&[&str]]) -> TaskStorage {
+        let mut storage = TaskStorage::default();
+        for (index, task_tags) in tags.iter().enumerate() {
+            let mut task = Task::new(0, format!("Task {}", index + 1));
+            task.tags = task_tags.iter().map(|tag| tag.to_string()).collect();
+            storage.add_task(task);
+        }
+        storage
+    }
+
+    fn tags(storage: &TaskStorage) -> Vec<Vec<&str>> {
+        storage
+            .tasks
+            .iter()
+            .map(|task| task.tags.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_tag_counts() {
+        let mut storage = storage_with_tags(&[&["work", "urgent"], &["work"], &["home"]]);
+        storage.tasks[1].complete();
+
+        let counts = storage.tag_counts();
+        let summary: Vec<(&str, usize, usize)> = counts
+            .iter()
+            .map(|count| (count.tag.as_str(), count.tasks, count.open))
+            .collect();
+        assert_eq!(summary, [("work", 2, 1), ("home", 1, 1), ("urgent", 1, 1)]);
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let mut storage = storage_with_tags(&[&[], &[], &[]]);
+        storage.delete_task(2).unwrap();
+        storage.delete_task(1).unwrap();
+        assert_eq!(storage.tasks.len(), 1);
+        assert_eq!(storage.trash.len(), 2);
+        let uuid_of = |storage: &TaskStorage, id: u64| {
+            storage
+                .trash
+                .iter()
+                .find(|trashed| trashed.task.id == id)
+                .map(|trashed| trashed.task.uuid)
+        };
+        assert!(matches!(
+            storage.restore_task(Uuid::new_v4()),
+            Err(TodoError::UnknownTask(_))
+        ));
+
+        let uuid = uuid_of(&storage, 2).unwrap();
+        assert_eq!(storage.restore_task(uuid).unwrap().description, "Task 2");
+        let ids: Vec<u64> = storage.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, [2, 3]);
+
+        storage.trash[0].deleted_at = Local::now() - chrono::Duration::days(40);
+        assert_eq!(
+            storage.expire_trash(Local::now() - chrono::Duration::days(30)),
+            1
+        );
+        assert!(storage.trash.is_empty());
+    }
+
+    #[test]
+    fn test_save_stamps_changed_fields() {
+        let dir = TempDir::new("storage");
+        let path = dir.join("tasks.json");
+        let mut storage = storage_with_tags(&[&["work"], &[]]);
+        storage.save_to_file(&path).unwrap();
+        assert!(storage.tasks.iter().all(|task| task.modified.is_empty()));
+
+        let mut loaded = TaskStorage::load_from_file(&path).unwrap();
+        loaded.get_task_mut(1).unwrap().priority = Some(2);
+        loaded.get_task_mut(1).unwrap().complete();
+        loaded.save_to_file(&path).unwrap();
+
+        let fields: Vec<&str> = loaded.tasks[0]
+            .modified
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(fields, ["completed", "priority"]);
+        assert!(loaded.tasks[1].modified.is_empty());
+    }
+
+    #[test]
+    fn test_load_migrates_without_writing() {
+        let dir = TempDir::new("legacy");
+        let path = dir.join("tasks.json");
+        let legacy = r#"{"tasks": [{"id": 1, "description": "Old", "priority": null,
+            "due_date": null, "tags": [], "completed": false,
+            "created_at": "2025-07-01T08:30:00Z", "completed_at": null}], "next_id": 2}"#;
+        std::fs::write(&path, legacy).unwrap();
+
+        let mut storage = TaskStorage::load_from_file(&path).unwrap();
+        let uuid = storage.tasks[0].uuid;
+        assert!(!uuid.is_nil());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), legacy);
+        // The same UUID comes back until the file is saved.
+        assert_eq!(
+            TaskStorage::load_from_file(&path).unwrap().tasks[0].uuid,
+            uuid
+        );
+        assert!(storage.unsaved_changes().is_empty());
+
+        storage.save_to_file(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains(&uuid.to_string()));
+    }
+
+    #[test]
+    fn test_unsaved_changes() {
+        let dir = TempDir::new("changes");
+        let path = dir.join("tasks.json");
+        let mut storage = storage_with_tags(&[&[], &[], &[]]);
+        assert_eq!(storage.unsaved_changes().len(), 3);
+        storage.save_to_file(&path).unwrap();
+        assert!(storage.unsaved_changes().is_empty());
+
+        storage.get_task_mut(1).unwrap().description = "Changed".to_string();
+        storage.delete_task(2).unwrap();
+        storage.add_task(Task::new(0, "New".to_string()));
+        let changes: Vec<(Option<u64>, Option<u64>)> = storage
+            .unsaved_changes()
+            .into_iter()
+            .map(|(old, new)| (old.map(|t| t.id), new.map(|t| t.id)))
+            .collect();
+        assert_eq!(
+            changes,
+            [(Some(1), Some(1)), (None, Some(4)), (Some(2), None)]
+        );
+    }
+
+    #[test]
+    fn test_compact_ids() {
+        let mut storage = storage_with_tags(&[&[], &[], &[], &[], &[]]);
+        storage.tasks[0].complete();
+        storage.delete_task(2).unwrap();
+        let uuid = storage.get_task(5).unwrap().uuid;
+
+        assert_eq!(storage.compact_ids(&[]), [(3, 1), (4, 2), (5, 3), (1, 4)]);
+        assert_eq!(storage.get_task(3).unwrap().uuid, uuid);
+        assert_eq!(storage.next_id, 5);
+
+        // Task 2 in the trash gets a fresh ID only if 2 was reused.
+        let trashed = storage.trash[0].task.uuid;
+        assert_eq!(storage.restore_task(trashed).unwrap().id, 5);
+
+        // IDs of trashed and archived tasks are never handed out again.
+        let mut storage = storage_with_tags(&[&[], &[], &[]]);
+        storage.delete_task(3).unwrap();
+        storage.compact_ids(&[]);
+        assert_eq!(storage.next_id, 4);
+        storage.compact_ids(&[9]);
+        assert_eq!(storage.next_id, 10);
+    }
+
+    #[test]
+    fn test_rename_and_merge_tags() {
+        let mut storage = storage_with_tags(&[&["wrk", "urgent"], &["work"], &["wrk", "work"]]);
+
+        assert!(matches!(
+            storage.rename_tag("wrk", "work"),
+            Err(TodoError::TagExists(_))
+        ));
+        assert!(matches!(
+            storage.rename_tag("nope", "other"),
+            Err(TodoError::TagNotFound(_))
+        ));
+
+        assert_eq!(storage.merge_tag("wrk", "work").unwrap(), [1, 3]);
+        assert_eq!(
+            tags(&storage),
+            [vec!["work", "urgent"], vec!["work"], vec!["work"]]
+        );
+
+        assert_eq!(storage.rename_tag("urgent", " asap ").unwrap(), [1]);
+        assert_eq!(tags(&storage)[0], ["work", "asap"]);
+        assert!(matches!(
+            storage.rename_tag("asap", ""),
+            Err(TodoError::InvalidTag(_))
+        ));
+    }
+
+    /// Tasks 1 > 2 > 3 and 1 > 4, where 4 is completed and 5 is unrelated.
+    fn storage_with_subtasks() -> TaskStorage {
+        let mut storage = storage_with_tags(&[&[], &[], &[], &[], &[]]);
+        let uuid_of = |storage: &TaskStorage, id: u64| storage.get_task(id).unwrap().uuid;
+        for (child, parent) in [(2, 1), (3, 2), (4, 1)] {
+            let parent = uuid_of(&storage, parent);
+            storage.set_parent(child, Some(parent)).unwrap();
+        }
+        storage.get_task_mut(4).unwrap().complete();
+        storage
+    }
+
+    #[test]
+    fn test_open_subtasks_and_cycles() {
+        let mut storage = storage_with_subtasks();
+        assert_eq!(storage.open_subtasks(1), [2, 3]);
+        assert_eq!(storage.open_subtasks(3), Vec::<u64>::new());
+
+        let uuid_of_3 = storage.get_task(3).unwrap().uuid;
+        assert!(matches!(
+            storage.set_parent(1, Some(uuid_of_3)),
+            Err(TodoError::InvalidParent(_))
+        ));
+        let uuid_of_1 = storage.get_task(1).unwrap().uuid;
+        assert!(storage.set_parent(1, Some(uuid_of_1)).is_err());
+        assert_eq!(storage.get_task(1).unwrap().parent, None);
+    }
+
+    #[test]
+    fn test_complete_with_open_subtasks() {
+        let mut storage = storage_with_subtasks();
+        match storage.complete_tasks(&[1], None, CompletionPolicy::Refuse) {
+            Err(TodoError::OpenSubtasks { id, subtasks }) => {
+                assert_eq!(id, 1);
+                assert_eq!(subtasks, [2, 3]);
+            }
+            other => panic!("expected a refusal, got {:?}", other),
+        }
+        assert!(!storage.get_task(1).unwrap().completed);
+
+        // Completing the whole family at once is fine.
+        let completed = storage
+            .complete_tasks(&[1, 2, 3], None, CompletionPolicy::Refuse)
+            .unwrap();
+        assert_eq!(completed, Completed::default());
+
+        let mut storage = storage_with_subtasks();
+        let completed = storage
+            .complete_tasks(&[2], Some("done"), CompletionPolicy::Warn)
+            .unwrap();
+        assert_eq!(completed.open_subtasks, [3]);
+        assert!(storage.get_task(2).unwrap().completed);
+        assert!(!storage.get_task(3).unwrap().completed);
+
+        let completed = storage
+            .complete_tasks(&[1, 2], Some("again"), CompletionPolicy::Cascade)
+            .unwrap();
+        assert_eq!(completed.already_completed, [2]);
+        assert_eq!(completed.cascaded, [3]);
+        assert!(storage.tasks.iter().take(4).all(|task| task.completed));
+        assert!(!storage.get_task(5).unwrap().completed);
+        assert_eq!(
+            storage.get_task(2).unwrap().completion_note.as_deref(),
+            Some("again")
+        );
+        assert_eq!(storage.get_task(3).unwrap().completion_note, None);
+    }
+
+    #[test]
+    fn test_reopen_tasks() {
+        let mut storage = storage_with_subtasks();
+        storage
+            .complete_tasks(&[2], Some("done"), CompletionPolicy::Warn)
+            .unwrap();
+
+        assert!(matches!(
+            storage.reopen_tasks(&[2, 99]),
+            Err(TodoError::TaskNotFound(99))
+        ));
+        assert!(storage.get_task(2).unwrap().completed);
+
+        assert_eq!(storage.reopen_tasks(&[1, 2]).unwrap(), [1]);
+        let task = storage.get_task(2).unwrap();
+        assert!(!task.completed);
+        assert_eq!(task.completed_at, None);
+        assert_eq!(task.completion_note, None);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::config::SyncConfig;
+use crate::error::{Result, TodoError};
+use crate::merge::{self, MergeSummary};
+use crate::storage::TaskStorage;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Path of the tasks file inside the sync repository when `sync.file` isn't set.
+const DEFAULT_SYNC_FILE: &str = "tasks.json";
+
+/// How the remote branch was brought in by `todo sync`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pull {
+    /// The remote branch doesn't exist yet; pushing creates it.
+    Skipped,
+    /// The remote had nothing new.
+    UpToDate,
+    /// Only the remote had new commits; the local branch moved to it.
+    FastForward,
+    /// Both sides had new commits; the data files were merged task by task.
+    Merged(MergeSummary),
+}
+
+impl Pull {
+    /// Name used in JSON output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Pull::Skipped => "skipped",
+            Pull::UpToDate => "up_to_date",
+            Pull::FastForward => "fast_forward",
+            Pull::Merged(_) => "merge",
+        }
+    }
+}
+
+/// What `todo sync` did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Whether local changes were committed.
+    pub committed: bool,
+    /// `remote/branch` synced with, or `None` without a remote.
+    pub remote: Option<String>,
+    /// How the remote branch was brought in, if there is a remote.
+    pub pull: Option<Pull>,
+    /// Whether the result was pushed.
+    pub pushed: bool,
+}
+
+/// Commit the tasks to the sync repository, merge in the remote branch and
+/// push the result.
+///
+/// Returns the synced tasks, which the caller saves to the data file.
+pub fn sync(
+    storage: &TaskStorage,
+    config: &SyncConfig,
+    push: bool,
+) -> Result<(TaskStorage, SyncReport)> {
+    let repo = config.repo.as_deref().ok_or_else(|| {
+        TodoError::Sync("no repository configured; set sync.repo in the config file".to_string())
+    })?;
+    let git = Git { repo };
+    if !git.check(&["rev-parse", "--is-inside-work-tree"])? {
+        return Err(TodoError::Sync(format!(
+            "{} is not a git repository; run 'git init' there first",
+            repo.display()
+        )));
+    }
+
+    let file = config
+        .file
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SYNC_FILE));
+    let file_name = file.to_string_lossy().into_owned();
+    let repo_file = repo.join(&file);
+
+    storage.clone().save_to_file(&repo_file)?;
+    let committed = git.commit(&file_name, "Update tasks")?;
+
+    let remote = match &config.remote {
+        Some(remote) => Some(remote.clone()),
+        None => git
+            .run(&["remote"])?
+            .lines()
+            .any(|name| name == "origin")
+            .then(|| "origin".to_string()),
+    };
+    let mut report = SyncReport {
+        committed,
+        remote: None,
+        pull: None,
+        pushed: false,
+    };
+    if let Some(remote) = remote {
+        let branch = match &config.branch {
+            Some(branch) => branch.clone(),
+            None => git.run(&["symbolic-ref", "--short", "HEAD"])?,
+        };
+        report.pull = Some(git.pull(&remote, &branch, &file_name, &repo_file)?);
+        if push {
+            let refspec = format!("HEAD:refs/heads/{}", branch);
+            git.run(&["push", "--quiet", &remote, &refspec])?;
+            report.pushed = true;
+        }
+        report.remote = Some(format!("{}/{}", remote, branch));
+    }
+
+    Ok((TaskStorage::load_from_file(&repo_file)?, report))
+}
+
+/// Runs git commands in the sync repository.
+struct Git<'a> {
+    repo: &'a Path,
+}
+
+impl Git<'_> {
+    fn command(&self, args: &[&str]) -> Result<Output> {
+        Command::new("git")
+            .arg("-C")
+            .arg(self.repo)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .map_err(|e| TodoError::Sync(format!("couldn't run git: {}", e)))
+    }
+
+    /// Run a command that must succeed and return its output.
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let output = self.command(args)?;
+        if !output.status.success() {
+            return Err(TodoError::Sync(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    }
+
+    /// Run a command and report whether it succeeded.
+    fn check(&self, args: &[&str]) -> Result<bool> {
+        Ok(self.command(args)?.status.success())
+    }
+
+    /// Commit the file if it changed. Returns whether there was a commit.
+    fn commit(&self, file: &str, message: &str) -> Result<bool> {
+        self.run(&["add", "--", file])?;
+        if self.check(&["diff", "--cached", "--quiet", "--", file])? {
+            return Ok(false);
+        }
+        self.run(&["commit", "--quiet", "-m", message, "--", file])?;
+        Ok(true)
+    }
+
+    /// The data file as of a commit, or no tasks if it isn't there.
+    fn show_storage(&self, rev: &str, file: &str) -> Result<TaskStorage> {
+        let output = self.command(&["show", &format!("{}:./{}", rev, file)])?;
+        if !output.status.success() {
+            return Ok(TaskStorage::default());
+        }
+        let content = String::from_utf8_lossy(&output.stdout);
+        TaskStorage::from_json(&content)
+            .map_err(|e| TodoError::Sync(format!("{} in {} is invalid: {}", file, rev, e)))
+    }
+
+    /// Fetch the remote branch and bring it into the current branch, merging
+    /// the data file task by task when both sides changed.
+    fn pull(&self, remote: &str, branch: &str, file: &str, repo_file: &Path) -> Result<Pull> {
+        let head = format!("refs/heads/{}", branch);
+        if self
+            .run(&["ls-remote", "--heads", remote, &head])?
+            .is_empty()
+        {
+            return Ok(Pull::Skipped);
+        }
+        self.run(&["fetch", "--quiet", remote, &head])?;
+        if self.check(&["merge-base", "--is-ancestor", "FETCH_HEAD", "HEAD"])? {
+            return Ok(Pull::UpToDate);
+        }
+        if self.check(&["merge-base", "--is-ancestor", "HEAD", "FETCH_HEAD"])? {
+            self.run(&["merge", "--quiet", "--ff-only", "FETCH_HEAD"])?;
+            return Ok(Pull::FastForward);
+        }
+
+        let merge_base = self.command(&["merge-base", "HEAD", "FETCH_HEAD"])?;
+        let base = if merge_base.status.success() {
+            let rev = String::from_utf8_lossy(&merge_base.stdout);
+            Some(self.show_storage(rev.trim(), file)?)
+        } else {
+            None
+        };
+        let ours = self.show_storage("HEAD", file)?;
+        let theirs = self.show_storage("FETCH_HEAD", file)?;
+        // Conflicting changes keep whichever side changed last.
+        let mut merged = merge::merge(base.as_ref(), &ours, &theirs);
+        let summary = merged.summary(&ours);
+
+        // The data file usually conflicts; it is replaced by the merged tasks
+        // below. Any other conflict is left for the user.
+        let mut args = vec!["merge", "--quiet", "--no-ff", "--no-commit"];
+        if base.is_none() {
+            args.push("--allow-unrelated-histories");
+        }
+        args.push("FETCH_HEAD");
+        let output = self.command(&args)?;
+        if !self.check(&["rev-parse", "--quiet", "--verify", "MERGE_HEAD"])? {
+            return Err(TodoError::Sync(format!(
+                "git merge failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        merged.storage.save_to_file(repo_file)?;
+        self.run(&["add", "--", file])?;
+        let conflicts = self.run(&["diff", "--name-only", "--diff-filter=U"])?;
+        if !conflicts.is_empty() {
+            self.run(&["merge", "--abort"])?;
+            return Err(TodoError::Sync(format!(
+                "other files in the repository conflict: {}",
+                conflicts.lines().collect::<Vec<_>>().join(", ")
+            )));
+        }
+        let message = format!("Merge tasks from {}/{}", remote, branch);
+        self.run(&["commit", "--quiet", "-m", &message])?;
+        Ok(Pull::Merged(summary))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Task;
+    use crate::test_util::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        Git { repo: dir }.run(args).unwrap();
+    }
+
+    fn clone(remote: &Path, dir: &Path) -> SyncConfig {
+        std::fs::create_dir_all(dir).unwrap();
+        git(dir, &["init", "--quiet", "--initial-branch", "main"]);
+        git(dir, &["config", "user.name", "Todo Test"]);
+        git(dir, &["config", "user.email", "todo@example.com"]);
+        git(dir, &["remote", "add", "origin", &remote.to_string_lossy()]);
+        SyncConfig {
+            repo: Some(dir.to_path_buf()),
+            ..SyncConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_sync_with_bare_remote() {
+        let dir = TempDir::new("sync");
+        let remote = dir.join("remote.git");
+        git(dir.path(), &["init", "--quiet", "--bare", "remote.git"]);
+        let laptop = clone(&remote, &dir.join("laptop"));
+        let desktop = clone(&remote, &dir.join("desktop"));
+
+        let mut tasks = TaskStorage::default();
+        tasks.add_task(Task::new(0, "Shared".to_string()));
+        let (laptop_tasks, report) = sync(&tasks, &laptop, true).unwrap();
+        assert!(report.committed);
+        assert_eq!(report.pull, Some(Pull::Skipped));
+        assert_eq!(report.remote.as_deref(), Some("origin/main"));
+
+        let (mut desktop_tasks, report) = sync(&TaskStorage::default(), &desktop, true).unwrap();
+        assert_eq!(
+            report.pull,
+            Some(Pull::Merged(MergeSummary {
+                added: 1,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(desktop_tasks.tasks.len(), 1);
+
+        // Both sides change the same task and add one with the same ID.
+        let mut laptop_tasks = laptop_tasks;
+        laptop_tasks.tasks[0].priority = Some(1);
+        laptop_tasks.add_task(Task::new(0, "From laptop".to_string()));
+        laptop_tasks.save_to_file(&dir.join("laptop.json")).unwrap();
+        sync(&laptop_tasks, &laptop, true).unwrap();
+
+        desktop_tasks.tasks[0].tags = vec!["home".to_string()];
+        desktop_tasks.add_task(Task::new(0, "From desktop".to_string()));
+        desktop_tasks
+            .save_to_file(&dir.join("desktop.json"))
+            .unwrap();
+        let (merged, report) = sync(&desktop_tasks, &desktop, true).unwrap();
+        let Some(Pull::Merged(summary)) = report.pull else {
+            panic!("expected a merge, got {:?}", report.pull);
+        };
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.renumbered, [(2, 3)]);
+        assert_eq!(merged.tasks[0].priority, Some(1));
+        assert_eq!(merged.tasks[0].tags, ["home"]);
+        assert_eq!(merged.tasks.len(), 3);
+
+        let (laptop_tasks, report) = sync(&laptop_tasks, &laptop, true).unwrap();
+        assert_eq!(report.pull, Some(Pull::FastForward));
+        assert_eq!(laptop_tasks.tasks.len(), 3);
+    }
+}
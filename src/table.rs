@@ -0,0 +1,375 @@
+use crate::cli::TableColumn;
+use crate::renderer::{format_relative_date, style_due, style_priority};
+use crate::task::Task;
+use crate::theme::theme;
+use chrono::{DateTime, Local};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns shown when `--columns` is not given.
+pub const DEFAULT_COLUMNS: [TableColumn; 6] = [
+    TableColumn::Id,
+    TableColumn::Priority,
+    TableColumn::Status,
+    TableColumn::Description,
+    TableColumn::Tags,
+    TableColumn::Due,
+];
+
+/// Width used when the terminal size cannot be detected.
+const FALLBACK_WIDTH: usize = 80;
+/// Narrowest the description column is squeezed to before other columns shrink.
+const MIN_DESCRIPTION_WIDTH: usize = 12;
+/// Spaces between two columns.
+const COLUMN_GAP: usize = 2;
+
+/// Detect the terminal width, falling back to `$COLUMNS` and then 80.
+pub fn terminal_width() -> usize {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return width as usize;
+    }
+
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&width| width > 0)
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+/// Render tasks as an aligned table that fits in `width` columns.
+pub fn render_task_table(tasks: &[&Task], columns: &[TableColumn], width: usize) {
+    for line in format_task_table(tasks, columns, width) {
+        println!("{}", line);
+    }
+}
+
+/// Build the lines of a task table without printing them.
+///
+/// Descriptions wrap onto continuation lines; every other column is
+/// truncated with an ellipsis when it doesn't fit. Widths are measured in
+/// terminal cells so emoji and CJK text stay aligned.
+pub fn format_task_table(tasks: &[&Task], columns: &[TableColumn], width: usize) -> Vec<String> {
+    let rows: Vec<Vec<String>> = tasks
+        .iter()
+        .map(|task| columns.iter().map(|&c| cell_text(task, c)).collect())
+        .collect();
+
+    let widths = column_widths(columns, &rows, width);
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+
+    let header: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, &w)| pad(&truncate(column_title(*column), w), w))
+        .collect();
+    lines.push(
+        join_cells(
+            header
+                .iter()
+                .map(|cell| theme().header.apply(cell).to_string()),
+        )
+        .trim_end()
+        .to_string(),
+    );
+
+    for (task, row) in tasks.iter().zip(&rows) {
+        let cells: Vec<Vec<String>> = columns
+            .iter()
+            .zip(row)
+            .zip(&widths)
+            .map(|((column, text), &w)| match column {
+                TableColumn::Description => wrap(text, w),
+                _ => vec![truncate(text, w)],
+            })
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+        for line_index in 0..height {
+            let line = join_cells(columns.iter().zip(&cells).zip(&widths).map(
+                |((column, parts), &w)| {
+                    let part = parts.get(line_index).map(String::as_str).unwrap_or("");
+                    style_cell(task, *column, &pad(part, w), part.is_empty())
+                },
+            ));
+            lines.push(line.trim_end().to_string());
+        }
+    }
+
+    lines
+}
+
+/// Plain (uncolored) text for a single cell.
+fn cell_text(task: &Task, column: TableColumn) -> String {
+    match column {
+        TableColumn::Id => task.id.to_string(),
+        TableColumn::Priority => task.priority.map(|p| format!("P{}", p)).unwrap_or_default(),
+        TableColumn::Status => task_status(task).to_string(),
+        TableColumn::Description => task.description.clone(),
+        TableColumn::Tags => task
+            .tags
+            .iter()
+            .map(|tag| format!("#{}", tag))
+            .collect::<Vec<_>>()
+            .join(" "),
+        TableColumn::Due => task.due_date.map(format_relative_date).unwrap_or_default(),
+        TableColumn::Age => format_age(task.created_at),
+    }
+}
+
+fn style_cell(task: &Task, column: TableColumn, text: &str, is_blank: bool) -> String {
+    if is_blank {
+        return text.to_string();
+    }
+
+    let theme = theme();
+    match column {
+        TableColumn::Id => theme.id.apply(text).to_string(),
+        TableColumn::Priority => match task.priority {
+            Some(priority) => style_priority(text, priority).to_string(),
+            None => text.to_string(),
+        },
+        TableColumn::Status => match task_status(task) {
+            "done" => theme.done.apply(text).to_string(),
+            "overdue" => theme.overdue.apply(text).to_string(),
+            _ => text.to_string(),
+        },
+        TableColumn::Description if task.completed => theme.completed.apply(text).to_string(),
+        TableColumn::Description if task.is_overdue() => theme.overdue.apply(text).to_string(),
+        TableColumn::Description => text.to_string(),
+        TableColumn::Tags => theme.tag.apply(text).to_string(),
+        TableColumn::Due => match task.due_date {
+            Some(due_date) => style_due(text, due_date, task.is_overdue()).to_string(),
+            None => text.to_string(),
+        },
+        TableColumn::Age => theme.muted.apply(text).to_string(),
+    }
+}
+
+/// Short status label: "done", "overdue", or the state of an open task
+/// ("pending", "started" or "waiting").
+pub fn task_status(task: &Task) -> &'static str {
+    if task.completed {
+        "done"
+    } else if task.is_overdue() {
+        "overdue"
+    } else {
+        task.state.as_str()
+    }
+}
+
+fn column_title(column: TableColumn) -> &'static str {
+    match column {
+        TableColumn::Id => "ID",
+        TableColumn::Priority => "PRI",
+        TableColumn::Status => "STATUS",
+        TableColumn::Description => "DESCRIPTION",
+        TableColumn::Tags => "TAGS",
+        TableColumn::Due => "DUE",
+        TableColumn::Age => "AGE",
+    }
+}
+
+/// Compute the display width of every column so the table fits `total_width`.
+///
+/// Fixed columns take the width of their widest cell. The description column
+/// gets whatever is left; if that is too little, the widest other columns
+/// are shrunk first.
+fn column_widths(columns: &[TableColumn], rows: &[Vec<String>], total_width: usize) -> Vec<usize> {
+    let mut widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].width())
+                .chain(std::iter::once(column_title(*column).width()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let gaps = COLUMN_GAP * columns.len().saturating_sub(1);
+    let description = columns.iter().position(|c| *c == TableColumn::Description);
+    let reserved = description.map_or(0, |i| widths[i].min(MIN_DESCRIPTION_WIDTH));
+
+    let fixed_width = |widths: &[usize]| -> usize {
+        widths
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != description)
+            .map(|(_, w)| w)
+            .sum()
+    };
+
+    // Shrink the widest non-description column until everything fits.
+    while fixed_width(&widths) + gaps + reserved > total_width {
+        let widest = widths
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != description)
+            .max_by_key(|(_, w)| **w)
+            .map(|(i, _)| i);
+        match widest {
+            Some(i) if widths[i] > 3 => widths[i] -= 1,
+            _ => break,
+        }
+    }
+
+    if let Some(i) = description {
+        let available = total_width.saturating_sub(fixed_width(&widths) + gaps);
+        widths[i] = widths[i].min(available).max(reserved.max(1));
+    }
+
+    widths
+}
+
+fn join_cells(cells: impl Iterator<Item = String>) -> String {
+    cells.collect::<Vec<_>>().join(&" ".repeat(COLUMN_GAP))
+}
+
+/// Pad `text` with spaces to exactly `width` terminal cells.
+pub fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Cut `text` to at most `width` terminal cells, ending with `…` when cut.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width - 1 {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result.push('…');
+    result
+}
+
+/// Word-wrap `text` into lines of at most `width` terminal cells.
+///
+/// Words wider than a full line are split at character boundaries.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let separator = usize::from(!current.is_empty());
+        if current.width() + separator + word.width() <= width {
+            if separator == 1 {
+                current.push(' ');
+            }
+            current.push_str(word);
+            continue;
+        }
+
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+
+        for c in word.chars() {
+            if current.width() + c.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+    }
+
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Compact age of a timestamp: "3h", "5d", "2w", "4mo", "1y".
+pub fn format_age(created_at: DateTime<Local>) -> String {
+    let age = Local::now() - created_at;
+
+    if age.num_days() >= 365 {
+        format!("{}y", age.num_days() / 365)
+    } else if age.num_days() >= 30 {
+        format!("{}mo", age.num_days() / 30)
+    } else if age.num_days() >= 14 {
+        format!("{}w", age.num_days() / 7)
+    } else if age.num_days() >= 1 {
+        format!("{}d", age.num_days())
+    } else if age.num_hours() >= 1 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes().max(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::TaskState;
+    use chrono::Duration;
+
+    #[test]
+    fn test_truncate_respects_display_width() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello world", 6), "hello…");
+        // Each CJK character takes two cells.
+        assert_eq!(truncate("日本語テキスト", 7), "日本語…");
+        assert_eq!(truncate("日本語テキスト", 7).width(), 7);
+    }
+
+    #[test]
+    fn test_wrap_breaks_on_words_and_long_words() {
+        assert_eq!(
+            wrap("fix the login bug", 8),
+            vec!["fix the", "login", "bug"]
+        );
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("🎉🎉🎉", 4), vec!["🎉🎉", "🎉"]);
+        assert_eq!(wrap("", 4), vec![""]);
+    }
+
+    #[test]
+    fn test_task_status_reports_state() {
+        let mut task = Task::new(1, "Review".to_string());
+        assert_eq!(task_status(&task), "pending");
+        task.state = TaskState::Started;
+        assert_eq!(task_status(&task), "started");
+        task.state = TaskState::Waiting;
+        assert_eq!(task_status(&task), "waiting");
+        task.due_date = Some(Local::now() - Duration::days(1));
+        assert_eq!(task_status(&task), "overdue");
+        task.completed = true;
+        assert_eq!(task_status(&task), "done");
+    }
+
+    #[test]
+    fn test_table_fits_width_and_aligns() {
+        colored::control::set_override(false);
+
+        let mut first = Task::new(1, "Short".to_string());
+        first.tags = vec!["work".to_string()];
+        let second = Task::new(
+            12,
+            "A much longer description that will need to wrap onto several lines".to_string(),
+        );
+        let third = Task::new(3, "日本語のタスク説明".to_string());
+
+        let columns = [TableColumn::Id, TableColumn::Description, TableColumn::Tags];
+        let lines = format_task_table(&[&first, &second, &third], &columns, 30);
+
+        assert_eq!(lines[0], "ID  DESCRIPTION          TAGS");
+        assert_eq!(lines[1], "1   Short                #work");
+        assert!(lines.iter().all(|line| line.width() <= 30));
+        assert!(lines[2].starts_with("12  A much longer"));
+        assert!(lines[3].starts_with("    "));
+        let cjk = lines.iter().find(|line| line.starts_with("3 ")).unwrap();
+        assert_eq!(cjk.width(), "3   日本語のタスク説明".width());
+    }
+}
@@ -1,11 +1,67 @@
+use crate::error::{Result, TodoError};
 use chrono::{DateTime, Local};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use uuid::Uuid;
+
+/// Progress of a task that is not completed yet.
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    /// Not started.
+    #[default]
+    Pending,
+    /// Being worked on.
+    Started,
+    /// Blocked on someone or something else.
+    Waiting,
+}
+
+impl TaskState {
+    /// Lowercase name, as used on the command line and in JSON.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskState::Pending => "pending",
+            TaskState::Started => "started",
+            TaskState::Waiting => "waiting",
+        }
+    }
+}
+
+impl fmt::Display for TaskState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Fields whose last change is tracked in `Task::modified`, so copies of the
+/// same task edited on different machines can be merged field by field.
+///
+/// `completed` covers the completion time and note as well.
+pub const TRACKED_FIELDS: [&str; 9] = [
+    "description",
+    "priority",
+    "due_date",
+    "tags",
+    "parent",
+    "completed",
+    "state",
+    "notes",
+    "properties",
+];
 
 /// Represents a single task in the todo application.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
-    /// Unique task ID.
+    /// Short task ID used on the command line and in listings.
     pub id: u64,
+    /// Stable identity that stays the same across machines and renumbering.
+    ///
+    /// Nil only while loading a file written before tasks had UUIDs.
+    #[serde(default)]
+    pub uuid: Uuid,
     /// Task description.
     pub description: String,
     /// Priority (1-5, 1 = highest).
@@ -14,12 +70,35 @@ pub struct Task {
     pub due_date: Option<DateTime<Local>>,
     /// Tags associated with the task.
     pub tags: Vec<String>,
+    /// UUID of the task this one is a subtask of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Uuid>,
+    /// ID of the parent in files written before tasks had UUIDs; replaced by
+    /// `parent` while loading.
+    #[serde(default, skip_serializing)]
+    pub parent_id: Option<u64>,
     /// Whether the task is completed.
     pub completed: bool,
+    /// Progress while the task is open; ignored once it is completed.
+    #[serde(default)]
+    pub state: TaskState,
     /// Creation timestamp.
     pub created_at: DateTime<Local>,
     /// Completion timestamp, if completed.
     pub completed_at: Option<DateTime<Local>>,
+    /// Note left when the task was completed, e.g. how it was resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_note: Option<String>,
+    /// Free-form notes, usually written with `todo edit --interactive`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Extra key/value properties carried over from imported files.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+    /// When each of the `TRACKED_FIELDS` last changed. Fields missing here
+    /// haven't changed since the task was created.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modified: BTreeMap<String, DateTime<Local>>,
 }
 
 impl Task {
@@ -27,13 +106,21 @@ impl Task {
     pub fn new(id: u64, description: String) -> Self {
         Self {
             id,
+            uuid: Uuid::new_v4(),
             description,
             priority: None,
             due_date: None,
             tags: Vec::new(),
+            parent: None,
+            parent_id: None,
             completed: false,
+            state: TaskState::default(),
             created_at: Local::now(),
             completed_at: None,
+            completion_note: None,
+            notes: None,
+            properties: BTreeMap::new(),
+            modified: BTreeMap::new(),
         }
     }
 
@@ -52,18 +139,140 @@ impl Task {
         self.completed_at = Some(Local::now());
     }
 
-    /// Returns true if the task matches the tag filters.
-    pub fn matches_tag_filter(&self, include_tag: Option<&str>, exclude_tag: Option<&str>) -> bool {
-        if let Some(tag) = include_tag {
-            if !self.tags.iter().any(|t| t == tag) {
-                return false;
+    /// Mark a completed task as open again, dropping its completion time and note.
+    pub fn reopen(&mut self) {
+        self.completed = false;
+        self.completed_at = None;
+        self.completion_note = None;
+    }
+
+    /// When `field` (one of `TRACKED_FIELDS`) was last changed.
+    pub fn modified_at(&self, field: &str) -> DateTime<Local> {
+        self.modified.get(field).copied().unwrap_or(self.created_at)
+    }
+
+    /// When any tracked field was last changed.
+    pub fn last_modified(&self) -> DateTime<Local> {
+        self.modified
+            .values()
+            .copied()
+            .fold(self.created_at, DateTime::max)
+    }
+
+    /// Tracked fields whose values differ between the two tasks.
+    pub fn changed_fields(&self, other: &Task) -> Vec<&'static str> {
+        TRACKED_FIELDS
+            .into_iter()
+            .filter(|&field| !self.same_field(other, field))
+            .collect()
+    }
+
+    /// Record `now` as the modification time of every field that differs
+    /// from `old`.
+    pub fn stamp_changes(&mut self, old: &Task, now: DateTime<Local>) {
+        for field in self.changed_fields(old) {
+            self.modified.insert(field.to_string(), now);
+        }
+    }
+
+    /// Take the value of a tracked field, and its modification time, from `other`.
+    pub fn copy_field(&mut self, other: &Task, field: &str) {
+        match field {
+            "description" => self.description = other.description.clone(),
+            "priority" => self.priority = other.priority,
+            "due_date" => self.due_date = other.due_date,
+            "tags" => self.tags = other.tags.clone(),
+            "parent" => self.parent = other.parent,
+            "completed" => {
+                self.completed = other.completed;
+                self.completed_at = other.completed_at;
+                self.completion_note = other.completion_note.clone();
             }
+            "state" => self.state = other.state,
+            "notes" => self.notes = other.notes.clone(),
+            "properties" => self.properties = other.properties.clone(),
+            _ => return,
         }
+        match other.modified.get(field) {
+            Some(&at) => self.modified.insert(field.to_string(), at),
+            None => self.modified.remove(field),
+        };
+    }
+
+    /// The value of a tracked field as plain text, e.g. to show two versions
+    /// of a task side by side.
+    pub fn field_value(&self, field: &str) -> String {
+        fn or_none(value: String) -> String {
+            if value.is_empty() {
+                "none".to_string()
+            } else {
+                value
+            }
+        }
+
+        match field {
+            "description" => self.description.clone(),
+            "priority" => or_none(self.priority.map(|p| p.to_string()).unwrap_or_default()),
+            "due_date" => or_none(
+                self.due_date
+                    .map(|due| due.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+            ),
+            "tags" => or_none(self.tags.join(", ")),
+            "parent" => or_none(self.parent.map(|uuid| uuid.to_string()).unwrap_or_default()),
+            "completed" => match (self.completed_at, &self.completion_note) {
+                _ if !self.completed => "open".to_string(),
+                (Some(at), Some(note)) => {
+                    format!("completed {} ({})", at.format("%Y-%m-%d %H:%M"), note)
+                }
+                (Some(at), None) => format!("completed {}", at.format("%Y-%m-%d %H:%M")),
+                (None, _) => "completed".to_string(),
+            },
+            "state" => self.state.to_string(),
+            "notes" => or_none(self.notes.clone().unwrap_or_default()),
+            "properties" => or_none(
+                self.properties
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            _ => String::new(),
+        }
+    }
 
-        if let Some(tag) = exclude_tag {
-            if self.tags.iter().any(|t| t == tag) {
-                return false;
+    /// Whether a tracked field has the same value in both tasks.
+    pub fn same_field(&self, other: &Task, field: &str) -> bool {
+        match field {
+            "description" => self.description == other.description,
+            "priority" => self.priority == other.priority,
+            "due_date" => self.due_date == other.due_date,
+            "tags" => self.tags == other.tags,
+            "parent" => self.parent == other.parent,
+            "completed" => {
+                self.completed == other.completed
+                    && self.completed_at == other.completed_at
+                    && self.completion_note == other.completion_note
             }
+            "state" => self.state == other.state,
+            "notes" => self.notes == other.notes,
+            "properties" => self.properties == other.properties,
+            _ => true,
+        }
+    }
+
+    /// Returns true if the task matches the tag filters.
+    pub fn matches_tag_filter(&self, include_tag: Option<&str>, exclude_tag: Option<&str>) -> bool {
+        if let Some(tag) = include_tag
+            && !self.tags.iter().any(|t| t == tag)
+        {
+            return false;
+        }
+
+        if let Some(tag) = exclude_tag
+            && self.tags.iter().any(|t| t == tag)
+        {
+            return false;
         }
 
         true
@@ -121,3 +330,30 @@ impl Ord for Task {
         self.created_at.cmp(&other.created_at)
     }
 }
+
+/// Check that a priority is between 1 and 5.
+pub(crate) fn valid_priority(priority: Option<u8>) -> Result<Option<u8>> {
+    match priority {
+        Some(p) if !(1..=5).contains(&p) => Err(TodoError::InvalidPriority(p)),
+        _ => Ok(priority),
+    }
+}
+
+/// Trim tags and drop duplicates, rejecting blank ones.
+pub(crate) fn valid_tags<I, S>(tags: I) -> Result<Vec<String>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut valid: Vec<String> = Vec::new();
+    for tag in tags {
+        let trimmed = tag.as_ref().trim();
+        if trimmed.is_empty() {
+            return Err(TodoError::InvalidTag(tag.as_ref().to_string()));
+        }
+        if !valid.iter().any(|t| t == trimmed) {
+            valid.push(trimmed.to_string());
+        }
+    }
+    Ok(valid)
+}
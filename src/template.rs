@@ -0,0 +1,406 @@
+use crate::error::{Result, TodoError};
+use crate::renderer::{format_relative_date, style_due, style_priority};
+use crate::table::{format_age, task_status, truncate};
+use crate::task::Task;
+use crate::theme::{Style, theme};
+use chrono::{DateTime, Local, SecondsFormat};
+use colored::ColoredString;
+
+/// A compiled output template such as `{id} {priority:P} {description} {due:relative}`.
+///
+/// Syntax:
+/// - `{field}` or `{field:modifier}` inserts a task field.
+/// - `{?field}...{/field}` renders its body only when the field is set;
+///   `{!field}...{/field}` only when it is not.
+/// - `{@style}` colors the following text with a style (`{@bold red}`,
+///   `{@#ff8800}`) or a task-dependent color (`{@priority}`, `{@due}`,
+///   `{@status}`); `{@reset}` goes back to plain text.
+/// - `{{` and `}}` insert literal braces.
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Field(Field, Modifier),
+    Section {
+        field: Field,
+        negated: bool,
+        body: Vec<Node>,
+    },
+    Color(Color),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Description,
+    Priority,
+    Due,
+    Tags,
+    Status,
+    Overdue,
+    Created,
+    Completed,
+    Age,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Modifier {
+    None,
+    PriorityPrefix,
+    Relative,
+    Date,
+    Iso,
+    Strftime(String),
+    Upper,
+    Lower,
+    Width(usize),
+    Plain,
+    Csv,
+}
+
+#[derive(Debug, Clone)]
+enum Color {
+    Reset,
+    Fixed(Style),
+    Priority,
+    Due,
+    Status,
+}
+
+impl Template {
+    /// Compile a template string.
+    ///
+    /// # Errors
+    /// Returns a `TodoError::Template` for unknown fields or modifiers,
+    /// unbalanced sections and unterminated `{`.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut stack: Vec<(Field, bool, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find(['{', '}']) {
+            text.push_str(&rest[..start]);
+            let brace = &rest[start..];
+
+            if let Some(after) = brace.strip_prefix("{{") {
+                text.push('{');
+                rest = after;
+                continue;
+            }
+            if let Some(after) = brace.strip_prefix("}}") {
+                text.push('}');
+                rest = after;
+                continue;
+            }
+            if brace.starts_with('}') {
+                return Err(template_error(
+                    "unmatched '}' (use '}}' for a literal brace)",
+                ));
+            }
+
+            let end = brace
+                .find('}')
+                .ok_or_else(|| template_error("unterminated '{'"))?;
+            let tag = &brace[1..end];
+            rest = &brace[end + 1..];
+
+            let current = stack.last_mut().map_or(&mut nodes, |(_, _, body)| body);
+            if !text.is_empty() {
+                current.push(Node::Text(std::mem::take(&mut text)));
+            }
+
+            if let Some(name) = tag.strip_prefix('?').or_else(|| tag.strip_prefix('!')) {
+                stack.push((parse_field(name)?, tag.starts_with('!'), Vec::new()));
+            } else if let Some(name) = tag.strip_prefix('/') {
+                let (field, negated, body) = stack.pop().ok_or_else(|| {
+                    template_error(&format!("'{{/{}}}' without an open section", name))
+                })?;
+                if parse_field(name)? != field {
+                    return Err(template_error(&format!(
+                        "'{{/{}}}' closes a section for a different field",
+                        name
+                    )));
+                }
+                let parent = stack.last_mut().map_or(&mut nodes, |(_, _, body)| body);
+                parent.push(Node::Section {
+                    field,
+                    negated,
+                    body,
+                });
+            } else if let Some(spec) = tag.strip_prefix('@') {
+                current.push(Node::Color(parse_color(spec)?));
+            } else {
+                let (name, modifier) = tag.split_once(':').unwrap_or((tag, ""));
+                let field = parse_field(name)?;
+                current.push(Node::Field(field, parse_modifier(field, modifier)?));
+            }
+        }
+
+        text.push_str(rest);
+        if let Some((field, _, _)) = stack.last() {
+            return Err(template_error(&format!(
+                "section '{}' is never closed",
+                field_name(*field)
+            )));
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Render the template for a single task.
+    pub fn render(&self, task: &Task) -> String {
+        let mut output = String::new();
+        let mut color = Color::Reset;
+        render_nodes(&self.nodes, task, &mut color, &mut output);
+        output
+    }
+}
+
+fn render_nodes(nodes: &[Node], task: &Task, color: &mut Color, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(&paint(text, task, color)),
+            Node::Field(field, modifier) => {
+                let value = field_value(task, *field, modifier);
+                output.push_str(&paint(&value, task, color));
+            }
+            Node::Section {
+                field,
+                negated,
+                body,
+            } => {
+                if is_set(task, *field) != *negated {
+                    render_nodes(body, task, color, output);
+                }
+            }
+            Node::Color(new_color) => *color = new_color.clone(),
+        }
+    }
+}
+
+fn paint(text: &str, task: &Task, color: &Color) -> String {
+    let painted: ColoredString = match color {
+        Color::Reset => return text.to_string(),
+        Color::Fixed(style) => style.apply(text),
+        Color::Priority => match task.priority {
+            Some(priority) => style_priority(text, priority),
+            None => return text.to_string(),
+        },
+        Color::Due => match task.due_date {
+            Some(due_date) => style_due(text, due_date, task.is_overdue()),
+            None => return text.to_string(),
+        },
+        Color::Status => match task_status(task) {
+            "done" => theme().done.apply(text),
+            "overdue" => theme().overdue.apply(text),
+            _ => return text.to_string(),
+        },
+    };
+    painted.to_string()
+}
+
+fn is_set(task: &Task, field: Field) -> bool {
+    match field {
+        Field::Id | Field::Status | Field::Created | Field::Age => true,
+        Field::Description => !task.description.is_empty(),
+        Field::Priority => task.priority.is_some(),
+        Field::Due => task.due_date.is_some(),
+        Field::Tags => !task.tags.is_empty(),
+        Field::Overdue => task.is_overdue(),
+        Field::Completed => task.completed,
+    }
+}
+
+fn field_value(task: &Task, field: Field, modifier: &Modifier) -> String {
+    match field {
+        Field::Id => task.id.to_string(),
+        Field::Description => match modifier {
+            Modifier::Upper => task.description.to_uppercase(),
+            Modifier::Lower => task.description.to_lowercase(),
+            Modifier::Width(width) => truncate(&task.description, *width),
+            _ => task.description.clone(),
+        },
+        Field::Priority => match (task.priority, modifier) {
+            (Some(priority), Modifier::PriorityPrefix) => format!("P{}", priority),
+            (Some(priority), _) => priority.to_string(),
+            (None, _) => String::new(),
+        },
+        Field::Due => task
+            .due_date
+            .map(|date| format_date(date, modifier))
+            .unwrap_or_default(),
+        Field::Tags => match modifier {
+            Modifier::Plain => task.tags.join(" "),
+            Modifier::Csv => task.tags.join(","),
+            _ => task
+                .tags
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect::<Vec<_>>()
+                .join(" "),
+        },
+        Field::Status => task_status(task).to_string(),
+        Field::Overdue => if task.is_overdue() { "overdue" } else { "" }.to_string(),
+        Field::Created => format_date(task.created_at, modifier),
+        Field::Completed => task
+            .completed_at
+            .map(|date| format_date(date, modifier))
+            .unwrap_or_default(),
+        Field::Age => format_age(task.created_at),
+    }
+}
+
+fn format_date(date: DateTime<Local>, modifier: &Modifier) -> String {
+    match modifier {
+        Modifier::Relative => format_relative_date(date),
+        Modifier::Iso => date.to_rfc3339_opts(SecondsFormat::Secs, false),
+        Modifier::Strftime(format) => date.format(format).to_string(),
+        _ => date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+fn parse_field(name: &str) -> Result<Field> {
+    let field = match name.trim() {
+        "id" => Field::Id,
+        "description" | "desc" => Field::Description,
+        "priority" => Field::Priority,
+        "due" => Field::Due,
+        "tags" => Field::Tags,
+        "status" => Field::Status,
+        "overdue" => Field::Overdue,
+        "created" => Field::Created,
+        "completed" => Field::Completed,
+        "age" => Field::Age,
+        other => return Err(template_error(&format!("unknown field '{}'", other))),
+    };
+    Ok(field)
+}
+
+fn field_name(field: Field) -> &'static str {
+    match field {
+        Field::Id => "id",
+        Field::Description => "description",
+        Field::Priority => "priority",
+        Field::Due => "due",
+        Field::Tags => "tags",
+        Field::Status => "status",
+        Field::Overdue => "overdue",
+        Field::Created => "created",
+        Field::Completed => "completed",
+        Field::Age => "age",
+    }
+}
+
+fn parse_modifier(field: Field, modifier: &str) -> Result<Modifier> {
+    if modifier.is_empty() {
+        return Ok(Modifier::None);
+    }
+
+    let parsed = match field {
+        Field::Priority if modifier == "P" => Some(Modifier::PriorityPrefix),
+        Field::Due | Field::Created | Field::Completed => match modifier {
+            "relative" => Some(Modifier::Relative),
+            "date" => Some(Modifier::Date),
+            "iso" => Some(Modifier::Iso),
+            _ if modifier.contains('%') => Some(Modifier::Strftime(modifier.to_string())),
+            _ => None,
+        },
+        Field::Description => match modifier {
+            "upper" => Some(Modifier::Upper),
+            "lower" => Some(Modifier::Lower),
+            _ => modifier.parse().ok().map(Modifier::Width),
+        },
+        Field::Tags => match modifier {
+            "plain" => Some(Modifier::Plain),
+            "csv" => Some(Modifier::Csv),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    parsed.ok_or_else(|| {
+        template_error(&format!(
+            "unknown modifier '{}' for field '{}'",
+            modifier,
+            field_name(field)
+        ))
+    })
+}
+
+fn parse_color(spec: &str) -> Result<Color> {
+    let color = match spec.trim() {
+        "" | "reset" => Color::Reset,
+        "priority" => Color::Priority,
+        "due" => Color::Due,
+        "status" => Color::Status,
+        other => Color::Fixed(other.parse().map_err(|e: String| template_error(&e))?),
+    };
+    Ok(color)
+}
+
+fn template_error(message: &str) -> TodoError {
+    TodoError::Template(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_task() -> Task {
+        let mut task = Task::new(7, "Write report".to_string());
+        task.priority = Some(2);
+        task.tags = vec!["work".to_string(), "q3".to_string()];
+        task
+    }
+
+    #[test]
+    fn test_render_fields_and_modifiers() {
+        colored::control::set_override(false);
+        let task = sample_task();
+
+        let template = Template::parse("{id} {priority:P} {description:upper} {tags:csv}").unwrap();
+        assert_eq!(template.render(&task), "7 P2 WRITE REPORT work,q3");
+
+        let template = Template::parse("{description:8}|{tags}").unwrap();
+        assert_eq!(template.render(&task), "Write r…|#work #q3");
+    }
+
+    #[test]
+    fn test_conditional_sections() {
+        colored::control::set_override(false);
+        let mut task = sample_task();
+        let template =
+            Template::parse("{description}{?due} (due {due:relative}){/due}{!due} [no date]{/due}")
+                .unwrap();
+        assert_eq!(template.render(&task), "Write report [no date]");
+
+        task.due_date = Some(Local::now());
+        assert_eq!(template.render(&task), "Write report (due today)");
+    }
+
+    #[test]
+    fn test_escapes_and_colors() {
+        colored::control::set_override(false);
+        let task = sample_task();
+        let template = Template::parse("{{{id}}} {@bold red}{description}{@reset}").unwrap();
+        assert_eq!(template.render(&task), "{7} Write report");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{nope}").is_err());
+        assert!(Template::parse("{priority:relative}").is_err());
+        assert!(Template::parse("{?due}unclosed").is_err());
+        assert!(Template::parse("{?due}x{/tags}").is_err());
+        assert!(Template::parse("{id").is_err());
+        assert!(Template::parse("{@sparkly}").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Directories created so far, so that tests with the same name get their own.
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+/// An empty directory under the system temp directory, removed with
+/// everything in it when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create a directory named after the test using it.
+    pub(crate) fn new(name: &str) -> Self {
+        let count = CREATED.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("todo-{}-{}-{}", name, std::process::id(), count));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    /// The directory itself.
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// A file in the directory.
+    pub(crate) fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
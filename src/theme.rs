@@ -0,0 +1,396 @@
+use crate::cli::ColorChoice;
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Text style parsed from strings like `"bold red"`, `"#ff8800"` or `"dimmed 244"`.
+///
+/// A style is a whitespace-separated list of modifiers (`bold`, `dimmed`,
+/// `italic`, `underline`, `strikethrough`, `reversed`) and at most one color:
+/// a name (`red`, `bright_cyan`), a 256-color index (`0`-`255`) or a
+/// truecolor hex value (`#rrggbb`). An empty string means "no styling".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    reversed: bool,
+}
+
+impl Style {
+    /// Apply this style to a piece of text.
+    pub fn apply(&self, text: &str) -> ColoredString {
+        let mut styled = text.normal();
+        if let Some(color) = self.color {
+            styled = styled.color(color);
+        }
+        if self.bold {
+            styled = styled.bold();
+        }
+        if self.dimmed {
+            styled = styled.dimmed();
+        }
+        if self.italic {
+            styled = styled.italic();
+        }
+        if self.underline {
+            styled = styled.underline();
+        }
+        if self.strikethrough {
+            styled = styled.strikethrough();
+        }
+        if self.reversed {
+            styled = styled.reversed();
+        }
+        styled
+    }
+}
+
+impl TryFrom<String> for Style {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::default();
+
+        for token in spec.split_whitespace() {
+            let token = token.to_lowercase();
+            match token.as_str() {
+                "bold" => style.bold = true,
+                "dim" | "dimmed" => style.dimmed = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "strikethrough" => style.strikethrough = true,
+                "reverse" | "reversed" => style.reversed = true,
+                "normal" | "none" => {}
+                _ => {
+                    let color = parse_color(&token)
+                        .ok_or_else(|| format!("unknown color or modifier '{}'", token))?;
+                    if style.color.replace(color).is_some() {
+                        return Err(format!("style '{}' has more than one color", spec));
+                    }
+                }
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+/// Parse a color name, 256-color index or `#rrggbb` hex value.
+fn parse_color(token: &str) -> Option<Color> {
+    if let Some(hex) = token.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::TrueColor {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+
+    if let Ok(index) = token.parse::<u8>() {
+        return Some(ansi256_to_color(index));
+    }
+
+    let color = match token.replace('-', "_").as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" | "purple" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "bright_black" | "gray" | "grey" => Color::BrightBlack,
+        "bright_red" => Color::BrightRed,
+        "bright_green" => Color::BrightGreen,
+        "bright_yellow" => Color::BrightYellow,
+        "bright_blue" => Color::BrightBlue,
+        "bright_magenta" | "bright_purple" => Color::BrightMagenta,
+        "bright_cyan" => Color::BrightCyan,
+        "bright_white" => Color::BrightWhite,
+        _ => return None,
+    };
+    Some(color)
+}
+
+/// Map an xterm 256-color palette index to a color.
+///
+/// The first 16 entries are the terminal's own ANSI colors; the rest are the
+/// standard 6x6x6 color cube and 24-step grayscale ramp.
+fn ansi256_to_color(index: u8) -> Color {
+    const ANSI: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+        Color::BrightBlack,
+        Color::BrightRed,
+        Color::BrightGreen,
+        Color::BrightYellow,
+        Color::BrightBlue,
+        Color::BrightMagenta,
+        Color::BrightCyan,
+        Color::BrightWhite,
+    ];
+
+    match index {
+        0..=15 => ANSI[index as usize],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let i = index - 16;
+            Color::TrueColor {
+                r: level(i / 36),
+                g: level((i / 6) % 6),
+                b: level(i % 6),
+            }
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Color::TrueColor {
+                r: gray,
+                g: gray,
+                b: gray,
+            }
+        }
+    }
+}
+
+/// Colors and markers used by every renderer.
+///
+/// Missing fields in a theme loaded from the config file fall back to the
+/// default theme.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Task IDs.
+    pub id: Style,
+    /// Priorities P1 to P5, in order.
+    pub priority: Vec<Style>,
+    /// Tags.
+    pub tag: Style,
+    /// Descriptions and due dates of overdue tasks.
+    pub overdue: Style,
+    /// Due dates falling today.
+    pub due_today: Style,
+    /// Due dates within the next three days.
+    pub due_soon: Style,
+    /// Descriptions of completed tasks.
+    pub completed: Style,
+    /// The "(completed ...)" suffix and "done" status.
+    pub done: Style,
+    /// Table headers.
+    pub header: Style,
+    /// Secondary text such as labels and ages.
+    pub muted: Style,
+    /// Success marker.
+    pub success: Style,
+    /// Error marker.
+    pub error: Style,
+    /// Error message text.
+    pub error_text: Style,
+    /// Warning marker.
+    pub warning: Style,
+    /// Warning message text.
+    pub warning_text: Style,
+    /// Info marker.
+    pub info: Style,
+    /// Use emoji markers; when false, ASCII replacements are used.
+    pub emoji: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let style = |spec: &str| spec.parse().expect("built-in style must parse");
+        Self {
+            id: style("bold cyan"),
+            priority: vec![
+                style("bold red"),
+                style("bold yellow"),
+                style("bold blue"),
+                style("bold green"),
+                style("bold cyan"),
+            ],
+            tag: style("bright_cyan"),
+            overdue: style("bold red"),
+            due_today: style("bold yellow"),
+            due_soon: style("yellow"),
+            completed: style("strikethrough dimmed"),
+            done: style("dimmed green"),
+            header: style("bold"),
+            muted: style("dimmed"),
+            success: style("bold green"),
+            error: style("bold red"),
+            error_text: style("red"),
+            warning: style("bold yellow"),
+            warning_text: style("yellow"),
+            info: style("bold blue"),
+            emoji: true,
+        }
+    }
+}
+
+impl Theme {
+    /// Built-in theme without any colors or emoji.
+    pub fn plain() -> Self {
+        let none = Style::default();
+        Self {
+            id: none.clone(),
+            priority: vec![none.clone(); 5],
+            tag: none.clone(),
+            overdue: none.clone(),
+            due_today: none.clone(),
+            due_soon: none.clone(),
+            completed: none.clone(),
+            done: none.clone(),
+            header: none.clone(),
+            muted: none.clone(),
+            success: none.clone(),
+            error: none.clone(),
+            error_text: none.clone(),
+            warning: none.clone(),
+            warning_text: none.clone(),
+            info: none,
+            emoji: false,
+        }
+    }
+
+    /// Look up a built-in theme by name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "plain" => Some(Self::plain()),
+            _ => None,
+        }
+    }
+
+    /// Style for a priority level, falling back to no styling.
+    pub fn priority_style(&self, priority: u8) -> Style {
+        usize::from(priority)
+            .checked_sub(1)
+            .and_then(|index| self.priority.get(index))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Marker printed before overdue task descriptions.
+    pub fn overdue_marker(&self) -> &'static str {
+        if self.emoji { "⚠️  " } else { "! " }
+    }
+
+    /// Marker printed before warnings.
+    pub fn warning_marker(&self) -> &'static str {
+        if self.emoji { "⚠" } else { "!" }
+    }
+}
+
+/// Decide whether to force colors on or off.
+///
+/// Returns `None` when the decision is left to terminal detection.
+/// `NO_COLOR` (any non-empty value) disables colors unless `--color always`
+/// is given.
+pub fn color_override(choice: ColorChoice, no_color: Option<&str>) -> Option<bool> {
+    match choice {
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+        ColorChoice::Auto if no_color.is_some_and(|value| !value.is_empty()) => Some(false),
+        ColorChoice::Auto => None,
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Install the theme used for the rest of the process.
+///
+/// Only the first call has an effect.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The active theme (the default theme if none was installed).
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        let style: Style = "bold bright_cyan".parse().unwrap();
+        assert!(style.bold);
+        assert_eq!(style.color, Some(Color::BrightCyan));
+
+        let style: Style = "#ff8800 underline".parse().unwrap();
+        assert!(style.underline);
+        assert_eq!(
+            style.color,
+            Some(Color::TrueColor {
+                r: 255,
+                g: 136,
+                b: 0
+            })
+        );
+
+        assert_eq!("".parse::<Style>().unwrap(), Style::default());
+        assert!("bold sparkly".parse::<Style>().is_err());
+        assert!("red blue".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn test_ansi256_palette() {
+        assert_eq!(ansi256_to_color(1), Color::Red);
+        assert_eq!(
+            ansi256_to_color(208),
+            Color::TrueColor {
+                r: 255,
+                g: 135,
+                b: 0
+            }
+        );
+        assert_eq!(
+            ansi256_to_color(244),
+            Color::TrueColor {
+                r: 128,
+                g: 128,
+                b: 128
+            }
+        );
+    }
+
+    #[test]
+    fn test_color_override() {
+        assert_eq!(color_override(ColorChoice::Auto, None), None);
+        assert_eq!(color_override(ColorChoice::Auto, Some("")), None);
+        assert_eq!(color_override(ColorChoice::Auto, Some("1")), Some(false));
+        assert_eq!(color_override(ColorChoice::Always, Some("1")), Some(true));
+        assert_eq!(color_override(ColorChoice::Never, None), Some(false));
+    }
+
+    #[test]
+    fn test_partial_theme_uses_defaults() {
+        let theme: Theme = serde_json::from_str(r##"{"tag": "#00ff00", "emoji": false}"##).unwrap();
+        assert_eq!(theme.tag, "#00ff00".parse().unwrap());
+        assert_eq!(theme.id, Theme::default().id);
+        assert_eq!(theme.overdue_marker(), "! ");
+    }
+}
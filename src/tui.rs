@@ -0,0 +1,655 @@
+use crate::config::{CompletionPolicy, HooksConfig};
+use crate::error::{Result, join_ids};
+use crate::hooks::run_hooks;
+use crate::renderer::{format_relative_date, style_due, style_priority};
+use crate::storage::TaskStorage;
+use crate::table::{task_status, truncate, wrap};
+use crate::task::Task;
+use crate::theme::theme;
+use colored::Colorize;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue, style::Print};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+/// Number of rows used by the detail pane, including its separator.
+const DETAIL_HEIGHT: usize = 8;
+/// Key help shown in the status line.
+const HELP: &str = "j/k move  x done  e edit  d delete  +/- 0-5 priority  / filter  c completed  enter details  q quit";
+
+/// Run the full-screen interactive task list until the user quits.
+///
+/// Every change is written to `data_path` immediately, and completing a task
+/// handles its open subtasks as `policy` says. Returns the storage with all
+/// changes applied.
+pub fn run_tui(
+    storage: TaskStorage,
+    data_path: &Path,
+    hooks: &HooksConfig,
+    policy: CompletionPolicy,
+) -> Result<TaskStorage> {
+    let mut app = App::new(storage, data_path.to_path_buf(), hooks.clone(), policy);
+    let _guard = TerminalGuard::enter()?;
+    let mut stdout = io::stdout();
+
+    loop {
+        let (width, height) = terminal::size()?;
+        draw(&mut stdout, &app, width as usize, height as usize)?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if !app.handle_key(key) {
+                break;
+            }
+        }
+    }
+
+    Ok(app.storage)
+}
+
+/// Restores the terminal when dropped, even if drawing fails.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What keystrokes currently do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Filter,
+    Edit(String),
+    ConfirmDelete,
+}
+
+/// State of the interactive session.
+struct App {
+    storage: TaskStorage,
+    data_path: PathBuf,
+    hooks: HooksConfig,
+    policy: CompletionPolicy,
+    /// IDs of the tasks shown, in display order.
+    visible: Vec<u64>,
+    selected: usize,
+    filter: String,
+    show_completed: bool,
+    show_details: bool,
+    mode: Mode,
+    message: Option<String>,
+}
+
+impl App {
+    fn new(
+        storage: TaskStorage,
+        data_path: PathBuf,
+        hooks: HooksConfig,
+        policy: CompletionPolicy,
+    ) -> Self {
+        let mut app = Self {
+            storage,
+            data_path,
+            hooks,
+            policy,
+            visible: Vec::new(),
+            selected: 0,
+            filter: String::new(),
+            show_completed: false,
+            show_details: true,
+            mode: Mode::Normal,
+            message: None,
+        };
+        app.refresh();
+        app
+    }
+
+    /// Recompute the visible tasks, keeping the selected task when possible.
+    fn refresh(&mut self) {
+        let selected_id = self.selected_id();
+        let terms: Vec<String> = self
+            .filter
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        let mut tasks: Vec<&Task> = self
+            .storage
+            .tasks
+            .iter()
+            .filter(|task| self.show_completed || !task.completed)
+            .filter(|task| terms.iter().all(|term| matches_term(task, term)))
+            .collect();
+        tasks.sort();
+        self.visible = tasks.iter().map(|task| task.id).collect();
+
+        self.selected = selected_id
+            .and_then(|id| self.visible.iter().position(|&v| v == id))
+            .unwrap_or(self.selected)
+            .min(self.visible.len().saturating_sub(1));
+    }
+
+    fn selected_id(&self) -> Option<u64> {
+        self.visible.get(self.selected).copied()
+    }
+
+    fn selected_task(&self) -> Option<&Task> {
+        self.selected_id().and_then(|id| self.storage.get_task(id))
+    }
+
+    fn save(&mut self, message: String) {
+        if let Err(e) = run_hooks(&self.hooks, &mut self.storage) {
+            // Go back to the saved tasks so the rejected change isn't kept.
+            if let Ok(storage) = TaskStorage::load_from_file(&self.data_path) {
+                self.storage = storage;
+            }
+            self.message = Some(e.to_string());
+            self.refresh();
+            return;
+        }
+        self.message = Some(match self.storage.save_to_file(&self.data_path) {
+            Ok(()) => message,
+            Err(e) => format!("Save failed: {}", e),
+        });
+        self.refresh();
+    }
+
+    /// Handle a key press. Returns false when the session should end.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match self.mode.clone() {
+            Mode::Normal => return self.handle_normal_key(key),
+            Mode::Filter => match key.code {
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Normal;
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => {}
+            },
+            Mode::Edit(mut buffer) => match key.code {
+                KeyCode::Enter => {
+                    self.mode = Mode::Normal;
+                    self.set_description(buffer.trim().to_string());
+                }
+                KeyCode::Esc => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                    self.mode = Mode::Edit(buffer);
+                }
+                KeyCode::Char(c) => {
+                    buffer.push(c);
+                    self.mode = Mode::Edit(buffer);
+                }
+                _ => {}
+            },
+            Mode::ConfirmDelete => {
+                self.mode = Mode::Normal;
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    self.delete_selected();
+                } else {
+                    self.message = Some("Delete cancelled".to_string());
+                }
+            }
+        }
+
+        self.refresh();
+        true
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) -> bool {
+        self.message = None;
+        let last = self.visible.len().saturating_sub(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('j') | KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('d') if ctrl => self.selected = (self.selected + 10).min(last),
+            KeyCode::Char('u') if ctrl => self.selected = self.selected.saturating_sub(10),
+            KeyCode::Char('g') | KeyCode::Home => self.selected = 0,
+            KeyCode::Char('G') | KeyCode::End => self.selected = last,
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('c') => {
+                self.show_completed = !self.show_completed;
+                self.refresh();
+            }
+            KeyCode::Enter | KeyCode::Tab => self.show_details = !self.show_details,
+            KeyCode::Char('x') | KeyCode::Char(' ') => self.complete_selected(),
+            KeyCode::Char('e') => {
+                if let Some(task) = self.selected_task() {
+                    self.mode = Mode::Edit(task.description.clone());
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete if self.selected_id().is_some() => {
+                self.mode = Mode::ConfirmDelete;
+            }
+            KeyCode::Char('+') => self.shift_priority(-1),
+            KeyCode::Char('-') => self.shift_priority(1),
+            KeyCode::Char(c @ '0'..='5') => {
+                let priority = c.to_digit(10).filter(|&p| p > 0).map(|p| p as u8);
+                self.set_priority(priority);
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn complete_selected(&mut self) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        match self.storage.complete_tasks(&[id], None, self.policy) {
+            Ok(completed) if !completed.already_completed.is_empty() => {
+                self.message = Some(format!("Task {} is already completed", id));
+            }
+            Ok(completed) => {
+                let mut message = format!("Task {} marked as complete", id);
+                if !completed.open_subtasks.is_empty() {
+                    message.push_str(&format!(
+                        "; left open subtasks: {}",
+                        join_ids(&completed.open_subtasks)
+                    ));
+                }
+                if !completed.cascaded.is_empty() {
+                    message.push_str(&format!(
+                        "; also completed subtasks: {}",
+                        join_ids(&completed.cascaded)
+                    ));
+                }
+                self.save(message);
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        match self.storage.delete_task(id) {
+            Ok(_) => self.save(format!("Task {} moved to the trash", id)),
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    fn set_description(&mut self, description: String) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        if description.is_empty() {
+            self.message = Some("Description cannot be empty".to_string());
+            return;
+        }
+        if let Some(task) = self.storage.get_task_mut(id) {
+            task.description = description;
+            self.save(format!("Task {} updated", id));
+        }
+    }
+
+    /// Move the priority up (negative) or down (positive) one level.
+    ///
+    /// Raising an unprioritized task gives it P5; lowering P5 clears it.
+    fn shift_priority(&mut self, delta: i8) {
+        let Some(task) = self.selected_task() else {
+            return;
+        };
+        let priority = match (task.priority, delta) {
+            (None, d) if d < 0 => Some(5),
+            (None, _) => None,
+            (Some(5), d) if d > 0 => None,
+            (Some(p), d) => Some((p as i8 + d).clamp(1, 5) as u8),
+        };
+        self.set_priority(priority);
+    }
+
+    fn set_priority(&mut self, priority: Option<u8>) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        if let Some(task) = self.storage.get_task_mut(id) {
+            task.priority = priority;
+            let label = priority.map_or("none".to_string(), |p| format!("P{}", p));
+            self.save(format!("Task {} priority set to {}", id, label));
+        }
+    }
+}
+
+/// Whether a lowercase filter term matches a task.
+///
+/// Terms starting with `#` match tags by prefix; others match the description.
+fn matches_term(task: &Task, term: &str) -> bool {
+    match term.strip_prefix('#') {
+        Some(tag) => task.tags.iter().any(|t| t.to_lowercase().starts_with(tag)),
+        None => task.description.to_lowercase().contains(term),
+    }
+}
+
+fn draw(out: &mut impl Write, app: &App, width: usize, height: usize) -> io::Result<()> {
+    let theme = theme();
+    let mut lines = Vec::with_capacity(height);
+
+    let mut title = format!(
+        " todo - {} of {} tasks",
+        app.visible.len(),
+        app.storage.tasks.len()
+    );
+    if !app.filter.is_empty() {
+        title.push_str(&format!("  filter: {}", app.filter));
+    }
+    if app.show_completed {
+        title.push_str("  (showing completed)");
+    }
+    lines.push(theme.header.apply(&truncate(&title, width)).to_string());
+
+    let details = if app.show_details {
+        app.selected_task().map(|task| detail_lines(task, width))
+    } else {
+        None
+    };
+    let detail_rows = details.as_ref().map_or(0, |_| DETAIL_HEIGHT);
+    let list_rows = height.saturating_sub(2 + detail_rows);
+
+    let scroll = (app.selected + 1).saturating_sub(list_rows);
+    for row in 0..list_rows {
+        let line = app
+            .visible
+            .get(scroll + row)
+            .and_then(|&id| app.storage.get_task(id))
+            .map(|task| task_line(task, width, scroll + row == app.selected))
+            .unwrap_or_default();
+        lines.push(line);
+    }
+    if app.visible.is_empty() && list_rows > 0 {
+        lines[1] = theme.muted.apply(" No tasks found.").to_string();
+    }
+
+    if let Some(details) = details {
+        lines.push(theme.muted.apply(&"─".repeat(width)).to_string());
+        lines.extend(details.into_iter().take(DETAIL_HEIGHT - 1));
+        while lines.len() < height.saturating_sub(1) {
+            lines.push(String::new());
+        }
+    }
+
+    let status = match &app.mode {
+        Mode::Filter => format!("/{}", app.filter),
+        Mode::Edit(buffer) => format!("Edit: {}", buffer),
+        Mode::ConfirmDelete => format!(
+            "Delete task {}? (y/n)",
+            app.selected_id().unwrap_or_default()
+        ),
+        Mode::Normal => app.message.clone().unwrap_or_else(|| HELP.to_string()),
+    };
+    lines.push(truncate(&status, width));
+
+    for (row, line) in lines.iter().take(height).enumerate() {
+        queue!(
+            out,
+            MoveTo(0, row as u16),
+            Clear(ClearType::CurrentLine),
+            Print(line)
+        )?;
+    }
+    out.flush()
+}
+
+/// One list row: status box, ID, priority, description, tags and due date.
+fn task_line(task: &Task, width: usize, selected: bool) -> String {
+    let theme = theme();
+    let status = match task_status(task) {
+        "done" => "[x]",
+        "overdue" => "[!]",
+        "started" => "[>]",
+        "waiting" => "[~]",
+        _ => "[ ]",
+    };
+    let id = format!("{:>4}", task.id);
+    let priority = task
+        .priority
+        .map_or("  ".to_string(), |p| format!("P{}", p));
+    let tags: String = task.tags.iter().map(|tag| format!(" #{}", tag)).collect();
+    let due = task
+        .due_date
+        .map(|date| format!(" (due {})", format_relative_date(date)))
+        .unwrap_or_default();
+
+    let prefix_width = status.width() + id.width() + priority.width() + 3;
+    let mut suffix_width = tags.width() + due.width();
+    let mut tags = tags;
+    if prefix_width + suffix_width + 10 > width {
+        tags.clear();
+        suffix_width = due.width();
+    }
+    let description = truncate(
+        &single_line(&task.description),
+        width.saturating_sub(prefix_width + suffix_width),
+    );
+
+    if selected {
+        let plain = format!(
+            "{} {} {} {}{}{}",
+            status, id, priority, description, tags, due
+        );
+        let padding = width.saturating_sub(plain.width());
+        return format!("{}{}", plain, " ".repeat(padding))
+            .reversed()
+            .to_string();
+    }
+
+    let description = if task.completed {
+        theme.completed.apply(&description)
+    } else if task.is_overdue() {
+        theme.overdue.apply(&description)
+    } else {
+        description.normal()
+    };
+    let status = match task_status(task) {
+        "done" => theme.done.apply(status),
+        "overdue" => theme.overdue.apply(status),
+        _ => status.normal(),
+    };
+    let priority = match task.priority {
+        Some(p) => style_priority(&priority, p),
+        None => priority.normal(),
+    };
+    let due = match task.due_date {
+        Some(date) => style_due(&due, date, task.is_overdue()),
+        None => due.normal(),
+    };
+
+    format!(
+        "{} {} {} {}{}{}",
+        status,
+        theme.id.apply(&id),
+        priority,
+        description,
+        theme.tag.apply(&tags),
+        due
+    )
+}
+
+/// Lines of the detail pane for the selected task.
+fn detail_lines(task: &Task, width: usize) -> Vec<String> {
+    let theme = theme();
+    let label = |name: &str| theme.muted.apply(&format!("{:>10} ", name)).to_string();
+    let value_width = width.saturating_sub(11);
+    let mut lines = Vec::new();
+
+    for (i, part) in wrap(&single_line(&task.description), value_width)
+        .into_iter()
+        .take(2)
+        .enumerate()
+    {
+        let name = if i == 0 { "Task:" } else { "" };
+        lines.push(format!("{}{}", label(name), part));
+    }
+    lines.push(format!(
+        "{}{} #{}",
+        label("Status:"),
+        task_status(task),
+        task.id
+    ));
+    if let Some(priority) = task.priority {
+        lines.push(format!(
+            "{}{}",
+            label("Priority:"),
+            style_priority(&format!("P{}", priority), priority)
+        ));
+    }
+    if let Some(due_date) = task.due_date {
+        lines.push(format!(
+            "{}{} ({})",
+            label("Due:"),
+            due_date.format("%Y-%m-%d %H:%M"),
+            format_relative_date(due_date)
+        ));
+    }
+    if !task.tags.is_empty() {
+        let tags = truncate(&task.tags.join(", "), value_width);
+        lines.push(format!("{}{}", label("Tags:"), theme.tag.apply(&tags)));
+    }
+    lines.push(format!(
+        "{}{}",
+        label("Created:"),
+        task.created_at.format("%Y-%m-%d %H:%M")
+    ));
+    if let Some(completed_at) = task.completed_at {
+        lines.push(format!(
+            "{}{}",
+            label("Completed:"),
+            completed_at.format("%Y-%m-%d %H:%M")
+        ));
+    }
+
+    lines
+}
+
+/// Replace control characters so a value can't break the screen layout.
+fn single_line(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    /// An app over three tasks, saving to `tasks.json` in the returned
+    /// directory.
+    fn test_app(name: &str) -> (App, TempDir) {
+        let dir = TempDir::new(name);
+        let mut storage = TaskStorage::default();
+        for (description, tag) in [
+            ("Buy milk", "home"),
+            ("Fix bug", "work"),
+            ("Write docs", "work"),
+        ] {
+            let mut task = Task::new(0, description.to_string());
+            task.tags = vec![tag.to_string()];
+            storage.add_task(task);
+        }
+        let app = App::new(
+            storage,
+            dir.join("tasks.json"),
+            HooksConfig::default(),
+            CompletionPolicy::default(),
+        );
+        (app, dir)
+    }
+
+    #[test]
+    fn test_navigation_is_clamped() {
+        let (mut app, _dir) = test_app("tui-navigation");
+        assert_eq!(app.selected, 0);
+        app.handle_key(key('k'));
+        assert_eq!(app.selected, 0);
+        for _ in 0..5 {
+            app.handle_key(key('j'));
+        }
+        assert_eq!(app.selected, 2);
+        app.handle_key(key('g'));
+        assert_eq!(app.selected, 0);
+        app.handle_key(key('G'));
+        assert_eq!(app.selected, 2);
+        assert!(!app.handle_key(key('q')));
+    }
+
+    #[test]
+    fn test_live_filter() {
+        let (mut app, _dir) = test_app("tui-filter");
+        app.handle_key(key('/'));
+        for c in "#wo".chars() {
+            app.handle_key(key(c));
+        }
+        assert_eq!(app.visible.len(), 2);
+        app.handle_key(key(' '));
+        app.handle_key(key('d'));
+        assert_eq!(app.visible, vec![3]);
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(app.visible.len(), 3);
+    }
+
+    #[test]
+    fn test_changes_are_saved() {
+        let (mut app, _dir) = test_app("tui-save");
+        app.handle_key(key('1'));
+        app.handle_key(key('-'));
+        app.handle_key(key('j'));
+        app.handle_key(key('x'));
+
+        let saved = TaskStorage::load_from_file(&app.data_path).unwrap();
+        assert_eq!(saved.get_task(1).unwrap().priority, Some(2));
+        assert!(saved.get_task(2).unwrap().completed);
+        assert_eq!(app.visible.len(), 2);
+    }
+
+    #[test]
+    fn test_complete_follows_subtask_policy() {
+        let (mut app, _dir) = test_app("tui-subtasks");
+        let parent = app.storage.get_task(1).unwrap().uuid;
+        app.storage.set_parent(2, Some(parent)).unwrap();
+
+        app.policy = CompletionPolicy::Refuse;
+        app.handle_key(key('x'));
+        assert!(!app.storage.get_task(1).unwrap().completed);
+        assert!(app.message.as_deref().unwrap().contains("open subtasks: 2"));
+
+        app.policy = CompletionPolicy::Cascade;
+        app.handle_key(key('x'));
+        let saved = TaskStorage::load_from_file(&app.data_path).unwrap();
+        assert!(saved.get_task(1).unwrap().completed);
+        assert!(saved.get_task(2).unwrap().completed);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Task 1 marked as complete; also completed subtasks: 2")
+        );
+    }
+}